@@ -0,0 +1,237 @@
+use anyhow::{Result, bail};
+use std::path::PathBuf;
+
+use crate::ipc::LabelField;
+
+/// What the process was asked to do on the command line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// No arguments: start the overlay as usual.
+    Run,
+    /// `export-theme <path>`: write the appearance section to a theme file.
+    ExportTheme(PathBuf),
+    /// `import-theme <path>`: merge a theme file into the current appearance.
+    ImportTheme(PathBuf),
+    /// `rehook`: ask the running instance to re-register its hotkeys.
+    Rehook,
+    /// `--autostart on|off|status`: change or report Run at login, then exit.
+    Autostart(AutostartAction),
+    /// `--dump`: print the current desktop and all labels as JSON, then exit.
+    Dump,
+    /// `--set-title <text>` / `--set-desc <text>`: change the current desktop's label.
+    SetLabel(LabelField, String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AutostartAction {
+    On,
+    Off,
+    Status,
+}
+
+pub const USAGE: &str = "Usage: mddsklbl [--portable] [--config <file>] [export-theme <file> | import-theme <file> | rehook | --autostart on|off|status | --dump | --set-title <text> | --set-desc <text>]";
+
+/// A parsed command line: the global options plus the command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Invocation {
+    /// `--config <path>`.
+    pub config: Option<PathBuf>,
+    /// `--portable`: keep config and logs next to the executable.
+    pub portable: bool,
+    pub command: Command,
+}
+
+/// Split the global `--portable` flag and `--config <path>` pair off the arguments, then
+/// parse the rest with [`parse_args`].
+pub fn parse_invocation<I, S>(args: I) -> Result<Invocation>
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    let mut args: Vec<String> = args.into_iter().map(Into::into).collect();
+    let before = args.len();
+    args.retain(|a| a != "--portable");
+    let portable = args.len() != before;
+    let mut config = None;
+    if let Some(i) = args.iter().position(|a| a == "--config") {
+        if i + 1 >= args.len() {
+            bail!("{}", USAGE);
+        }
+        config = Some(PathBuf::from(args.remove(i + 1)));
+        args.remove(i);
+    }
+    // Only one `--config` is allowed.
+    if args.iter().any(|a| a == "--config") {
+        bail!("{}", USAGE);
+    }
+    Ok(Invocation {
+        config,
+        portable,
+        command: parse_args(args)?,
+    })
+}
+
+/// Parse the process arguments (without the program name).
+pub fn parse_args<I, S>(args: I) -> Result<Command>
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    let args: Vec<String> = args.into_iter().map(Into::into).collect();
+    match args.as_slice() {
+        [] => Ok(Command::Run),
+        [cmd, path] if cmd == "export-theme" => Ok(Command::ExportTheme(PathBuf::from(path))),
+        [cmd, path] if cmd == "import-theme" => Ok(Command::ImportTheme(PathBuf::from(path))),
+        [cmd] if cmd == "rehook" => Ok(Command::Rehook),
+        [flag] if flag == "--dump" => Ok(Command::Dump),
+        [flag, text] if flag == "--set-title" => {
+            Ok(Command::SetLabel(LabelField::Title, text.clone()))
+        }
+        [flag, text] if flag == "--set-desc" => {
+            Ok(Command::SetLabel(LabelField::Description, text.clone()))
+        }
+        [flag, state] if flag == "--autostart" => match state.to_ascii_lowercase().as_str() {
+            "on" => Ok(Command::Autostart(AutostartAction::On)),
+            "off" => Ok(Command::Autostart(AutostartAction::Off)),
+            "status" => Ok(Command::Autostart(AutostartAction::Status)),
+            _ => bail!("{}", USAGE),
+        },
+        _ => bail!("{}", USAGE),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_config(config: Option<&str>, command: Command) -> Invocation {
+        Invocation {
+            config: config.map(PathBuf::from),
+            portable: false,
+            command,
+        }
+    }
+
+    #[test]
+    fn no_args_runs_ui() {
+        assert_eq!(parse_args(Vec::<String>::new()).unwrap(), Command::Run);
+    }
+
+    #[test]
+    fn parses_theme_commands() {
+        assert_eq!(
+            parse_args(["export-theme", "a.json"]).unwrap(),
+            Command::ExportTheme("a.json".into())
+        );
+        assert_eq!(
+            parse_args(["import-theme", "b.json"]).unwrap(),
+            Command::ImportTheme("b.json".into())
+        );
+    }
+
+    #[test]
+    fn parses_rehook() {
+        assert_eq!(parse_args(["rehook"]).unwrap(), Command::Rehook);
+        assert!(parse_args(["rehook", "extra"]).is_err());
+    }
+
+    #[test]
+    fn parses_autostart() {
+        assert_eq!(
+            parse_args(["--autostart", "on"]).unwrap(),
+            Command::Autostart(AutostartAction::On)
+        );
+        assert_eq!(
+            parse_args(["--autostart", "OFF"]).unwrap(),
+            Command::Autostart(AutostartAction::Off)
+        );
+        assert_eq!(
+            parse_args(["--autostart", "status"]).unwrap(),
+            Command::Autostart(AutostartAction::Status)
+        );
+        assert!(parse_args(["--autostart"]).is_err());
+        assert!(parse_args(["--autostart", "maybe"]).is_err());
+        assert!(parse_args(["--autostrat", "on"]).is_err());
+    }
+
+    #[test]
+    fn parses_config_override() {
+        assert_eq!(
+            parse_invocation(["--config", "c.json"]).unwrap(),
+            with_config(Some("c.json"), Command::Run)
+        );
+        assert_eq!(
+            parse_invocation(["--config", "c.json", "export-theme", "t.json"]).unwrap(),
+            with_config(Some("c.json"), Command::ExportTheme("t.json".into()))
+        );
+        assert_eq!(
+            parse_invocation(["rehook", "--config", "c.json"]).unwrap(),
+            with_config(Some("c.json"), Command::Rehook)
+        );
+        assert_eq!(
+            parse_invocation(["rehook"]).unwrap(),
+            with_config(None, Command::Rehook)
+        );
+        assert!(parse_invocation(["--config"]).is_err());
+        assert!(parse_invocation(["--config", "a", "--config", "b"]).is_err());
+    }
+
+    #[test]
+    fn parses_portable_anywhere() {
+        assert_eq!(
+            parse_invocation(["--portable"]).unwrap(),
+            Invocation {
+                config: None,
+                portable: true,
+                command: Command::Run,
+            }
+        );
+        assert_eq!(
+            parse_invocation(["--dump", "--portable", "--config", "c.json"]).unwrap(),
+            Invocation {
+                config: Some("c.json".into()),
+                portable: true,
+                command: Command::Dump,
+            }
+        );
+        assert!(!parse_invocation(["--dump"]).unwrap().portable);
+        assert!(parse_args(["--portable"]).is_err());
+    }
+
+    #[test]
+    fn parses_dump() {
+        assert_eq!(parse_args(["--dump"]).unwrap(), Command::Dump);
+        assert_eq!(
+            parse_invocation(["--config", "c.json", "--dump"]).unwrap(),
+            with_config(Some("c.json"), Command::Dump)
+        );
+        assert!(parse_args(["--dump", "extra"]).is_err());
+    }
+
+    #[test]
+    fn parses_set_label() {
+        assert_eq!(
+            parse_args(["--set-title", "Work"]).unwrap(),
+            Command::SetLabel(LabelField::Title, "Work".into())
+        );
+        assert_eq!(
+            parse_args(["--set-desc", ""]).unwrap(),
+            Command::SetLabel(LabelField::Description, String::new())
+        );
+        assert_eq!(
+            parse_invocation(["--config", "c.json", "--set-title", "Home"]).unwrap(),
+            with_config(
+                Some("c.json"),
+                Command::SetLabel(LabelField::Title, "Home".into())
+            )
+        );
+        assert!(parse_args(["--set-title"]).is_err());
+        assert!(parse_args(["--set-desc", "a", "b"]).is_err());
+    }
+
+    #[test]
+    fn rejects_missing_path_and_unknown_commands() {
+        assert!(parse_args(["export-theme"]).is_err());
+        assert!(parse_args(["frobnicate"]).is_err());
+    }
+}
@@ -1,33 +1,113 @@
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
 use directories::ProjectDirs;
-use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, fs, io::Write, path::PathBuf};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     pub desktops: HashMap<String, DesktopLabel>,
     pub hotkeys: Hotkeys,
     pub appearance: Appearance,
+    /// Hide the overlay and pause its timers while the display is off (lid closed, modern standby).
+    #[serde(default = "default_true")]
+    pub suspend_on_display_off: bool,
+    /// Small badge composited onto the tray icon for the current desktop.
+    #[serde(default)]
+    pub tray_badge: TrayBadge,
+    /// Stacking slot when several instances run; unset means auto-detect from running overlays.
+    #[serde(default)]
+    pub instance_slot: Option<u32>,
+    /// Pixels each instance slot is nudged down so stacked overlays stay legible.
+    #[serde(default = "default_instance_step")]
+    pub instance_step_px: i32,
+    /// Instead of pinning the overlay to all desktops, move it onto the current desktop on each switch.
+    #[serde(default)]
+    pub per_desktop_window: bool,
+    /// Where the edit dialogs appear.
+    #[serde(default)]
+    pub dialog_placement: DialogPlacement,
+    /// Shell command run (detached) after each desktop switch; gets MDDSK_INDEX/GUID/TITLE env vars.
+    #[serde(default)]
+    pub on_switch_command: Option<String>,
+    /// The switch command is killed if it runs longer than this.
+    #[serde(default = "default_on_switch_timeout")]
+    pub on_switch_timeout_ms: u64,
+    /// Render labels verbatim instead of trimming and collapsing whitespace runs.
+    #[serde(default)]
+    pub preserve_whitespace: bool,
+    /// Show the 1-based desktop number instead of "Desktop" for desktops without a title.
+    #[serde(default)]
+    pub auto_number_unlabeled: bool,
+    /// Lock labels against in-app edits; `save_atomic` refuses to write. Only removable by
+    /// editing the file directly, which the watcher still picks up.
+    #[serde(default)]
+    pub read_only: bool,
+    /// Count switches into each desktop in `usage.json` next to the config. Local only.
+    #[serde(default)]
+    pub track_usage: bool,
+    /// Log verbosity and how many daily log files to keep.
+    #[serde(default)]
+    pub logging: Logging,
     #[serde(default)]
     pub version: Option<u32>,
+    /// Top-level keys this build doesn't know (hand-added `_comment`s, settings from newer
+    /// builds), kept so saving doesn't drop them. Sorted, so saves write them in a stable order.
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct DesktopLabel {
     pub title: String,
     pub description: String,
+    /// Background color for this desktop's overlay (hex); unset uses `appearance.background_color`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    /// Never show the overlay on this desktop, whatever the global toggle says.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub hidden: bool,
+    /// Font size for this desktop's overlay; unset uses `appearance.font_size_dip`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub font_size_dip: Option<u32>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Hotkeys {
     pub edit_title: KeyChord,
     pub edit_description: KeyChord,
     pub toggle_overlay: KeyChord,
     #[serde(default = "default_snap_key")]
     pub snap_position: KeyChord,
+    /// Optional chord that flips run-at-login; unset by default.
+    #[serde(default)]
+    pub toggle_autorun: Option<KeyChord>,
+    /// Edit title and description together in one dialog.
+    #[serde(default = "default_edit_both_key")]
+    pub edit_both: KeyChord,
+    /// Optional chord that hides/shows the overlay on the current desktop only.
+    #[serde(default)]
+    pub toggle_desktop_hidden: Option<KeyChord>,
+    /// Optional chord that creates a new virtual desktop.
+    #[serde(default)]
+    pub new_desktop: Option<KeyChord>,
+    /// Optional chord that removes the current virtual desktop.
+    #[serde(default)]
+    pub remove_desktop: Option<KeyChord>,
+    /// Optional chord that hides the overlay outright until pressed again (not saved).
+    #[serde(default)]
+    pub panic_hide: Option<KeyChord>,
+    /// Optional chord that shows the overlay for a moment, whatever hides it otherwise.
+    #[serde(default)]
+    pub peek: Option<KeyChord>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct KeyChord {
     pub ctrl: bool,
     pub alt: bool,
@@ -39,9 +119,436 @@ pub struct KeyChord {
 pub struct Appearance {
     pub font_family: String,
     pub font_size_dip: u32,
+    /// 1–999 (400 = normal, 700 = bold); also accepts names like `"bold"` in the file.
+    #[serde(
+        default = "default_font_weight",
+        deserialize_with = "lenient_font_weight"
+    )]
+    pub font_weight: u16,
+    #[serde(default)]
+    pub font_italic: bool,
+    /// Fonts tried in order for characters `font_family` lacks (emoji, CJK), before the
+    /// system's own fallback.
+    #[serde(default = "default_fallback_fonts")]
+    pub fallback_fonts: Vec<String>,
+    /// Text drawn before the title (not the description), e.g. `"「 "` or an icon character;
+    /// empty adds nothing.
+    #[serde(default)]
+    pub label_prefix: String,
+    /// Text drawn after the title, e.g. `" 」"`; empty adds nothing.
+    #[serde(default)]
+    pub label_suffix: String,
+    /// Draw the description on its own row(s) under the title, in a smaller font, instead of
+    /// after it as `"title : description"`.
+    #[serde(default)]
+    pub stacked_description: bool,
+    /// Description font size relative to the title when `stacked_description` is on;
+    /// clamped to 0.3–1.0.
+    #[serde(default = "default_description_scale")]
+    pub description_scale: f32,
     pub margin_px: i32,
+    /// Horizontal nudge applied after anchoring (positive moves right), e.g. to clear a
+    /// centered webcam; the overlay is kept at least partly on its monitor.
+    #[serde(default)]
+    pub offset_x: i32,
+    /// Vertical nudge applied after anchoring (positive moves down).
+    #[serde(default)]
+    pub offset_y: i32,
+    /// Space between the text and the edge of its background box; unset uses `margin_px`.
+    #[serde(default)]
+    pub padding_px: Option<u32>,
+    /// Background corner radius; 0 gives square corners, unset scales with the font size.
+    #[serde(default)]
+    pub corner_radius_px: Option<u32>,
+    /// Draw the text once more underneath, offset down and right, in the shadow color so it
+    /// stays readable over light wallpapers.
+    #[serde(default)]
+    pub text_shadow: bool,
+    /// Shadow color (hex, alpha allowed); unset is translucent black.
+    #[serde(default)]
+    pub text_shadow_color: Option<String>,
+    /// Shadow offset in pixels; unset is 2.
+    #[serde(default)]
+    pub text_shadow_offset_px: Option<u32>,
     #[serde(default)]
     pub hide_on_fullscreen: bool,
+    /// Keep the overlay up over fullscreen and `hide_for_processes` apps (e.g. for screen
+    /// capture). The toggle, per-desktop `hidden`, High Contrast and the lock screen still apply.
+    #[serde(default)]
+    pub always_show: bool,
+    /// The overlay takes mouse input: right-click opens the tray menu, double-click edits the
+    /// title. Off keeps it fully click-through.
+    #[serde(default)]
+    pub overlay_interactive: bool,
+    /// Ask before quitting from the tray's Exit item.
+    #[serde(default)]
+    pub confirm_exit: bool,
+    /// Executable names (e.g. `obs64.exe`) that hide the overlay while in the foreground.
+    #[serde(default)]
+    pub hide_for_processes: Vec<String>,
+    /// Pick black or white text from what's on screen around the overlay, sampled once per
+    /// desktop after a switch.
+    #[serde(default)]
+    pub auto_contrast: bool,
+    /// Minimum WCAG contrast ratio auto-contrast must reach with plain text before it
+    /// falls back to drawing the background box.
+    #[serde(default = "default_min_contrast_ratio")]
+    pub min_contrast_ratio: f32,
+    /// Blur whatever is behind the overlay (Windows 10/11); ignored where unsupported.
+    #[serde(default)]
+    pub backdrop_blur: bool,
+    /// Where on the work area the overlay sits; cycled by the snap hotkey.
+    #[serde(default, deserialize_with = "lenient_anchor")]
+    pub anchor: OverlayAnchor,
+    /// Label color as `#RGB`, `#RRGGBB` or `#RRGGBBAA`; unset follows the theme.
+    #[serde(default)]
+    pub text_color: Option<String>,
+    /// Background box color; the alpha channel sets its translucency. Unset follows the theme.
+    #[serde(default)]
+    pub background_color: Option<String>,
+    /// Also rename the Windows desktop (Task View) when the title is edited in-app.
+    #[serde(default)]
+    pub sync_windows_desktop_name: bool,
+    /// Store labels under the desktop's 0-based position (`"0"`, `"1"`, …) instead of its
+    /// GUID, so they follow the position when desktops are reordered.
+    #[serde(default)]
+    pub key_by_index: bool,
+    /// Whole-overlay opacity: 0–255, or a fraction such as `0.7`. Defaults to opaque.
+    #[serde(default = "default_opacity", deserialize_with = "lenient_opacity")]
+    pub opacity: u8,
+    /// Show the overlay only for this long after a desktop switch; unset keeps it always on.
+    #[serde(default)]
+    pub auto_hide_ms: Option<u32>,
+    /// Fade the overlay in and out over this many milliseconds; 0 shows and hides instantly.
+    #[serde(default)]
+    pub fade_ms: u32,
+    /// How often to poll for desktop switches when desktop events are unavailable; 0 turns
+    /// the fallback poller off.
+    #[serde(default = "default_poll_interval")]
+    pub poll_interval_ms: u32,
+    /// How often fullscreen/foreground-app visibility is re-checked.
+    #[serde(default = "default_visibility_interval")]
+    pub visibility_interval_ms: u32,
+    /// Monitor to show the overlay on (0-based, in enumeration order); unset means primary.
+    #[serde(default)]
+    pub monitor_index: Option<usize>,
+    /// Also show a copy of the overlay on every other attached monitor.
+    #[serde(default)]
+    pub mirror_all_monitors: bool,
+    /// Longest title (and, unless `max_description_chars` is set, description) the edit
+    /// dialogs accept.
+    #[serde(default = "default_max_label_chars")]
+    pub max_label_chars: u32,
+    #[serde(default)]
+    pub max_description_chars: Option<u32>,
+    /// Show the edit hotkeys after the title.
+    #[serde(default = "default_true")]
+    pub show_hints: bool,
+    /// Follow the Windows light/dark theme for colors left at their defaults.
+    #[serde(default = "default_true")]
+    pub auto_theme: bool,
+    /// `.ico` file shown in the tray instead of the built-in icon; relative paths are taken
+    /// from the config folder. Unset (or unloadable) uses the built-in icon.
+    #[serde(default)]
+    pub tray_icon_path: Option<String>,
+}
+
+impl Default for Appearance {
+    fn default() -> Self {
+        Self {
+            font_family: "Segoe UI".into(),
+            font_size_dip: 16,
+            font_weight: default_font_weight(),
+            font_italic: false,
+            fallback_fonts: default_fallback_fonts(),
+            label_prefix: String::new(),
+            label_suffix: String::new(),
+            stacked_description: false,
+            description_scale: default_description_scale(),
+            margin_px: 8,
+            offset_x: 0,
+            offset_y: 0,
+            padding_px: None,
+            corner_radius_px: None,
+            text_shadow: false,
+            text_shadow_color: None,
+            text_shadow_offset_px: None,
+            hide_on_fullscreen: false,
+            always_show: false,
+            hide_for_processes: Vec::new(),
+            overlay_interactive: false,
+            confirm_exit: false,
+            auto_contrast: false,
+            min_contrast_ratio: default_min_contrast_ratio(),
+            backdrop_blur: false,
+            anchor: OverlayAnchor::TopCenter,
+            text_color: None,
+            background_color: None,
+            sync_windows_desktop_name: false,
+            key_by_index: false,
+            opacity: default_opacity(),
+            auto_hide_ms: None,
+            fade_ms: 0,
+            poll_interval_ms: default_poll_interval(),
+            visibility_interval_ms: default_visibility_interval(),
+            monitor_index: None,
+            mirror_all_monitors: false,
+            max_label_chars: default_max_label_chars(),
+            max_description_chars: None,
+            show_hints: true,
+            auto_theme: true,
+            tray_icon_path: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverlayAnchor {
+    #[default]
+    TopCenter,
+    TopLeft,
+    TopRight,
+    BottomCenter,
+    /// Flush against the taskbar, on whichever screen edge it sits.
+    Taskbar,
+}
+
+impl Appearance {
+    /// Edit caps in characters as (title, description).
+    pub fn label_limits(&self) -> (usize, usize) {
+        let title = self.max_label_chars as usize;
+        let description = self.max_description_chars.map_or(title, |n| n as usize);
+        (title, description)
+    }
+}
+
+impl OverlayAnchor {
+    /// The next position in the snap cycle, wrapping back to top-center.
+    pub fn next(self) -> Self {
+        match self {
+            Self::TopCenter => Self::TopLeft,
+            Self::TopLeft => Self::TopRight,
+            Self::TopRight => Self::BottomCenter,
+            Self::BottomCenter => Self::Taskbar,
+            Self::Taskbar => Self::TopCenter,
+        }
+    }
+}
+
+fn default_opacity() -> u8 {
+    255
+}
+
+/// Map a JSON opacity to 0–255: fractions in `0.0..=1.0` are scaled, integers are clamped.
+pub fn opacity_from_json(v: &serde_json::Value) -> Option<u8> {
+    if let Some(i) = v.as_i64() {
+        return Some(i.clamp(0, 255) as u8);
+    }
+    let f = v.as_f64()?;
+    let scaled = if (0.0..=1.0).contains(&f) {
+        f * 255.0
+    } else {
+        f
+    };
+    Some(scaled.round().clamp(0.0, 255.0) as u8)
+}
+
+fn lenient_opacity<'de, D: Deserializer<'de>>(d: D) -> std::result::Result<u8, D::Error> {
+    let v = serde_json::Value::deserialize(d)?;
+    Ok(opacity_from_json(&v).unwrap_or_else(|| {
+        tracing::warn!("Invalid appearance.opacity ({}), using 255", v);
+        default_opacity()
+    }))
+}
+
+fn default_font_weight() -> u16 {
+    400
+}
+
+fn default_fallback_fonts() -> Vec<String> {
+    vec!["Segoe UI Emoji".into(), "Segoe UI Symbol".into()]
+}
+
+fn default_poll_interval() -> u32 {
+    crate::core::DEFAULT_POLL_INTERVAL_MS
+}
+
+fn default_visibility_interval() -> u32 {
+    crate::core::DEFAULT_VISIBILITY_INTERVAL_MS
+}
+
+fn default_description_scale() -> f32 {
+    crate::core::DEFAULT_DESCRIPTION_SCALE
+}
+
+/// Weight names or numbers (JSON numbers or strings) via `core::parse_font_weight`; anything
+/// else falls back to normal.
+fn lenient_font_weight<'de, D: Deserializer<'de>>(d: D) -> std::result::Result<u16, D::Error> {
+    let v = serde_json::Value::deserialize(d)?;
+    let text = match &v {
+        serde_json::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    Ok(crate::core::parse_font_weight(&text).unwrap_or_else(|| {
+        tracing::warn!("Invalid appearance.font_weight ({}), using normal", v);
+        default_font_weight()
+    }))
+}
+
+/// Unknown or malformed anchors fall back to top-center instead of failing the whole config.
+fn lenient_anchor<'de, D: Deserializer<'de>>(d: D) -> std::result::Result<OverlayAnchor, D::Error> {
+    let v = serde_json::Value::deserialize(d)?;
+    Ok(serde_json::from_value(v).unwrap_or_else(|e| {
+        tracing::warn!("Invalid appearance.anchor ({}), using top_center", e);
+        OverlayAnchor::TopCenter
+    }))
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrayBadge {
+    /// Plain tray icon.
+    #[default]
+    None,
+    /// 1-based index of the current desktop.
+    Index,
+    /// First character of the current desktop's title (index when untitled).
+    FirstLetter,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DialogPlacement {
+    /// Centered on the work area of the monitor the overlay is on.
+    #[default]
+    Centered,
+    /// Just below and to the right of the mouse cursor.
+    NearCursor,
+    /// Just below the overlay label.
+    NearOverlay,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogLevel {
+    Off,
+    Error,
+    Warn,
+    #[default]
+    Info,
+    Debug,
+}
+
+impl LogLevel {
+    /// The `EnvFilter` directive for this level.
+    pub fn as_filter(self) -> &'static str {
+        match self {
+            LogLevel::Off => "off",
+            LogLevel::Error => "error",
+            LogLevel::Warn => "warn",
+            LogLevel::Info => "info",
+            LogLevel::Debug => "debug",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Logging {
+    /// Used unless `RUST_LOG` is set.
+    #[serde(default, deserialize_with = "lenient_log_level")]
+    pub level: LogLevel,
+    /// Daily log files kept in `log_dir`; older ones are deleted at startup. 0 keeps all.
+    #[serde(default = "default_max_log_files")]
+    pub max_files: usize,
+    /// Line format of the log file. Read once at startup.
+    #[serde(default, deserialize_with = "lenient_log_format")]
+    pub format: LogFormat,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogFormat {
+    /// Human-readable lines.
+    #[default]
+    Text,
+    /// One JSON object per line, for log tooling.
+    Json,
+}
+
+impl Default for Logging {
+    fn default() -> Self {
+        Self {
+            level: LogLevel::default(),
+            max_files: default_max_log_files(),
+            format: LogFormat::default(),
+        }
+    }
+}
+
+fn default_max_log_files() -> usize {
+    14
+}
+
+/// Unknown levels fall back to `info` instead of failing the whole config.
+fn lenient_log_level<'de, D: Deserializer<'de>>(d: D) -> std::result::Result<LogLevel, D::Error> {
+    let v = serde_json::Value::deserialize(d)?;
+    Ok(serde_json::from_value(v).unwrap_or_else(|e| {
+        tracing::warn!("Invalid logging.level ({}), using info", e);
+        LogLevel::Info
+    }))
+}
+
+/// Unknown formats fall back to `text` instead of failing the whole config.
+fn lenient_log_format<'de, D: Deserializer<'de>>(d: D) -> std::result::Result<LogFormat, D::Error> {
+    let v = serde_json::Value::deserialize(d)?;
+    Ok(serde_json::from_value(v).unwrap_or_else(|e| {
+        tracing::warn!("Invalid logging.format ({}), using text", e);
+        LogFormat::Text
+    }))
+}
+
+/// Base name of the daily rolling log; rotated files are `mddsklbl.log.<YYYY-MM-DD>`.
+pub const LOG_FILE_NAME: &str = "mddsklbl.log";
+
+/// Only the `logging` section of `cfg_file`, for setting up tracing before the full config
+/// is loaded. Defaults if the file is missing or unreadable.
+pub fn read_logging(cfg_file: &Path) -> Logging {
+    fs::read_to_string(cfg_file)
+        .ok()
+        .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok())
+        .and_then(|v| v.get("logging").cloned())
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+/// Delete the oldest rotated `mddsklbl.log.*` files in `log_dir` so at most `max_files`
+/// remain (0 keeps all). Returns how many were removed.
+pub fn prune_log_files(log_dir: &Path, max_files: usize) -> Result<usize> {
+    if max_files == 0 {
+        return Ok(0);
+    }
+    let prefix = format!("{}.", LOG_FILE_NAME);
+    let mut logs: Vec<PathBuf> = fs::read_dir(log_dir)?
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let name = path.file_name()?.to_str()?;
+            (name.starts_with(&prefix) && path.is_file()).then_some(path)
+        })
+        .collect();
+    // Date suffixes sort chronologically.
+    logs.sort();
+    let excess = logs.len().saturating_sub(max_files);
+    let mut removed = 0;
+    for old in &logs[..excess] {
+        match fs::remove_file(old) {
+            Ok(()) => removed += 1,
+            Err(e) => {
+                tracing::warn!(path = %old.display(), error = ?e, "failed to remove old log file")
+            }
+        }
+    }
+    Ok(removed)
 }
 
 #[derive(Debug, Clone)]
@@ -80,18 +587,54 @@ impl Default for Config {
                     shift: false,
                     key: "L".into(),
                 },
+                toggle_autorun: None,
+                edit_both: default_edit_both_key(),
+                toggle_desktop_hidden: None,
+                new_desktop: None,
+                remove_desktop: None,
+                panic_hide: None,
+                peek: None,
             },
-            appearance: Appearance {
-                font_family: "Segoe UI".into(),
-                font_size_dip: 16,
-                margin_px: 8,
-                hide_on_fullscreen: false,
-            },
+            appearance: Appearance::default(),
+            suspend_on_display_off: true,
+            tray_badge: TrayBadge::None,
+            instance_slot: None,
+            instance_step_px: default_instance_step(),
+            per_desktop_window: false,
+            dialog_placement: DialogPlacement::Centered,
+            on_switch_command: None,
+            on_switch_timeout_ms: default_on_switch_timeout(),
+            preserve_whitespace: false,
+            auto_number_unlabeled: false,
+            read_only: false,
+            track_usage: false,
+            logging: Logging::default(),
             version: None,
+            extra: BTreeMap::new(),
         }
     }
 }
 
+fn default_max_label_chars() -> u32 {
+    64
+}
+
+fn default_min_contrast_ratio() -> f32 {
+    4.5
+}
+
+fn default_instance_step() -> i32 {
+    40
+}
+
+fn default_on_switch_timeout() -> u64 {
+    10_000
+}
+
+fn default_true() -> bool {
+    true
+}
+
 fn default_snap_key() -> KeyChord {
     KeyChord {
         ctrl: true,
@@ -101,20 +644,285 @@ fn default_snap_key() -> KeyChord {
     }
 }
 
-pub fn project_paths() -> Result<Paths> {
-    let dirs = ProjectDirs::from("com", "Acme", "DesktopLabeler")
-        .context("Failed to determine project directories")?;
-    let cfg_dir = dirs.config_dir().to_path_buf();
-    let cfg_file = cfg_dir.join("labels.json");
-    let log_dir = dirs.data_local_dir().join("logs");
-    Ok(Paths {
+fn default_edit_both_key() -> KeyChord {
+    KeyChord {
+        ctrl: true,
+        alt: true,
+        shift: false,
+        key: "E".into(),
+    }
+}
+
+/// Environment variable naming a config file to use instead of the default `labels.json`.
+pub const CONFIG_ENV_VAR: &str = "MDDSKMGR_CONFIG";
+
+static CONFIG_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+
+/// File next to the executable that turns on portable mode, like `--portable`.
+pub const PORTABLE_MARKER: &str = "portable.marker";
+
+static PORTABLE: OnceLock<bool> = OnceLock::new();
+
+/// Keep config and logs next to the executable for the rest of the process (`--portable`).
+pub fn set_portable() {
+    let _ = PORTABLE.set(true);
+}
+
+/// `exe_dir` when portable mode is on, via the flag (`forced`) or a [`PORTABLE_MARKER`]
+/// file in that folder.
+pub fn portable_base(exe_dir: &Path, forced: bool) -> Option<PathBuf> {
+    (forced || exe_dir.join(PORTABLE_MARKER).is_file()).then(|| exe_dir.to_path_buf())
+}
+
+/// The executable's folder if this process runs in portable mode.
+fn portable_dir() -> Option<PathBuf> {
+    let exe = std::env::current_exe().ok()?;
+    portable_base(exe.parent()?, PORTABLE.get().copied().unwrap_or(false))
+}
+
+/// Use `path` as the config file for the rest of the process (`--config <path>`).
+/// Takes precedence over `MDDSKMGR_CONFIG`; only the first call has an effect.
+pub fn set_config_override(path: PathBuf) {
+    let _ = CONFIG_OVERRIDE.set(path);
+}
+
+/// The `--config` path if one was given, else `MDDSKMGR_CONFIG` if set and non-empty and
+/// `use_env` (portable mode ignores the variable).
+fn config_override(use_env: bool) -> Option<PathBuf> {
+    CONFIG_OVERRIDE.get().cloned().or_else(|| {
+        std::env::var_os(CONFIG_ENV_VAR)
+            .filter(|v| use_env && !v.is_empty())
+            .map(PathBuf::from)
+    })
+}
+
+/// Point `defaults` at `cfg_file` instead, with `cfg_dir` as its parent; `log_dir` is
+/// kept. Falls back to `defaults` (logged) if that parent can't be created.
+pub fn with_config_file(defaults: Paths, cfg_file: &Path) -> Paths {
+    let cfg_file = std::path::absolute(cfg_file).unwrap_or_else(|_| cfg_file.to_path_buf());
+    let Some(cfg_dir) = cfg_file.parent().map(Path::to_path_buf) else {
+        tracing::warn!(path = %cfg_file.display(), "config override has no parent directory; using the default");
+        return defaults;
+    };
+    if let Err(e) = fs::create_dir_all(&cfg_dir) {
+        tracing::warn!(path = %cfg_file.display(), error = ?e, "cannot create config override directory; using the default");
+        return defaults;
+    }
+    Paths {
         cfg_file,
         cfg_dir,
-        log_dir,
+        log_dir: defaults.log_dir,
+    }
+}
+
+/// Default locations (next to the executable in portable mode), redirected to the
+/// `--config`/`MDDSKMGR_CONFIG` file when one is set.
+pub fn project_paths() -> Result<Paths> {
+    let portable = portable_dir();
+    let defaults = match &portable {
+        Some(dir) => paths_in(dir),
+        None => default_paths()?,
+    };
+    Ok(match config_override(portable.is_none()) {
+        Some(cfg_file) => with_config_file(defaults, &cfg_file),
+        None => defaults,
+    })
+}
+
+fn default_paths() -> Result<Paths> {
+    if let Some(dirs) = ProjectDirs::from("com", "Acme", "DesktopLabeler") {
+        let cfg_dir = dirs.config_dir().to_path_buf();
+        let cfg_file = cfg_dir.join("labels.json");
+        let log_dir = dirs.data_local_dir().join("logs");
+        return Ok(Paths {
+            cfg_file,
+            cfg_dir,
+            log_dir,
+        });
+    }
+    // Stripped environments may have no resolvable profile folders; keep working from a
+    // well-known location instead of failing startup.
+    let base = first_usable_dir(fallback_base_dirs())
+        .context("Failed to determine project directories")?;
+    tracing::warn!(dir=%base.display(), "ProjectDirs unavailable; using fallback directory");
+    Ok(paths_in(&base))
+}
+
+/// Config and log locations rooted at a single directory (`labels.json`, `logs/`).
+pub fn paths_in(base: &Path) -> Paths {
+    Paths {
+        cfg_file: base.join("labels.json"),
+        cfg_dir: base.to_path_buf(),
+        log_dir: base.join("logs"),
+    }
+}
+
+/// Candidate fallback folders in order: `%LOCALAPPDATA%\mddskmgr`, the directory
+/// containing the executable, then `%TEMP%\mddskmgr`.
+fn fallback_base_dirs() -> Vec<PathBuf> {
+    let local = std::env::var_os("LOCALAPPDATA")
+        .filter(|v| !v.is_empty())
+        .map(|v| PathBuf::from(v).join("mddskmgr"));
+    let exe_dir = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(Path::to_path_buf));
+    let temp = Some(std::env::temp_dir().join("mddskmgr"));
+    [local, exe_dir, temp].into_iter().flatten().collect()
+}
+
+/// The first of `candidates` that can be created and written to, so a read-only install
+/// folder (e.g. under Program Files) is skipped instead of leaving the app unable to save.
+pub fn first_usable_dir(candidates: impl IntoIterator<Item = PathBuf>) -> Option<PathBuf> {
+    candidates.into_iter().find(|dir| {
+        let probe = dir.join(".mddskmgr-write-test");
+        let usable = fs::create_dir_all(dir).and_then(|()| fs::write(&probe, b""));
+        let _ = fs::remove_file(&probe);
+        match usable {
+            Ok(()) => true,
+            Err(e) => {
+                tracing::warn!(dir = %dir.display(), error = ?e, "fallback directory unusable");
+                false
+            }
+        }
     })
 }
 
+/// How many `labels.json.bak-*` copies of unparseable configs are kept.
+pub const MAX_CONFIG_BACKUPS: usize = 5;
+
+/// Copy `cfg_file` to `<name>.bak-<unix millis>` beside it, then delete the oldest backups
+/// so at most `keep` remain. Returns the new backup's path.
+pub fn backup_config(cfg_file: &Path, keep: usize) -> Result<PathBuf> {
+    let dir = cfg_file
+        .parent()
+        .context("config file has no parent directory")?;
+    let name = cfg_file
+        .file_name()
+        .context("config file has no name")?
+        .to_string_lossy()
+        .into_owned();
+    let prefix = format!("{}.bak-", name);
+    let list_backups = || -> Result<Vec<(u128, PathBuf)>> {
+        let mut backups: Vec<(u128, PathBuf)> = fs::read_dir(dir)?
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                let stamp = path
+                    .file_name()?
+                    .to_str()?
+                    .strip_prefix(&prefix)?
+                    .parse()
+                    .ok()?;
+                Some((stamp, path))
+            })
+            .collect();
+        backups.sort();
+        Ok(backups)
+    };
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    // Always sort after the existing backups, even within the same millisecond.
+    let newest = list_backups()?.last().map(|(stamp, _)| *stamp);
+    let stamp = newest.map_or(now, |n| now.max(n + 1));
+    let bak = dir.join(format!("{}{}", prefix, stamp));
+    fs::copy(cfg_file, &bak).with_context(|| format!("copy to {}", bak.display()))?;
+
+    let backups = list_backups()?;
+    let excess = backups.len().saturating_sub(keep);
+    for (_, old) in &backups[..excess] {
+        if let Err(e) = fs::remove_file(old) {
+            tracing::warn!(path = %old.display(), error = ?e, "failed to remove old config backup");
+        }
+    }
+    Ok(bak)
+}
+
+/// One schema upgrade; `MIGRATIONS[n]` turns a version-`n` config into version `n + 1`.
+type Migration = fn(&mut Config);
+
+/// Ordered upgrade steps. Append new steps; never reorder or remove one.
+const MIGRATIONS: &[Migration] = &[
+    // 0 -> 1: the snap hotkey moved from Ctrl+Alt+S to Ctrl+Alt+L.
+    |cfg| {
+        if cfg.hotkeys.snap_position.key.eq_ignore_ascii_case("S") {
+            cfg.hotkeys.snap_position.key = "L".into();
+        }
+    },
+    // 1 -> 2: unset colors follow the theme. Older builds always wrote the defaults out, so
+    // those are dropped rather than read as the user's choice.
+    |cfg| {
+        let ap = &mut cfg.appearance;
+        for (color, old_default) in [
+            (&mut ap.text_color, "#FFFFFF"),
+            (&mut ap.background_color, "#00000059"),
+        ] {
+            if color
+                .as_deref()
+                .is_some_and(|c| c.trim().eq_ignore_ascii_case(old_default))
+            {
+                *color = None;
+            }
+        }
+    },
+];
+
+/// Schema version written by this build (a missing `version` counts as 0).
+pub const CURRENT_VERSION: u32 = MIGRATIONS.len() as u32;
+
+/// Whether `cfg` was written by a build with a newer schema than [`CURRENT_VERSION`]. It is used
+/// as far as this build understands it, but [`save_atomic`] refuses to write it back, since
+/// that would drop the settings this build doesn't know.
+pub fn is_from_newer_version(cfg: &Config) -> bool {
+    cfg.version.is_some_and(|v| v > CURRENT_VERSION)
+}
+
+/// Apply every migration step newer than `cfg.version`, bumping it after each one.
+/// Returns whether anything ran, i.e. whether the config needs saving.
+pub fn migrate(cfg: &mut Config) -> bool {
+    let mut changed = false;
+    while let Some(step) = MIGRATIONS.get(cfg.version.unwrap_or(0) as usize) {
+        let from = cfg.version.unwrap_or(0);
+        step(cfg);
+        cfg.version = Some(from + 1);
+        tracing::info!(from, to = from + 1, "migrated config");
+        changed = true;
+    }
+    changed
+}
+
+/// Back up `paths.cfg_file` (if it exists) and overwrite it with `Config::default()`.
+/// Returns the backup's path; nothing is written if the backup fails.
+pub fn reset_to_defaults(paths: &Paths) -> Result<Option<PathBuf>> {
+    let backup = if paths.cfg_file.exists() {
+        Some(backup_config(&paths.cfg_file, MAX_CONFIG_BACKUPS).context("back up config")?)
+    } else {
+        None
+    };
+    save_atomic(&Config::default(), paths)?;
+    Ok(backup)
+}
+
+/// How [`load_at`] came by the config it returned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadOutcome {
+    /// Read from the existing config file.
+    Existing,
+    /// The file could not be parsed; it was backed up and defaults are used.
+    Recovered,
+    /// Imported from the previous app name's config folder.
+    Migrated,
+    /// No config anywhere: defaults were written, so the next start is not a first run.
+    FirstRun,
+}
+
 pub fn load_or_default() -> Result<(Config, Paths)> {
+    let (cfg, paths, _) = load_with_outcome()?;
+    Ok((cfg, paths))
+}
+
+/// Like [`load_or_default`], also reporting whether this is the first run.
+pub fn load_with_outcome() -> Result<(Config, Paths, LoadOutcome)> {
     let paths = project_paths()?;
     if let Err(e) = fs::create_dir_all(&paths.cfg_dir) {
         tracing::warn!("Failed to create config directory: {}", e);
@@ -122,64 +930,414 @@ pub fn load_or_default() -> Result<(Config, Paths)> {
     if let Err(e) = fs::create_dir_all(&paths.log_dir) {
         tracing::warn!("Failed to create log directory: {}", e);
     }
+    // Migrate from old app name if present
+    let legacy = ProjectDirs::from("com", "Acme", "DesktopOverlay")
+        .map(|old_dirs| old_dirs.config_dir().join("labels.json"));
+    let (cfg, outcome) = load_at(&paths, legacy.as_deref())?;
+    Ok((cfg, paths, outcome))
+}
+
+/// Read the config without writing anything, for reloads and read-only commands. A missing
+/// file gives the defaults and migrations only apply in memory. An unreadable or unparseable
+/// file is an error, so a caller keeps the config it already has.
+pub fn load_without_writing() -> Result<(Config, Paths)> {
+    let paths = project_paths()?;
+    let cfg = read_existing(&paths)?;
+    Ok((cfg, paths))
+}
+
+/// The config at `paths` as [`load_without_writing`] reads it.
+pub fn read_existing(paths: &Paths) -> Result<Config> {
     let mut cfg = match fs::read_to_string(&paths.cfg_file) {
+        Ok(s) => serde_json::from_str(&s)
+            .with_context(|| format!("parse config {}", paths.cfg_file.display()))?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Config::default(),
+        Err(e) => {
+            return Err(e).with_context(|| format!("read config {}", paths.cfg_file.display()));
+        }
+    };
+    migrate(&mut cfg);
+    Ok(cfg)
+}
+
+/// Load the config at `paths`, falling back to `legacy_file` and then to defaults. Only a
+/// missing file counts as a first run; any other read error (e.g. an editor holding the file
+/// mid-save) is returned rather than overwriting the file with defaults.
+pub fn load_at(paths: &Paths, legacy_file: Option<&Path>) -> Result<(Config, LoadOutcome)> {
+    let (mut cfg, outcome) = match fs::read_to_string(&paths.cfg_file) {
         Ok(s) => match serde_json::from_str(&s) {
-            Ok(cfg) => cfg,
+            Ok(cfg) => (cfg, LoadOutcome::Existing),
             Err(e) => {
                 tracing::warn!("Failed to parse config JSON: {}, using defaults", e);
-                Config::default()
+                // Keep the broken file: the next save would otherwise overwrite it.
+                match backup_config(&paths.cfg_file, MAX_CONFIG_BACKUPS) {
+                    Ok(bak) => {
+                        tracing::warn!(backup = %bak.display(), "backed up unparseable config")
+                    }
+                    Err(e) => tracing::warn!(error = ?e, "failed to back up unparseable config"),
+                }
+                (Config::default(), LoadOutcome::Recovered)
             }
         },
-        Err(_) => {
-            // Migrate from old app name if present
-            if let Some(old_dirs) = ProjectDirs::from("com", "Acme", "DesktopOverlay") {
-                let old_file = old_dirs.config_dir().join("labels.json");
-                if let Ok(s) = fs::read_to_string(&old_file) {
-                    match serde_json::from_str(&s) {
-                        Ok(parsed) => {
-                            // Save to new location
-                            let _ = save_atomic(&parsed, &paths);
-                            parsed
-                        }
-                        Err(e) => {
-                            tracing::warn!(
-                                "Failed to parse migration config JSON: {}, using defaults",
-                                e
-                            );
-                            Config::default()
-                        }
-                    }
-                } else {
-                    Config::default()
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            return Err(e).with_context(|| format!("read config {}", paths.cfg_file.display()));
+        }
+        Err(_) => match legacy_file.map(fs::read_to_string) {
+            Some(Ok(s)) => match serde_json::from_str(&s) {
+                Ok(parsed) => {
+                    // Save to new location
+                    let _ = save_atomic(&parsed, paths);
+                    (parsed, LoadOutcome::Migrated)
                 }
+                Err(e) => {
+                    tracing::warn!(
+                        "Failed to parse migration config JSON: {}, using defaults",
+                        e
+                    );
+                    (Config::default(), LoadOutcome::Recovered)
+                }
+            },
+            _ => {
+                // Write the defaults now so only this start counts as the first run.
+                let cfg = Config::default();
+                if let Err(e) = save_atomic(&cfg, paths) {
+                    tracing::warn!(error = ?e, "failed to save default config");
+                }
+                (cfg, LoadOutcome::FirstRun)
+            }
+        },
+    };
+
+    if is_from_newer_version(&cfg) {
+        tracing::warn!(
+            version = ?cfg.version,
+            supported = CURRENT_VERSION,
+            "config was written by a newer version; some settings may be ignored"
+        );
+    }
+    if !migrate(&mut cfg) {
+        return Ok((cfg, outcome));
+    }
+    if let Err(e) = save_atomic(&cfg, paths) {
+        tracing::warn!(error = ?e, "failed to save migrated config");
+    }
+    Ok((cfg, outcome))
+}
+
+/// Whether a file-system event touching `paths` concerns the config file itself.
+///
+/// Compares file names so delete-and-recreate and rename-over saves in the config directory
+/// still count; the `.json.tmp` used by `save_atomic` does not.
+pub fn is_config_event(paths: &[PathBuf], cfg_file: &Path) -> bool {
+    let Some(name) = cfg_file.file_name() else {
+        return false;
+    };
+    paths.iter().any(|p| p.file_name() == Some(name))
+}
+
+/// Drop labels for desktops not in `live_guids`; returns whether anything was removed.
+///
+/// An empty live list is treated as "unknown" and never prunes.
+pub fn prune_desktops(cfg: &mut Config, live_guids: &[String]) -> bool {
+    if live_guids.is_empty() {
+        return false;
+    }
+    let before = cfg.desktops.len();
+    cfg.desktops.retain(|guid, _| live_guids.contains(guid));
+    cfg.desktops.len() != before
+}
+
+/// The file `appearance.tray_icon_path` names, relative paths resolved against `cfg_dir`;
+/// `None` when unset or blank.
+pub fn tray_icon_file(cfg: &Config, cfg_dir: &Path) -> Option<PathBuf> {
+    let raw = cfg.appearance.tray_icon_path.as_deref()?.trim();
+    (!raw.is_empty()).then(|| cfg_dir.join(raw))
+}
+
+/// Key of `guid`'s entry in `cfg.desktops`: the GUID, or with `appearance.key_by_index` its
+/// position in `live_guids` (falling back to the GUID when it isn't listed).
+pub fn label_key(cfg: &Config, guid: &str, live_guids: &[String]) -> String {
+    live_guids
+        .iter()
+        .position(|g| g == guid)
+        .filter(|_| cfg.appearance.key_by_index)
+        .map_or_else(|| guid.to_string(), |i| i.to_string())
+}
+
+/// `guid`'s label entry, created empty if missing, under the key [`label_key`] picks.
+pub fn label_entry<'a>(
+    cfg: &'a mut Config,
+    guid: &str,
+    live_guids: &[String],
+) -> &'a mut DesktopLabel {
+    let key = label_key(cfg, guid, live_guids);
+    cfg.desktops.entry(key).or_default()
+}
+
+/// Move label entries to the keys `appearance.key_by_index` calls for: GUID keys of live
+/// desktops become their index, or index keys become the GUID at that position. Keys that
+/// don't map, or whose new key is already taken, are left alone. Returns whether anything
+/// moved. Running it again is a no-op.
+pub fn rekey_desktops(cfg: &mut Config, live_guids: &[String]) -> bool {
+    let by_index = cfg.appearance.key_by_index;
+    let moves: Vec<(String, String)> = live_guids
+        .iter()
+        .enumerate()
+        .map(|(i, guid)| {
+            if by_index {
+                (guid.clone(), i.to_string())
             } else {
-                Config::default()
+                (i.to_string(), guid.clone())
             }
+        })
+        .filter(|(from, to)| cfg.desktops.contains_key(from) && !cfg.desktops.contains_key(to))
+        .collect();
+    for (from, to) in &moves {
+        if let Some(label) = cfg.desktops.remove(from) {
+            cfg.desktops.insert(to.clone(), label);
         }
-    };
+    }
+    !moves.is_empty()
+}
 
-    // Migration: Change snap_position hotkey from "S" to "L" for version 0 or None
-    if cfg.version.is_none() || cfg.version == Some(0) {
-        if cfg.hotkeys.snap_position.key.eq_ignore_ascii_case("S") {
-            cfg.hotkeys.snap_position.key = "L".into();
+/// With `key_by_index`: the desktop at `index` was removed, so drop its label and shift the
+/// labels after it down one, keeping each with its desktop. Returns whether anything changed.
+pub fn remove_index_label(cfg: &mut Config, index: usize) -> bool {
+    let mut changed = cfg.desktops.remove(&index.to_string()).is_some();
+    let mut later: Vec<usize> = cfg
+        .desktops
+        .keys()
+        .filter_map(|k| k.parse::<usize>().ok())
+        .filter(|&i| i > index)
+        .collect();
+    later.sort_unstable();
+    for i in later {
+        if let Some(label) = cfg.desktops.remove(&i.to_string()) {
+            cfg.desktops.insert((i - 1).to_string(), label);
+            changed = true;
         }
-        cfg.version = Some(1);
-        let _ = save_atomic(&cfg, &paths);
     }
+    changed
+}
 
-    Ok((cfg, paths))
+/// A title as stored when edited in-app: trimmed, with whitespace runs (tabs and newlines
+/// included) collapsed to one space. All-whitespace input yields `""`, leaving the title
+/// unset so the fallback shows.
+pub fn normalize_label(s: &str) -> String {
+    crate::core::collapse_whitespace(s)
+}
+
+/// One row of the Manage Labels window.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LabelEdit {
+    pub guid: String,
+    pub title: String,
+    pub description: String,
+    /// Delete this desktop's label entry.
+    pub remove: bool,
+}
+
+/// Apply rows from the Manage Labels window; returns whether anything changed.
+///
+/// Rows matching the config are skipped, so listing an unlabeled desktop doesn't create an
+/// empty entry for it. Other label fields (e.g. `color`) are kept.
+pub fn apply_label_edits(cfg: &mut Config, edits: &[LabelEdit]) -> bool {
+    let mut changed = false;
+    for edit in edits {
+        if edit.remove {
+            changed |= cfg.desktops.remove(&edit.guid).is_some();
+            continue;
+        }
+        let unchanged = match cfg.desktops.get(&edit.guid) {
+            Some(label) => label.title == edit.title && label.description == edit.description,
+            None => edit.title.is_empty() && edit.description.is_empty(),
+        };
+        if unchanged {
+            continue;
+        }
+        let entry = cfg.desktops.entry(edit.guid.clone()).or_default();
+        entry.title = edit.title.clone();
+        entry.description = edit.description.clone();
+        changed = true;
+    }
+    changed
 }
 
 pub fn save_atomic(cfg: &Config, paths: &Paths) -> Result<()> {
+    if cfg.read_only {
+        bail!(
+            "config is read-only; edit {} directly to change it",
+            paths.cfg_file.display()
+        );
+    }
+    if is_from_newer_version(cfg) {
+        bail!(
+            "config was written by a newer version (schema {}); not overwriting it",
+            cfg.version.unwrap_or_default()
+        );
+    }
     fs::create_dir_all(&paths.cfg_dir).ok();
-    let tmp = paths.cfg_file.with_extension("json.tmp");
     let data = serde_json::to_vec_pretty(cfg)?;
+    write_atomic(&paths.cfg_file, &data).context("save cfg")
+}
+
+/// Replace `path` with `data` so a crash leaves either the old or the new contents: write a
+/// flushed `<path>.tmp` beside it, then [`replace_file`] it over `path`.
+pub fn write_atomic(path: &Path, data: &[u8]) -> Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    write_synced(&tmp, data).context("write temp file")?;
+    // Best-effort atomic replace.
+    if let Err(e) = replace_file(&tmp, path) {
+        // E.g. the config folder links onto another volume: overwrite in place instead.
+        tracing::warn!(
+            error = %e,
+            path = %path.display(),
+            "atomic rename failed; overwriting the file directly"
+        );
+        write_synced(path, data).context("overwrite file")?;
+        let _ = fs::remove_file(&tmp);
+    }
+    Ok(())
+}
+
+/// Create (or truncate) `path`, write `data` and flush it to disk.
+fn write_synced(path: &Path, data: &[u8]) -> Result<()> {
+    let mut f = fs::File::create(path)?;
+    f.write_all(data)?;
+    f.sync_all().ok();
+    Ok(())
+}
+
+/// Move `tmp` over `dst` so the change survives a crash. On Windows, `MoveFileExW` with
+/// write-through only returns once the rename is on disk (a directory can't be flushed
+/// there); elsewhere the directory is flushed after the rename.
+fn replace_file(tmp: &Path, dst: &Path) -> std::io::Result<()> {
+    #[cfg(windows)]
     {
-        let mut f = fs::File::create(&tmp).context("create temp cfg")?;
-        f.write_all(&data).context("write temp cfg")?;
-        f.sync_all().ok();
+        use windows::Win32::Storage::FileSystem::{
+            MOVEFILE_REPLACE_EXISTING, MOVEFILE_WRITE_THROUGH, MoveFileExW,
+        };
+        use windows::core::HSTRING;
+        unsafe {
+            MoveFileExW(
+                &HSTRING::from(tmp.as_os_str()),
+                &HSTRING::from(dst.as_os_str()),
+                MOVEFILE_REPLACE_EXISTING | MOVEFILE_WRITE_THROUGH,
+            )
+        }
+        .map_err(std::io::Error::from)
     }
-    // Best-effort atomic replace.
-    fs::rename(&tmp, &paths.cfg_file).context("rename temp to final")?;
+    #[cfg(not(windows))]
+    {
+        fs::rename(tmp, dst)?;
+        if let Some(dir) = dst.parent() {
+            sync_dir(dir);
+        }
+        Ok(())
+    }
+}
+
+/// Flush `dir`'s entries so a rename into it survives a crash. Best effort: some file systems
+/// can't flush a directory, and there the rename is as durable as it gets.
+#[cfg(not(windows))]
+fn sync_dir(dir: &Path) {
+    if let Err(e) = fs::File::open(dir).and_then(|d| d.sync_all()) {
+        tracing::debug!(error = %e, dir = %dir.display(), "directory sync skipped");
+    }
+}
+
+/// On-disk shape of a theme file: just the cosmetic settings, never desktop labels.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThemeFile {
+    pub appearance: Appearance,
+}
+
+/// `appearance` keys that aren't cosmetic (label keying, the user's app list, local file
+/// paths): left out of exported themes and skipped on import.
+const NON_THEME_KEYS: &[&str] = &["key_by_index", "hide_for_processes", "tray_icon_path"];
+
+/// Write only the `appearance` section of `cfg` to `path` so it can be shared
+/// without leaking per-desktop labels. Settings in [`NON_THEME_KEYS`] are left out.
+pub fn export_theme(cfg: &Config, path: &Path) -> Result<()> {
+    let mut appearance = match serde_json::to_value(&cfg.appearance)? {
+        serde_json::Value::Object(map) => map,
+        _ => unreachable!("Appearance serializes to an object"),
+    };
+    appearance.retain(|k, _| !NON_THEME_KEYS.contains(&k.as_str()));
+    let theme = serde_json::json!({ "appearance": appearance });
+    let data = serde_json::to_vec_pretty(&theme)?;
+    fs::write(path, data).with_context(|| format!("write theme {}", path.display()))?;
     Ok(())
 }
+
+/// Write the whole config (labels, hotkeys, appearance) to `path` for moving to another machine.
+pub fn export_config(cfg: &Config, path: &Path) -> Result<()> {
+    let data = serde_json::to_vec_pretty(cfg)?;
+    fs::write(path, data).with_context(|| format!("write config {}", path.display()))?;
+    Ok(())
+}
+
+/// Read and validate a config exported with `export_config` (or any `labels.json`).
+pub fn read_config_file(path: &Path) -> Result<Config> {
+    let s = fs::read_to_string(path).with_context(|| format!("read config {}", path.display()))?;
+    serde_json::from_str(&s).context("not a valid labels config")
+}
+
+/// What importing a config does with the labels already present.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportMode {
+    /// Imported labels win for the same desktop; other desktops keep theirs.
+    Merge,
+    /// The imported labels replace all current ones.
+    Replace,
+}
+
+/// Take the desktop labels from `imported` into `cfg`; other settings stay as they are.
+pub fn import_desktops(cfg: &mut Config, imported: Config, mode: ImportMode) {
+    match mode {
+        ImportMode::Merge => cfg.desktops.extend(imported.desktops),
+        ImportMode::Replace => cfg.desktops = imported.desktops,
+    }
+}
+
+/// Merge the appearance settings from the theme file at `path` into `cfg`.
+///
+/// Keys present in the theme override the current values; missing keys keep
+/// the current values. `desktops`, `hotkeys` and the [`NON_THEME_KEYS`] settings
+/// are never touched. Returns the names of unknown keys that were ignored so
+/// callers can warn about them.
+pub fn import_theme(cfg: &mut Config, path: &Path) -> Result<Vec<String>> {
+    let s = fs::read_to_string(path).with_context(|| format!("read theme {}", path.display()))?;
+    let root: serde_json::Value = serde_json::from_str(&s).context("parse theme JSON")?;
+    // Accept both the exported `{ "appearance": { .. } }` wrapper and a bare appearance object.
+    let incoming = match root.get("appearance") {
+        Some(v) => v.clone(),
+        None => root,
+    };
+    let incoming = match incoming {
+        serde_json::Value::Object(map) => map,
+        _ => anyhow::bail!("theme must be a JSON object"),
+    };
+
+    let mut merged = match serde_json::to_value(&cfg.appearance)? {
+        serde_json::Value::Object(map) => map,
+        _ => unreachable!("Appearance serializes to an object"),
+    };
+    let mut ignored = Vec::new();
+    for (k, v) in incoming {
+        if NON_THEME_KEYS.contains(&k.as_str()) {
+            tracing::info!(key=%k, "import_theme: skipping non-cosmetic setting");
+        } else if merged.contains_key(&k) {
+            merged.insert(k, v);
+        } else {
+            tracing::warn!(key=%k, "import_theme: ignoring unknown appearance key");
+            ignored.push(k);
+        }
+    }
+    let appearance: Appearance = serde_json::from_value(serde_json::Value::Object(merged))
+        .context("theme does not describe a valid appearance")?;
+    cfg.appearance = appearance;
+    Ok(ignored)
+}
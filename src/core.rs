@@ -1,5 +1,218 @@
-pub fn should_show(toggled_on: bool, high_contrast: bool, fullscreen: bool) -> bool {
-    toggled_on && !high_contrast && !fullscreen
+use crate::config::{Appearance, Config, DialogPlacement, OverlayAnchor, TrayBadge};
+use crate::vd_backend::VdBackend;
+
+/// Every input to the overlay's show/hide decision. See [`VisibilityState::hide_reason`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct VisibilityState {
+    /// The user's global toggle (tray / hotkey).
+    pub toggled_on: bool,
+    /// `appearance.auto_hide_ms` is set.
+    pub auto_hide: bool,
+    /// Auto-hide: inside the window after a desktop switch.
+    pub shown_by_switch: bool,
+    /// Auto-hide: toggled by hand since the last switch.
+    pub manual_override: bool,
+    pub high_contrast: bool,
+    /// The foreground window is fullscreen and `hide_on_fullscreen` is on.
+    pub fullscreen: bool,
+    /// A `hide_for_processes` app is in the foreground.
+    pub excluded_app: bool,
+    /// The current desktop's `hidden` flag.
+    pub desktop_hidden: bool,
+    /// The display is off and `suspend_on_display_off` is on.
+    pub display_off: bool,
+    /// The Remote Desktop session is disconnected.
+    pub remote_disconnected: bool,
+    /// `appearance.always_show`: `fullscreen` and `excluded_app` no longer hide the overlay.
+    pub always_show: bool,
+    /// The panic-hide hotkey is engaged; hides regardless of everything else.
+    pub panic_hidden: bool,
+    /// Inside the peek hotkey's brief window: the user's toggle, auto-hide, the desktop flag
+    /// and fullscreen/excluded apps don't hide the overlay.
+    pub peeking: bool,
+}
+
+/// Why the overlay is hidden, in precedence order: the first that applies is reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum HideReason {
+    PanicHidden,
+    DisplayOff,
+    RemoteDisconnected,
+    DesktopHidden,
+    HighContrast,
+    Fullscreen,
+    ExcludedApp,
+    ToggledOff,
+    AutoHidden,
+}
+
+impl VisibilityState {
+    /// The highest-precedence reason to hide, or `None` to show. The panic-hide hotkey beats
+    /// everything; then system state (display, session), then the per-desktop flag, then
+    /// environment (accessibility, foreground window, unless `always_show`), and finally the
+    /// user's toggle and the auto-hide window. A peek lifts the per-desktop flag, the
+    /// foreground window, the toggle and auto-hide, but not the panic hide, system state or
+    /// accessibility.
+    pub fn hide_reason(&self) -> Option<HideReason> {
+        let unless_peeking = !self.peeking;
+        let reasons = [
+            (self.panic_hidden, HideReason::PanicHidden),
+            (self.display_off, HideReason::DisplayOff),
+            (self.remote_disconnected, HideReason::RemoteDisconnected),
+            (
+                self.desktop_hidden && unless_peeking,
+                HideReason::DesktopHidden,
+            ),
+            (self.high_contrast, HideReason::HighContrast),
+            (
+                self.fullscreen && !self.always_show && unless_peeking,
+                HideReason::Fullscreen,
+            ),
+            (
+                self.excluded_app && !self.always_show && unless_peeking,
+                HideReason::ExcludedApp,
+            ),
+            (!self.toggled_on && unless_peeking, HideReason::ToggledOff),
+            (
+                !auto_hide_allows(self.auto_hide, self.shown_by_switch, self.manual_override)
+                    && unless_peeking,
+                HideReason::AutoHidden,
+            ),
+        ];
+        reasons
+            .into_iter()
+            .find_map(|(applies, reason)| applies.then_some(reason))
+    }
+
+    pub fn should_show(&self) -> bool {
+        self.hide_reason().is_none()
+    }
+}
+
+/// Positional form of [`VisibilityState::should_show`] for callers that only track these
+/// flags. `desktop_hidden` is the current desktop's `hidden` flag; `excluded_app` is set
+/// while a `hide_for_processes` app is in the foreground.
+pub fn should_show(
+    toggled_on: bool,
+    high_contrast: bool,
+    fullscreen: bool,
+    desktop_hidden: bool,
+    excluded_app: bool,
+) -> bool {
+    VisibilityState {
+        toggled_on,
+        high_contrast,
+        fullscreen,
+        desktop_hidden,
+        excluded_app,
+        ..Default::default()
+    }
+    .should_show()
+}
+
+/// Whether the executable at `image_path` is in `excluded` (`hide_for_processes`). Only the
+/// base file names are compared, case-insensitively.
+pub fn is_excluded_process(image_path: &str, excluded: &[String]) -> bool {
+    let base = |p: &str| {
+        p.rsplit(['\\', '/'])
+            .next()
+            .unwrap_or(p)
+            .trim()
+            .to_lowercase()
+    };
+    let name = base(image_path);
+    !name.is_empty() && excluded.iter().any(|e| base(e) == name)
+}
+
+/// Whether a window covering `window_rect` is fullscreen on the monitor `monitor_rect`
+/// (both `(left, top, right, bottom)`). It must cover the whole monitor, taskbar area
+/// included, within 2px, and have no caption or sizing frame (`has_caption`); a maximized
+/// normal window therefore doesn't count.
+pub fn is_fullscreen(
+    window_rect: (i32, i32, i32, i32),
+    monitor_rect: (i32, i32, i32, i32),
+    has_caption: bool,
+) -> bool {
+    let (l, t, r, b) = window_rect;
+    let (ml, mt, mr, mb) = monitor_rect;
+    let tol = 2;
+    let covers_monitor = l <= ml + tol && t <= mt + tol && r >= mr - tol && b >= mb - tol;
+    covers_monitor && !has_caption
+}
+
+/// Whether the overlay is switched off for desktop `guid` via its label's `hidden` flag.
+pub fn desktop_hidden(cfg: &Config, guid: &str) -> bool {
+    cfg.desktops.get(guid).is_some_and(|l| l.hidden)
+}
+
+/// Desktop to land on when removing desktop `index` of `count`: the one to its left, or the
+/// next one when removing the first. `None` when it is the only desktop.
+pub fn removal_fallback(index: usize, count: usize) -> Option<usize> {
+    if count <= 1 || index >= count {
+        return None;
+    }
+    Some(if index > 0 { index - 1 } else { 1 })
+}
+
+/// Whether auto-hide lets the overlay show: always when auto-hide is off, otherwise only
+/// while a desktop switch is being announced or after a manual toggle (until the next switch).
+pub fn auto_hide_allows(auto_hide: bool, shown_by_switch: bool, manual_override: bool) -> bool {
+    !auto_hide || shown_by_switch || manual_override
+}
+
+/// How long a fade from `from` to `to` takes when a full 0↔255 fade takes `fade_ms`, so a
+/// reversed half-finished fade doesn't take the full time.
+pub fn fade_duration(fade_ms: u32, from: u8, to: u8) -> u32 {
+    fade_ms * from.abs_diff(to) as u32 / 255
+}
+
+/// Fade level `elapsed_ms` into a linear fade from `from` to `to` over `duration_ms`.
+pub fn fade_level(from: u8, to: u8, elapsed_ms: u32, duration_ms: u32) -> u8 {
+    if elapsed_ms >= duration_ms {
+        return to;
+    }
+    let delta = (to as i64 - from as i64) * elapsed_ms as i64 / duration_ms as i64;
+    (from as i64 + delta) as u8
+}
+
+/// Default `appearance.poll_interval_ms`.
+pub const DEFAULT_POLL_INTERVAL_MS: u32 = 250;
+/// Default `appearance.visibility_interval_ms`.
+pub const DEFAULT_VISIBILITY_INTERVAL_MS: u32 = 1000;
+const MIN_POLL_INTERVAL_MS: u32 = 50;
+const MIN_VISIBILITY_INTERVAL_MS: u32 = 100;
+
+/// Restarts of a dead desktop-event thread before staying on the poller for good.
+pub const MAX_VD_RESTARTS: u32 = 5;
+
+/// Wait before restart attempt `attempt` (0-based) of a dead desktop-event thread: 2 s,
+/// doubling up to 60 s. `None` once [`MAX_VD_RESTARTS`] attempts have been made.
+pub fn vd_restart_delay_ms(attempt: u32) -> Option<u32> {
+    (attempt < MAX_VD_RESTARTS).then(|| 2000u32.saturating_mul(1 << attempt.min(5)).min(60_000))
+}
+
+/// Restart count to carry on when a desktop-event thread dies after `uptime_ms`, having been
+/// started with `restarts` attempts already made. It only goes back to 0 once the thread
+/// stayed up a full delay period ([`vd_restart_delay_ms`], 60 s past the last attempt), so a
+/// listener that dies right after each start keeps backing off and eventually gives up.
+pub fn vd_restarts_after_death(restarts: u32, uptime_ms: u64) -> u32 {
+    let period = vd_restart_delay_ms(restarts).unwrap_or(60_000);
+    if uptime_ms >= u64::from(period) {
+        0
+    } else {
+        restarts
+    }
+}
+
+/// Desktop-switch poll period from `appearance.poll_interval_ms`, raised to at least 50 ms;
+/// `None` when set to 0 (poller disabled).
+pub fn poll_interval(raw: u32) -> Option<u32> {
+    (raw != 0).then(|| raw.max(MIN_POLL_INTERVAL_MS))
+}
+
+/// Visibility re-check period from `appearance.visibility_interval_ms`, at least 100 ms.
+pub fn visibility_interval(raw: u32) -> u32 {
+    raw.max(MIN_VISIBILITY_INTERVAL_MS)
 }
 
 pub fn calc_top_center(
@@ -15,10 +228,1107 @@ pub fn calc_top_center(
     (x, y)
 }
 
+/// Default `appearance.description_scale`.
+pub const DEFAULT_DESCRIPTION_SCALE: f32 = 0.75;
+
+/// `appearance.description_scale` clamped to 0.3–1.0; NaN falls back to the default.
+pub fn description_scale(raw: f32) -> f32 {
+    if raw.is_nan() {
+        DEFAULT_DESCRIPTION_SCALE
+    } else {
+        raw.clamp(0.3, 1.0)
+    }
+}
+
+/// Window size for a title row of size `title` stacked above an optional description block
+/// of size `description`, with `pad` on every side: as wide as the wider part, as tall as
+/// both together.
+pub fn stacked_size(title: (i32, i32), description: Option<(i32, i32)>, pad: i32) -> (i32, i32) {
+    let (desc_w, desc_h) = description.unwrap_or((0, 0));
+    (title.0.max(desc_w) + pad * 2, title.1 + desc_h + pad * 2)
+}
+
+/// `value` DIPs in physical pixels at `dpi` (96 = 100% scaling), rounded to the nearest pixel.
+/// An unknown DPI (0) is treated as 96.
+pub fn scale_dip(value: i32, dpi: u32) -> i32 {
+    let dpi = if dpi == 0 { 96 } else { dpi };
+    (value as f64 * dpi as f64 / 96.0).round() as i32
+}
+
+/// Corner radius for a `width`×`height` overlay background: `configured` if set, else half
+/// the font size within 6–20 px. Never more than half the shorter side (a pill); 0 is square.
+pub fn corner_radius(configured: Option<i32>, font_px: i32, width: i32, height: i32) -> i32 {
+    let radius = configured.unwrap_or((font_px / 2).clamp(6, 20));
+    radius.clamp(0, (width.min(height) / 2).max(0))
+}
+
+/// Top-left position of a `w`×`h` overlay placed at `anchor` within `work`.
+///
+/// `offset_y` pushes the overlay away from its edge (down for top anchors, up for bottom).
+pub fn anchor_position(
+    anchor: OverlayAnchor,
+    work: (i32, i32, i32, i32),
+    w: i32,
+    h: i32,
+    margin: i32,
+    offset_y: i32,
+) -> (i32, i32) {
+    let (left, top, right, bottom) = work;
+    // `Taskbar` is placed by `taskbar_dock_position`; here it is the top-center fallback.
+    let x = match anchor {
+        OverlayAnchor::TopLeft => left + margin,
+        OverlayAnchor::TopRight => right - margin - w,
+        OverlayAnchor::TopCenter | OverlayAnchor::BottomCenter | OverlayAnchor::Taskbar => {
+            left + (right - left - w) / 2
+        }
+    };
+    let y = match anchor {
+        OverlayAnchor::BottomCenter => bottom - margin - h - offset_y,
+        _ => top + margin + offset_y,
+    };
+    (x, y)
+}
+
+/// How much of the overlay, per axis, [`nudge_within`] keeps inside its bounds.
+pub const MIN_ON_SCREEN_PX: i32 = 32;
+
+/// `origin` of a `size` window moved by `nudge` (`appearance.offset_x`/`offset_y`), then
+/// clamped so at least [`MIN_ON_SCREEN_PX`] of it (all of it, if smaller) stays inside
+/// `bounds` on each axis.
+pub fn nudge_within(
+    origin: (i32, i32),
+    size: (i32, i32),
+    nudge: (i32, i32),
+    bounds: (i32, i32, i32, i32),
+) -> (i32, i32) {
+    let (left, top, right, bottom) = bounds;
+    let axis = |pos: i32, len: i32, lo: i32, hi: i32| {
+        let visible = len.clamp(0, MIN_ON_SCREEN_PX);
+        pos.min(hi - visible).max(lo - len + visible)
+    };
+    (
+        axis(origin.0 + nudge.0, size.0, left, right),
+        axis(origin.1 + nudge.1, size.1, top, bottom),
+    )
+}
+
+/// Screen edge the taskbar is docked to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskbarEdge {
+    Left,
+    Top,
+    Right,
+    Bottom,
+}
+
+/// Top-left position of a `w`×`h` overlay flush against a taskbar at `edge` with bounds
+/// `taskbar` (left, top, right, bottom), centered along it. `offset` pushes the overlay
+/// further away from the taskbar (used to stack multiple instances).
+pub fn taskbar_dock_position(
+    edge: TaskbarEdge,
+    taskbar: (i32, i32, i32, i32),
+    w: i32,
+    h: i32,
+    offset: i32,
+) -> (i32, i32) {
+    let (left, top, right, bottom) = taskbar;
+    let center_x = left + (right - left - w) / 2;
+    let center_y = top + (bottom - top - h) / 2;
+    match edge {
+        TaskbarEdge::Bottom => (center_x, top - h - offset),
+        TaskbarEdge::Top => (center_x, bottom + offset),
+        TaskbarEdge::Left => (right + offset, center_y),
+        TaskbarEdge::Right => (left - w - offset, center_y),
+    }
+}
+
+/// Top-left position for a `w`×`h` dialog, kept inside `work`.
+///
+/// `anchor` is the overlay's window rect (left, top, right, bottom); `cursor` is the mouse position.
+/// `Centered` centers on `work`, which callers take from the overlay's monitor.
+pub fn place_dialog(
+    placement: DialogPlacement,
+    cursor: (i32, i32),
+    anchor: (i32, i32, i32, i32),
+    work: (i32, i32, i32, i32),
+    w: i32,
+    h: i32,
+) -> (i32, i32) {
+    const GAP: i32 = 12;
+    let (al, _, ar, ab) = anchor;
+    let (wl, wt, wr, wb) = work;
+    let (x, y) = match placement {
+        DialogPlacement::Centered => (wl + (wr - wl - w) / 2, wt + (wb - wt - h) / 2),
+        DialogPlacement::NearCursor => (cursor.0 + GAP, cursor.1 + GAP),
+        DialogPlacement::NearOverlay => (al + (ar - al - w) / 2, ab + GAP),
+    };
+    (x.min(wr - w).max(wl), y.min(wb - h).max(wt))
+}
+
+/// An sRGB color with straight (non-premultiplied) alpha.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rgba {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+/// Font weight from a CSS-style name (`"bold"`, `"semi-bold"`, …) or number (`1`–`999`).
+/// `None` for anything else; callers fall back to normal (400).
+pub fn parse_font_weight(s: &str) -> Option<u16> {
+    if let Ok(n) = s.trim().parse::<i64>() {
+        return (1..=999).contains(&n).then_some(n as u16);
+    }
+    let key: String = s
+        .chars()
+        .filter(|c| !matches!(c, '-' | '_' | ' '))
+        .collect::<String>()
+        .to_ascii_lowercase();
+    Some(match key.as_str() {
+        "thin" | "hairline" => 100,
+        "extralight" | "ultralight" => 200,
+        "light" => 300,
+        "semilight" => 350,
+        "normal" | "regular" => 400,
+        "medium" => 500,
+        "semibold" | "demibold" => 600,
+        "bold" => 700,
+        "extrabold" | "ultrabold" => 800,
+        "black" | "heavy" => 900,
+        _ => return None,
+    })
+}
+
+/// Parse `#RGB`, `#RRGGBB` or `#RRGGBBAA` (the `#` is optional); alpha defaults to opaque.
+pub fn parse_hex_color(s: &str) -> Option<Rgba> {
+    let hex = s.trim().trim_start_matches('#');
+    if !hex.is_ascii() {
+        return None;
+    }
+    let byte = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    match hex.len() {
+        3 => {
+            let nib = |i: usize| u8::from_str_radix(&hex[i..i + 1], 16).ok().map(|v| v * 17);
+            Some(Rgba {
+                r: nib(0)?,
+                g: nib(1)?,
+                b: nib(2)?,
+                a: 255,
+            })
+        }
+        6 | 8 => Some(Rgba {
+            r: byte(0)?,
+            g: byte(2)?,
+            b: byte(4)?,
+            a: if hex.len() == 8 { byte(6)? } else { 255 },
+        }),
+        _ => None,
+    }
+}
+
+/// Shadow color when `appearance.text_shadow_color` is unset: black at 70%.
+pub const DEFAULT_TEXT_SHADOW: Rgba = Rgba {
+    r: 0,
+    g: 0,
+    b: 0,
+    a: 180,
+};
+
+/// Color and offset (1–10 px, default 2) of the text shadow, or `None` when
+/// `appearance.text_shadow` is off. An unparseable color falls back to the default.
+pub fn text_shadow(appearance: &Appearance) -> Option<(Rgba, i32)> {
+    if !appearance.text_shadow {
+        return None;
+    }
+    let color = match appearance.text_shadow_color.as_deref() {
+        None => DEFAULT_TEXT_SHADOW,
+        Some(value) => parse_hex_color(value).unwrap_or_else(|| {
+            tracing::warn!(
+                field = "text_shadow_color",
+                value,
+                "invalid color, using default"
+            );
+            DEFAULT_TEXT_SHADOW
+        }),
+    };
+    let offset = appearance.text_shadow_offset_px.unwrap_or(2).clamp(1, 10) as i32;
+    Some((color, offset))
+}
+
+/// Text and background colors the user set in `appearance`. Unset colors, and ones that
+/// don't parse (with a warning), are `None`, meaning the theme's.
+pub fn appearance_colors(appearance: &Appearance) -> (Option<Rgba>, Option<Rgba>) {
+    fn explicit(field: &str, value: Option<&str>) -> Option<Rgba> {
+        let value = value?;
+        let parsed = parse_hex_color(value);
+        if parsed.is_none() {
+            tracing::warn!(field, value, "invalid color, using the theme's");
+        }
+        parsed
+    }
+    (
+        explicit("text_color", appearance.text_color.as_deref()),
+        explicit("background_color", appearance.background_color.as_deref()),
+    )
+}
+
+/// Default text and background colors for the Windows theme. High contrast gets opaque
+/// white-on-black; light mode gets dark text on a translucent white box.
+pub fn theme_colors(is_light: bool, high_contrast: bool) -> (Rgba, Rgba) {
+    let rgba = |r, g, b, a| Rgba { r, g, b, a };
+    if high_contrast {
+        (rgba(255, 255, 255, 255), rgba(0, 0, 0, 255))
+    } else if is_light {
+        (rgba(0x1A, 0x1A, 0x1A, 255), rgba(255, 255, 255, 0xB3))
+    } else {
+        (rgba(255, 255, 255, 255), rgba(0, 0, 0, 0x59))
+    }
+}
+
+/// Colors to draw with: the ones set in `appearance`, with unset colors taken from the
+/// Windows theme when `auto_theme` is on, or from the dark theme (the original white on
+/// translucent black) when it's off. Colors the user set always win.
+pub fn effective_colors(
+    appearance: &Appearance,
+    is_light: bool,
+    high_contrast: bool,
+) -> (Rgba, Rgba) {
+    let (fg, bg) = appearance_colors(appearance);
+    let (theme_fg, theme_bg) = if appearance.auto_theme {
+        theme_colors(is_light, high_contrast)
+    } else {
+        theme_colors(false, false)
+    };
+    (fg.unwrap_or(theme_fg), bg.unwrap_or(theme_bg))
+}
+
+/// Font size for `guid`'s overlay: its label's `font_size_dip` when set (and nonzero),
+/// else `appearance.font_size_dip`.
+pub fn desktop_font_size(cfg: &Config, guid: &str) -> u32 {
+    cfg.desktops
+        .get(guid)
+        .and_then(|l| l.font_size_dip)
+        .filter(|&size| size > 0)
+        .unwrap_or(cfg.appearance.font_size_dip)
+}
+
+/// Background override for `guid` from its label's `color`, if set and valid. Runs on every
+/// draw, so invalid colors are skipped silently; see [`warn_invalid_desktop_colors`].
+pub fn desktop_background(cfg: &Config, guid: &str) -> Option<Rgba> {
+    parse_hex_color(cfg.desktops.get(guid)?.color.as_deref()?)
+}
+
+/// Log each desktop `color` in `cfg` that doesn't parse. Called once per config load.
+pub fn warn_invalid_desktop_colors(cfg: &Config) {
+    let colors = cfg
+        .desktops
+        .iter()
+        .filter_map(|(guid, label)| Some((guid, label.color.as_deref()?)));
+    for (guid, value) in colors {
+        if parse_hex_color(value).is_none() {
+            tracing::warn!(
+                guid,
+                value,
+                "invalid desktop color, using appearance.background_color"
+            );
+        }
+    }
+}
+
+/// WCAG relative luminance of an sRGB color.
+pub fn relative_luminance(r: u8, g: u8, b: u8) -> f32 {
+    fn channel(c: u8) -> f32 {
+        let c = c as f32 / 255.0;
+        if c <= 0.039_28 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    }
+    0.2126 * channel(r) + 0.7152 * channel(g) + 0.0722 * channel(b)
+}
+
+/// WCAG contrast ratio (1.0..=21.0) between two relative luminances.
+pub fn contrast_ratio(l1: f32, l2: f32) -> f32 {
+    let (hi, lo) = if l1 >= l2 { (l1, l2) } else { (l2, l1) };
+    (hi + 0.05) / (lo + 0.05)
+}
+
+/// Auto-contrast decision for a background of luminance `bg`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContrastChoice {
+    /// Draw white text (otherwise black).
+    pub white_text: bool,
+    /// Neither black nor white reaches the minimum ratio; draw the background box.
+    pub needs_backdrop: bool,
+}
+
+/// Pick whichever of black/white contrasts more with `bg`, flagging when even that
+/// falls short of `min_ratio`.
+pub fn pick_contrast(bg: f32, min_ratio: f32) -> ContrastChoice {
+    let with_white = contrast_ratio(1.0, bg);
+    let with_black = contrast_ratio(0.0, bg);
+    let white_text = with_white >= with_black;
+    let best = with_white.max(with_black);
+    ContrastChoice {
+        white_text,
+        needs_backdrop: best < min_ratio,
+    }
+}
+
+/// Opaque black or white, whichever reads better on a background of luminance `bg`.
+pub fn pick_text_color(bg: f32) -> Rgba {
+    let v = if pick_contrast(bg, 0.0).white_text {
+        255
+    } else {
+        0
+    };
+    Rgba {
+        r: v,
+        g: v,
+        b: v,
+        a: 255,
+    }
+}
+
+/// Alpha of the box `auto_contrast_colors` adds behind text that can't stand on its own.
+const AUTO_CONTRAST_BOX_ALPHA: u8 = 180;
+
+/// Text and background colors for `appearance.auto_contrast` over a backdrop of luminance
+/// `bg`: the text is [`pick_text_color`], and `background` is kept unless even that text
+/// misses `min_ratio`, in which case the box is made at least mostly opaque in the opposite
+/// color.
+pub fn auto_contrast_colors(bg: f32, min_ratio: f32, background: Rgba) -> (Rgba, Rgba) {
+    let choice = pick_contrast(bg, min_ratio);
+    let text = pick_text_color(bg);
+    if !choice.needs_backdrop || background.a >= AUTO_CONTRAST_BOX_ALPHA {
+        return (text, background);
+    }
+    let v = 255 - text.r;
+    let boxed = Rgba {
+        r: v,
+        g: v,
+        b: v,
+        a: AUTO_CONTRAST_BOX_ALPHA,
+    };
+    (text, boxed)
+}
+
+/// Mean relative luminance of sampled sRGB pixels; `None` without samples.
+pub fn average_luminance(samples: &[(u8, u8, u8)]) -> Option<f32> {
+    if samples.is_empty() {
+        return None;
+    }
+    let sum: f32 = samples
+        .iter()
+        .map(|&(r, g, b)| relative_luminance(r, g, b))
+        .sum();
+    Some(sum / samples.len() as f32)
+}
+
+/// Screen points to sample for auto-contrast: a grid over `rect` (left, top, right, bottom)
+/// grown by half its height on every side, skipping points on the overlay itself (it would
+/// read its own pixels) and outside `bounds` (its monitor).
+pub fn contrast_sample_points(
+    rect: (i32, i32, i32, i32),
+    bounds: (i32, i32, i32, i32),
+) -> Vec<(i32, i32)> {
+    const COLS: i32 = 12;
+    const ROWS: i32 = 6;
+    let (l, t, r, b) = rect;
+    let pad = ((b - t) / 2).max(8);
+    let (gl, gt, gr, gb) = (l - pad, t - pad, r + pad, b + pad);
+    let (bl, bt, br, bb) = bounds;
+    let mut points = Vec::new();
+    for row in 0..ROWS {
+        for col in 0..COLS {
+            let x = gl + (gr - gl) * (2 * col + 1) / (2 * COLS);
+            let y = gt + (gb - gt) * (2 * row + 1) / (2 * ROWS);
+            let on_overlay = x >= l && x < r && y >= t && y < b;
+            let on_monitor = x >= bl && x < br && y >= bt && y < bb;
+            if on_monitor && !on_overlay {
+                points.push((x, y));
+            }
+        }
+    }
+    points
+}
+
+/// Index of the monitor to use out of `count` attached ones, or `None` for the primary
+/// (nothing requested, or the requested monitor is no longer attached).
+pub fn pick_monitor(requested: Option<usize>, count: usize) -> Option<usize> {
+    requested.filter(|&i| i < count)
+}
+
+/// Monitors that get a mirror overlay: every one of `count` except the main overlay's, which
+/// is `requested` when attached, else `primary`.
+pub fn mirror_monitors(
+    count: usize,
+    requested: Option<usize>,
+    primary: Option<usize>,
+) -> Vec<usize> {
+    let main = pick_monitor(requested, count).or(primary);
+    (0..count).filter(|&i| Some(i) != main).collect()
+}
+
+/// Window title that identifies the instance running with `cfg_file`, so a second launch with
+/// the same config finds it while `--config`/`--portable` launches with another one don't.
+/// Lowercased, since Windows paths are case-insensitive.
+pub fn instance_window_title(cfg_file: &std::path::Path) -> String {
+    format!("mddsklbl {}", cfg_file.display()).to_lowercase()
+}
+
+/// Non-zero tag for where an overlay sits (anchor and monitor); instances only stack against
+/// overlays with the same tag.
+pub fn placement_tag(anchor: OverlayAnchor, monitor_index: Option<usize>) -> isize {
+    let monitor = monitor_index.map_or(0, |i| i as isize + 1);
+    1 + anchor as isize + 8 * monitor
+}
+
+/// Vertical nudge for an overlay in stacking `slot` (slot 0 stays at the anchor).
+pub fn instance_offset(slot: u32, step_px: i32) -> i32 {
+    (slot as i32).saturating_mul(step_px.max(0))
+}
+
+/// Text to composite onto the tray icon, or `None` for the plain icon.
+///
+/// `index` is the 0-based desktop index; `FirstLetter` falls back to it when the title is blank.
+pub fn badge_text(mode: TrayBadge, index: Option<usize>, title: &str) -> Option<String> {
+    let index_text = || index.map(|i| (i + 1).to_string());
+    match mode {
+        TrayBadge::None => None,
+        TrayBadge::Index => index_text(),
+        TrayBadge::FirstLetter => match title.trim().chars().next() {
+            Some(c) => Some(c.to_uppercase().collect()),
+            None => index_text(),
+        },
+    }
+}
+
+/// Trim both ends and collapse internal whitespace runs to a single space.
+pub fn collapse_whitespace(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// `text` cut to at most `max_chars` characters (not bytes).
+pub fn clamp_label(text: &str, max_chars: usize) -> String {
+    text.chars().take(max_chars).collect()
+}
+
+/// `text` as-is if `measure(text)` fits in `budget`, else its longest prefix that fits with
+/// a trailing `…`. `measure` returns a width in the same unit as `budget` (pixels when
+/// drawing) and is assumed to grow with the text; a budget too small for anything yields `…`.
+pub fn ellipsize(text: &str, budget: i32, measure: impl Fn(&str) -> i32) -> String {
+    if measure(text) <= budget {
+        return text.to_string();
+    }
+    let chars: Vec<char> = text.chars().collect();
+    // Only cut where a character starts a new cluster, so emoji sequences, flags and
+    // accents are never split into stray joiners or half-pairs.
+    let cuts: Vec<usize> = std::iter::once(0)
+        .chain((1..chars.len()).filter(|&i| starts_cluster(&chars, i)))
+        .collect();
+    let candidate = |n: usize| {
+        let head: String = chars[..cuts[n]].iter().collect();
+        format!("{}…", head.trim_end())
+    };
+    // Binary search for the longest prefix that still fits once the ellipsis is added.
+    let (mut lo, mut hi) = (0, cuts.len() - 1);
+    while lo < hi {
+        let mid = (lo + hi).div_ceil(2);
+        if measure(&candidate(mid)) <= budget {
+            lo = mid;
+        } else {
+            hi = mid - 1;
+        }
+    }
+    candidate(lo)
+}
+
+/// Whether `chars[i]` begins a new user-perceived character: not a combining mark, variation
+/// selector, skin-tone modifier, tag or ZWJ, not right after a ZWJ, and not the second half
+/// of a regional-indicator (flag) pair. An approximation of grapheme clusters that covers
+/// emoji and accented text.
+fn starts_cluster(chars: &[char], i: usize) -> bool {
+    let c = chars[i] as u32;
+    let extends = matches!(
+        c,
+        0x0300..=0x036F
+            | 0x1AB0..=0x1AFF
+            | 0x1DC0..=0x1DFF
+            | 0x200D
+            | 0x20D0..=0x20FF
+            | 0xFE00..=0xFE0F
+            | 0xFE20..=0xFE2F
+            | 0x1F3FB..=0x1F3FF
+            | 0xE0020..=0xE007F
+            | 0xE0100..=0xE01EF
+    );
+    if extends || chars[i - 1] == '\u{200D}' {
+        return false;
+    }
+    let is_ri = |c: char| ('\u{1F1E6}'..='\u{1F1FF}').contains(&c);
+    if is_ri(chars[i]) {
+        // Flags pair up from the start of a run of regional indicators.
+        let run = chars[..i].iter().rev().take_while(|&&c| is_ri(c)).count();
+        return run % 2 == 0;
+    }
+    true
+}
+
+/// Replace `{n}` with the 1-based desktop number (`index` is 0-based) and `{count}` with
+/// the desktop count. A placeholder whose value is unknown is removed together with the
+/// space it leaves behind. `{{n}}` and `{{count}}` produce the literal text; any other
+/// braces are kept as written.
+pub fn expand_placeholders(template: &str, index: Option<usize>, count: Option<usize>) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        if let Some(name) = ["{{n}}", "{{count}}"]
+            .into_iter()
+            .find(|p| rest.starts_with(p))
+        {
+            out.push_str(&name[1..name.len() - 1]);
+            rest = &rest[name.len()..];
+            continue;
+        }
+        let (placeholder, value) = if rest.starts_with("{n}") {
+            ("{n}", index.map(|i| i + 1))
+        } else if rest.starts_with("{count}") {
+            ("{count}", count)
+        } else {
+            out.push('{');
+            rest = &rest[1..];
+            continue;
+        };
+        rest = &rest[placeholder.len()..];
+        match value {
+            Some(v) => out.push_str(&v.to_string()),
+            // Strip cleanly: "Desktop {n}: Work" -> "Desktop: Work", "{n} Work" -> "Work".
+            None => {
+                if out.is_empty() || out.ends_with(' ') {
+                    rest = rest.trim_start_matches(' ');
+                }
+                if rest.is_empty()
+                    || rest.starts_with(|c: char| c == ' ' || c.is_ascii_punctuation())
+                {
+                    out.truncate(out.trim_end_matches(' ').len());
+                }
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Cut `text` to at most `max_units` UTF-16 code units (ending in `…` when shortened),
+/// never splitting a surrogate pair. For fixed-size Win32 buffers such as tooltips.
+pub fn truncate_utf16(text: &str, max_units: usize) -> String {
+    if text.encode_utf16().count() <= max_units {
+        return text.to_string();
+    }
+    let budget = max_units.saturating_sub(1);
+    let mut used = 0;
+    let mut out: String = text
+        .chars()
+        .take_while(|c| {
+            used += c.len_utf16();
+            used <= budget
+        })
+        .collect();
+    if max_units > 0 {
+        out.push('…');
+    }
+    out
+}
+
+/// First non-blank of the config title and the Windows (Task View) desktop name, else `fallback`.
+pub fn pick_title(config_title: &str, windows_name: Option<&str>, fallback: &str) -> String {
+    [Some(config_title), windows_name]
+        .into_iter()
+        .flatten()
+        .find(|t| !t.trim().is_empty())
+        .unwrap_or(fallback)
+        .to_string()
+}
+
+/// The overlay lines for `guid`: `"{title} : {first description line}"`, then one line per
+/// further description line (descriptions split on `\n`). `{n}`/`{count}` placeholders in
+/// the label are expanded first (see `expand_placeholders`).
+///
+/// A blank config title falls back to the live Windows desktop name, then to the 1-based
+/// index (when `cfg.auto_number_unlabeled` is set), then to `"Desktop"`. Unless
+/// `cfg.preserve_whitespace` is set, each line is normalized with `collapse_whitespace` and
+/// blank description lines are dropped. The title carries `appearance.label_prefix`/`suffix`.
+pub fn overlay_lines(cfg: &Config, guid: &str, vd: &dyn VdBackend) -> Vec<String> {
+    let (title, desc) = decorated_label(cfg, guid, vd);
+    let mut rest = desc.into_iter();
+    let mut lines = vec![format!("{} : {}", title, rest.next().unwrap_or_default())];
+    lines.extend(rest);
+    lines
+}
+
+/// [`resolved_label`] with `appearance.label_prefix` and `label_suffix` around the title, as
+/// the overlay draws it. The decoration is part of the text, so it counts toward the width
+/// the overlay ellipsizes to.
+pub fn decorated_label(cfg: &Config, guid: &str, vd: &dyn VdBackend) -> (String, Vec<String>) {
+    let (title, desc) = resolved_label(cfg, guid, vd);
+    let a = &cfg.appearance;
+    (
+        format!("{}{}{}", a.label_prefix, title, a.label_suffix),
+        desc,
+    )
+}
+
+/// The title and description lines `overlay_lines` shows for `guid`, before formatting.
+pub fn resolved_label(cfg: &Config, guid: &str, vd: &dyn VdBackend) -> (String, Vec<String>) {
+    let mut label = cfg.desktops.get(guid).cloned().unwrap_or_default();
+    let position = vd.index_and_count();
+    let (index, count) = (position.map(|(i, _)| i), position.map(|(_, c)| c));
+    label.title = expand_placeholders(&label.title, index, count);
+    label.description = expand_placeholders(&label.description, index, count);
+    let mut windows_name = vd.name();
+    let mut desc: Vec<String> = label
+        .description
+        .split('\n')
+        .map(|l| l.trim_end_matches('\r').to_string())
+        .collect();
+    if !cfg.preserve_whitespace {
+        label.title = collapse_whitespace(&label.title);
+        windows_name = windows_name.map(|n| collapse_whitespace(&n));
+        desc = desc
+            .iter()
+            .map(|l| collapse_whitespace(l))
+            .filter(|l| !l.is_empty())
+            .collect();
+    }
+    let fallback = match position {
+        Some((i, _)) if cfg.auto_number_unlabeled => (i + 1).to_string(),
+        _ => "Desktop".to_string(),
+    };
+    let title = pick_title(&label.title, windows_name.as_deref(), &fallback);
+    (title, desc)
+}
+
+/// State for `--dump`: the current desktop with its resolved label, then every desktop in
+/// `live` order with its configured label (empty strings when unlabeled). Labels are looked
+/// up by [`crate::config::label_key`], so `key_by_index` configs dump the same labels.
+pub fn state_dump(
+    cfg: &Config,
+    current: &str,
+    live: &[String],
+    vd: &dyn VdBackend,
+) -> serde_json::Value {
+    let key = |guid: &str| crate::config::label_key(cfg, guid, live);
+    let (title, desc) = resolved_label(cfg, &key(current), vd);
+    let desktops: Vec<serde_json::Value> = live
+        .iter()
+        .enumerate()
+        .map(|(i, guid)| {
+            let label = cfg.desktops.get(&key(guid)).cloned().unwrap_or_default();
+            serde_json::json!({
+                "guid": guid,
+                "index": i + 1,
+                "title": label.title,
+                "description": label.description,
+            })
+        })
+        .collect();
+    serde_json::json!({
+        "current": {
+            "guid": current,
+            "index": vd.index_and_count().map(|(i, _)| i + 1),
+            "title": title,
+            "description": desc.join("\n"),
+        },
+        "desktops": desktops,
+    })
+}
+
+/// Every configured label as a `title — description` line for the clipboard, labels of live
+/// desktops first in `live` order, then the rest by key. Multi-line descriptions are joined
+/// with ` / `. The current desktop's line starts with `* ` and ends with its GUID; it gets a
+/// line even when it has no label.
+pub fn labels_text(cfg: &Config, current: &str, live: &[String]) -> String {
+    let key = |guid: &str| crate::config::label_key(cfg, guid, live);
+    let current_key = key(current);
+    let mut keys: Vec<&String> = cfg.desktops.keys().collect();
+    let rank = |k: &str| live.iter().position(|g| key(g) == k).unwrap_or(usize::MAX);
+    keys.sort_by(|a, b| rank(a).cmp(&rank(b)).then_with(|| a.cmp(b)));
+    let line = |k: &str| {
+        let label = cfg.desktops.get(k).cloned().unwrap_or_default();
+        let title = match label.title.trim() {
+            "" => "(untitled)",
+            t => t,
+        };
+        let desc: Vec<&str> = label
+            .description
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .collect();
+        let mut out = if desc.is_empty() {
+            title.to_string()
+        } else {
+            format!("{title} — {}", desc.join(" / "))
+        };
+        if k == current_key {
+            out = format!("* {out} [{current}]");
+        }
+        out
+    };
+    let mut lines: Vec<String> = keys.iter().map(|k| line(k)).collect();
+    if !cfg.desktops.contains_key(&current_key) {
+        lines.push(line(&current_key));
+    }
+    lines.join("\n")
+}
+
+/// `overlay_lines` joined with `\n`.
+pub fn format_overlay_line(cfg: &Config, guid: &str, vd: &dyn VdBackend) -> String {
+    overlay_lines(cfg, guid, vd).join("\n")
+}
+
+/// The new desktop id if the backend reports a different desktop than `current`.
+pub fn switched_desktop(vd: &dyn VdBackend, current: &str) -> Option<String> {
+    let id = vd.current_guid();
+    (id != current).then_some(id)
+}
+
+/// Tray badge for the desktop `guid` according to `cfg.tray_badge`.
+pub fn tray_badge_for(cfg: &Config, guid: &str, vd: &dyn VdBackend) -> Option<String> {
+    if cfg.tray_badge == TrayBadge::None {
+        return None;
+    }
+    let position = vd.index_and_count();
+    let (index, count) = (position.map(|(i, _)| i), position.map(|(_, c)| c));
+    let title = cfg
+        .desktops
+        .get(guid)
+        .map(|l| expand_placeholders(&l.title, index, count))
+        .unwrap_or_default();
+    badge_text(cfg.tray_badge, index, &title)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn vd_restarts_reset_only_after_a_stable_run() {
+        // The thread from startup dying keeps the count at 0 either way.
+        assert_eq!(vd_restarts_after_death(0, 0), 0);
+        // A restart that dies before its delay period is up keeps counting.
+        assert_eq!(vd_restarts_after_death(2, 500), 2);
+        assert_eq!(vd_restarts_after_death(2, 7_999), 2);
+        assert_eq!(vd_restarts_after_death(2, 8_000), 0);
+        // Past the last attempt the period is 60 s.
+        assert_eq!(
+            vd_restarts_after_death(MAX_VD_RESTARTS, 59_999),
+            MAX_VD_RESTARTS
+        );
+        assert_eq!(vd_restarts_after_death(MAX_VD_RESTARTS, 60_000), 0);
+        // So a listener that dies right after every start runs out of attempts.
+        let mut restarts = 0;
+        let mut attempts = 0;
+        while vd_restart_delay_ms(restarts).is_some() {
+            restarts = vd_restarts_after_death(restarts + 1, 10);
+            attempts += 1;
+        }
+        assert_eq!(attempts, MAX_VD_RESTARTS);
+    }
+
+    #[test]
+    fn vd_restart_delay_backs_off_then_gives_up() {
+        let delays: Vec<_> = (0..=MAX_VD_RESTARTS).map(vd_restart_delay_ms).collect();
+        assert_eq!(
+            delays,
+            [
+                Some(2000),
+                Some(4000),
+                Some(8000),
+                Some(16000),
+                Some(32000),
+                None
+            ]
+        );
+    }
+
+    fn cfg_with(title: &str, description: &str) -> Config {
+        let mut cfg = Config::default();
+        cfg.desktops.insert(
+            "g".into(),
+            crate::config::DesktopLabel {
+                title: title.into(),
+                description: description.into(),
+                color: None,
+                hidden: false,
+                font_size_dip: None,
+            },
+        );
+        cfg
+    }
+
+    fn vd_at(index: Option<usize>) -> crate::vd_backend::MockVd {
+        crate::vd_backend::MockVd {
+            index,
+            count: 3,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn pick_title_prefers_config_then_windows_name() {
+        assert_eq!(pick_title("Work", Some("Win"), "Desktop"), "Work");
+        assert_eq!(pick_title("", Some("Win"), "Desktop"), "Win");
+        assert_eq!(pick_title("  ", Some(" \t"), "Desktop"), "Desktop");
+        assert_eq!(pick_title("", None, "Desktop"), "Desktop");
+    }
+
+    #[test]
+    fn format_uses_windows_desktop_name() {
+        let cfg = cfg_with("", "notes");
+        let mut vd = vd_at(Some(1));
+        vd.name = Some("  Research  ".into());
+        assert_eq!(format_overlay_line(&cfg, "g", &vd), "Research : notes");
+        vd.name = Some("   ".into());
+        assert_eq!(format_overlay_line(&cfg, "g", &vd), "Desktop : notes");
+    }
+
+    #[test]
+    fn format_collapses_whitespace() {
+        let cfg = cfg_with("  Build \t box ", "  CI   runs\n here ");
+        assert_eq!(
+            format_overlay_line(&cfg, "g", &vd_at(None)),
+            "Build box : CI runs\nhere"
+        );
+        let cfg = cfg_with("   ", "x");
+        assert_eq!(format_overlay_line(&cfg, "g", &vd_at(None)), "Desktop : x");
+    }
+
+    #[test]
+    fn format_auto_numbers_unlabeled() {
+        let mut cfg = cfg_with("", "scratch");
+        assert_eq!(
+            format_overlay_line(&cfg, "g", &vd_at(Some(2))),
+            "Desktop : scratch"
+        );
+        cfg.auto_number_unlabeled = true;
+        assert_eq!(
+            format_overlay_line(&cfg, "g", &vd_at(Some(2))),
+            "3 : scratch"
+        );
+        assert_eq!(format_overlay_line(&cfg, "other", &vd_at(Some(0))), "1 : ");
+        assert_eq!(
+            format_overlay_line(&cfg, "g", &vd_at(None)),
+            "Desktop : scratch"
+        );
+        let cfg = Config {
+            auto_number_unlabeled: true,
+            ..cfg_with("Named", "")
+        };
+        assert_eq!(format_overlay_line(&cfg, "g", &vd_at(Some(2))), "Named : ");
+    }
+
+    #[test]
+    fn overlay_lines_split_description() {
+        let vd = vd_at(None);
+        let cfg = cfg_with("Work", "first\r\n\n  second  \n");
+        assert_eq!(overlay_lines(&cfg, "g", &vd), ["Work : first", "second"]);
+        // An empty description adds no extra line.
+        let cfg = cfg_with("Work", "");
+        assert_eq!(overlay_lines(&cfg, "g", &vd), ["Work : "]);
+        let mut cfg = cfg_with("Work", "a\n\n b");
+        cfg.preserve_whitespace = true;
+        assert_eq!(overlay_lines(&cfg, "g", &vd), ["Work : a", "", " b"]);
+    }
+
+    #[test]
+    fn label_decoration_wraps_only_the_title() {
+        let mut cfg = cfg_with("  Work ", "first\nsecond");
+        cfg.appearance.label_prefix = "「 ".into();
+        cfg.appearance.label_suffix = " 」".into();
+        let vd = vd_at(None);
+        // Applied after whitespace collapsing, so the decoration's own spaces survive.
+        assert_eq!(
+            overlay_lines(&cfg, "g", &vd),
+            ["「 Work 」 : first", "second"]
+        );
+        assert_eq!(
+            decorated_label(&cfg, "g", &vd),
+            (
+                "「 Work 」".to_string(),
+                vec!["first".to_string(), "second".to_string()]
+            )
+        );
+        // The fallback title is decorated too; the raw label is not.
+        assert_eq!(overlay_lines(&cfg, "x", &vd), ["「 Desktop 」 : "]);
+        assert_eq!(resolved_label(&cfg, "g", &vd).0, "Work");
+    }
+
+    #[test]
+    fn expand_placeholders_substitutes_index_and_count() {
+        assert_eq!(
+            expand_placeholders("Desktop {n}: Work", Some(2), Some(4)),
+            "Desktop 3: Work"
+        );
+        assert_eq!(expand_placeholders("{n}/{count}", Some(0), Some(4)), "1/4");
+        assert_eq!(
+            expand_placeholders("No placeholders", None, None),
+            "No placeholders"
+        );
+    }
+
+    #[test]
+    fn expand_placeholders_strips_missing_index() {
+        assert_eq!(
+            expand_placeholders("Desktop {n}: Work", None, None),
+            "Desktop: Work"
+        );
+        assert_eq!(expand_placeholders("{n} Work", None, None), "Work");
+        assert_eq!(expand_placeholders("Work {n}", None, None), "Work");
+        assert_eq!(expand_placeholders("Work ({n})", None, None), "Work ()");
+        assert_eq!(expand_placeholders("{n}", None, None), "");
+    }
+
+    #[test]
+    fn expand_placeholders_keeps_literal_braces() {
+        assert_eq!(
+            expand_placeholders("{{n}} is {n}", Some(0), Some(1)),
+            "{n} is 1"
+        );
+        assert_eq!(
+            expand_placeholders("{{count}}", Some(0), Some(1)),
+            "{count}"
+        );
+        assert_eq!(
+            expand_placeholders("{x} {N} {", Some(0), Some(1)),
+            "{x} {N} {"
+        );
+        assert_eq!(expand_placeholders("a{n", Some(0), Some(1)), "a{n");
+    }
+
+    #[test]
+    fn overlay_lines_expand_placeholders() {
+        let cfg = cfg_with("Desktop {n} of {count}", "on {n}");
+        assert_eq!(
+            overlay_lines(&cfg, "g", &vd_at(Some(1))),
+            ["Desktop 2 of 3 : on 2"]
+        );
+        assert_eq!(overlay_lines(&cfg, "g", &vd_at(None)), ["Desktop of : on"]);
+    }
+
+    #[test]
+    fn state_dump_lists_current_and_live_desktops() {
+        let mut cfg = cfg_with("Work {n}", "tickets");
+        cfg.desktops.insert("stale".into(), Default::default());
+        let live = vec!["a".to_string(), "g".to_string()];
+        let dump = state_dump(&cfg, "g", &live, &vd_at(Some(1)));
+        assert_eq!(dump["current"]["guid"], "g");
+        assert_eq!(dump["current"]["index"], 2);
+        assert_eq!(dump["current"]["title"], "Work 2");
+        assert_eq!(dump["current"]["description"], "tickets");
+        let desktops = dump["desktops"].as_array().unwrap();
+        assert_eq!(desktops.len(), 2);
+        assert_eq!(desktops[0]["guid"], "a");
+        assert_eq!(desktops[0]["title"], "");
+        assert_eq!(desktops[1]["title"], "Work {n}");
+        // Index-keyed labels are found by position.
+        let mut by_index = cfg.clone();
+        by_index.appearance.key_by_index = true;
+        let label = by_index.desktops.remove("g").unwrap();
+        by_index.desktops.insert("1".into(), label);
+        let dump = state_dump(&by_index, "g", &live, &vd_at(Some(1)));
+        assert_eq!(dump["current"]["title"], "Work 2");
+        assert_eq!(dump["desktops"][1]["title"], "Work {n}");
+        // Without a desktop index the fallback title is still resolved.
+        let dump = state_dump(&Config::default(), "x", &[], &vd_at(None));
+        assert_eq!(dump["current"]["index"], serde_json::Value::Null);
+        assert_eq!(dump["current"]["title"], "Desktop");
+    }
+
+    #[test]
+    fn labels_text_lists_labels_and_marks_the_current_desktop() {
+        let mut cfg = cfg_with("Mail", "inbox\n\nreplies");
+        cfg.desktops.insert(
+            "a".into(),
+            crate::config::DesktopLabel {
+                title: "Build".into(),
+                ..Default::default()
+            },
+        );
+        cfg.desktops.insert(
+            "old".into(),
+            crate::config::DesktopLabel {
+                description: "gone".into(),
+                ..Default::default()
+            },
+        );
+        let live = vec!["a".to_string(), "g".to_string()];
+        assert_eq!(
+            labels_text(&cfg, "g", &live),
+            "Build\n* Mail — inbox / replies [g]\n(untitled) — gone"
+        );
+        // An unlabeled current desktop still gets its marker line.
+        assert_eq!(
+            labels_text(&cfg, "x", &live),
+            "Build\nMail — inbox / replies\n(untitled) — gone\n* (untitled) [x]"
+        );
+        // Index keys are ordered and matched by position.
+        let mut by_index = Config::default();
+        by_index.appearance.key_by_index = true;
+        by_index
+            .desktops
+            .insert("1".into(), cfg.desktops["g"].clone());
+        by_index
+            .desktops
+            .insert("0".into(), cfg.desktops["a"].clone());
+        assert_eq!(
+            labels_text(&by_index, "g", &live),
+            "Build\n* Mail — inbox / replies [g]"
+        );
+    }
+
+    #[test]
+    fn format_preserves_whitespace_when_asked() {
+        let mut cfg = cfg_with("  A   B", "c  ");
+        cfg.preserve_whitespace = true;
+        assert_eq!(
+            format_overlay_line(&cfg, "g", &vd_at(None)),
+            "  A   B : c  "
+        );
+    }
+
+    #[test]
+    fn fade_steps_linearly_and_ends_on_target() {
+        assert_eq!(fade_level(0, 255, 0, 200), 0);
+        assert_eq!(fade_level(0, 255, 100, 200), 127);
+        assert_eq!(fade_level(255, 0, 100, 200), 128);
+        assert_eq!(fade_level(0, 255, 200, 200), 255);
+        assert_eq!(fade_level(255, 0, 500, 200), 0);
+        // A zero-length fade jumps straight to the target.
+        assert_eq!(fade_level(10, 0, 0, 0), 0);
+        // Reversing a half-finished fade takes half the time.
+        assert_eq!(fade_duration(200, 0, 255), 200);
+        assert_eq!(fade_duration(200, 255, 0), 200);
+        assert_eq!(fade_duration(200, 128, 0), 100);
+        assert_eq!(fade_duration(200, 40, 40), 0);
+    }
+
+    #[test]
+    fn timer_intervals_have_floors_and_poller_can_be_disabled() {
+        assert_eq!(poll_interval(DEFAULT_POLL_INTERVAL_MS), Some(250));
+        assert_eq!(poll_interval(2000), Some(2000));
+        assert_eq!(poll_interval(1), Some(50));
+        assert_eq!(poll_interval(0), None);
+        assert_eq!(visibility_interval(DEFAULT_VISIBILITY_INTERVAL_MS), 1000);
+        assert_eq!(visibility_interval(0), 100);
+        assert_eq!(visibility_interval(5000), 5000);
+    }
+
     #[test]
     fn center_calc_basic() {
         let (x, y) = calc_top_center((0, 0, 1000, 800), 200, 20, 10);
@@ -33,4 +1343,563 @@ mod tests {
         assert_eq!(x, 350);
         assert_eq!(y, 58);
     }
+
+    #[test]
+    fn parse_font_weight_names_and_numbers() {
+        assert_eq!(parse_font_weight("bold"), Some(700));
+        assert_eq!(parse_font_weight(" Normal "), Some(400));
+        assert_eq!(parse_font_weight("semi-bold"), Some(600));
+        assert_eq!(parse_font_weight("EXTRA_LIGHT"), Some(200));
+        assert_eq!(parse_font_weight("700"), Some(700));
+        assert_eq!(parse_font_weight("0"), None);
+        assert_eq!(parse_font_weight("1000"), None);
+        assert_eq!(parse_font_weight("-5"), None);
+        assert_eq!(parse_font_weight("chunky"), None);
+        assert_eq!(parse_font_weight(""), None);
+    }
+
+    #[test]
+    fn truncate_utf16_respects_unit_budget() {
+        assert_eq!(truncate_utf16("Work", 127), "Work");
+        assert_eq!(truncate_utf16("abcdef", 4), "abc…");
+        // "😀" is two UTF-16 units and must not be split.
+        assert_eq!(truncate_utf16("a😀b", 3), "a…");
+        assert_eq!(truncate_utf16("a😀b", 4), "a😀b");
+        let long = "x".repeat(300);
+        assert_eq!(truncate_utf16(&long, 127).encode_utf16().count(), 127);
+        assert_eq!(truncate_utf16("abc", 0), "");
+    }
+
+    #[test]
+    fn parse_hex_color_forms() {
+        let c = |r, g, b, a| Some(Rgba { r, g, b, a });
+        assert_eq!(parse_hex_color("#FFF"), c(255, 255, 255, 255));
+        assert_eq!(parse_hex_color("#1a2B3c"), c(0x1a, 0x2b, 0x3c, 255));
+        assert_eq!(parse_hex_color("00000059"), c(0, 0, 0, 0x59));
+        assert_eq!(parse_hex_color(" #f00 "), c(255, 0, 0, 255));
+        assert_eq!(parse_hex_color("#12345"), None);
+        assert_eq!(parse_hex_color("#GGGGGG"), None);
+        assert_eq!(parse_hex_color("#ééé"), None);
+        assert_eq!(parse_hex_color(""), None);
+    }
+
+    #[test]
+    fn appearance_colors_fall_back_on_bad_input() {
+        let mut ap = Appearance::default();
+        assert_eq!(appearance_colors(&ap), (None, None));
+        ap.text_color = Some("red".into());
+        ap.background_color = Some("#336699CC".into());
+        assert_eq!(
+            appearance_colors(&ap),
+            (
+                None,
+                Some(Rgba {
+                    r: 0x33,
+                    g: 0x66,
+                    b: 0x99,
+                    a: 0xCC
+                })
+            )
+        );
+    }
+
+    #[test]
+    fn desktop_background_overrides_only_when_valid() {
+        let mut cfg = cfg_with("Work", "");
+        assert_eq!(desktop_background(&cfg, "g"), None);
+        cfg.desktops.get_mut("g").unwrap().color = Some("#C00".into());
+        assert_eq!(
+            desktop_background(&cfg, "g"),
+            Some(Rgba {
+                r: 0xCC,
+                g: 0,
+                b: 0,
+                a: 255
+            })
+        );
+        cfg.desktops.get_mut("g").unwrap().color = Some("crimson".into());
+        assert_eq!(desktop_background(&cfg, "g"), None);
+        assert_eq!(desktop_background(&cfg, "missing"), None);
+    }
+
+    #[test]
+    fn anchor_positions() {
+        let work = (0, 0, 1000, 800);
+        let at = |a| anchor_position(a, work, 200, 40, 8, 0);
+        assert_eq!(at(OverlayAnchor::TopCenter), (400, 8));
+        assert_eq!(at(OverlayAnchor::TopLeft), (8, 8));
+        assert_eq!(at(OverlayAnchor::TopRight), (792, 8));
+        assert_eq!(at(OverlayAnchor::BottomCenter), (400, 752));
+        // Stacking offset moves bottom overlays up.
+        assert_eq!(
+            anchor_position(OverlayAnchor::BottomCenter, work, 200, 40, 8, 40),
+            (400, 712)
+        );
+    }
+
+    #[test]
+    fn taskbar_dock_positions() {
+        // 1920x1080 screen; a 48px taskbar on each edge in turn.
+        let dock = |edge, tb| taskbar_dock_position(edge, tb, 200, 40, 0);
+        assert_eq!(dock(TaskbarEdge::Bottom, (0, 1032, 1920, 1080)), (860, 992));
+        assert_eq!(dock(TaskbarEdge::Top, (0, 0, 1920, 48)), (860, 48));
+        assert_eq!(dock(TaskbarEdge::Left, (0, 0, 48, 1080)), (48, 520));
+        assert_eq!(dock(TaskbarEdge::Right, (1872, 0, 1920, 1080)), (1672, 520));
+        // Stacked instances move away from the taskbar.
+        assert_eq!(
+            taskbar_dock_position(TaskbarEdge::Bottom, (0, 1032, 1920, 1080), 200, 40, 40),
+            (860, 952)
+        );
+        // Without a taskbar the anchor falls back to top-center.
+        assert_eq!(
+            anchor_position(OverlayAnchor::Taskbar, (0, 0, 1000, 800), 200, 40, 8, 0),
+            (400, 8)
+        );
+    }
+
+    #[test]
+    fn stacked_size_adds_rows_and_takes_widest() {
+        // Title only: just the padding around it.
+        assert_eq!(stacked_size((120, 22), None, 8), (136, 38));
+        // A narrower description adds only height.
+        assert_eq!(stacked_size((120, 22), Some((80, 17)), 8), (136, 55));
+        // A wider one sets the width.
+        assert_eq!(stacked_size((120, 22), Some((300, 34)), 8), (316, 72));
+        assert_eq!(stacked_size((120, 22), Some((0, 0)), 0), (120, 22));
+    }
+
+    #[test]
+    fn desktop_font_size_overrides_global() {
+        let mut cfg = cfg_with("Media", "");
+        cfg.appearance.font_size_dip = 18;
+        assert_eq!(desktop_font_size(&cfg, "g"), 18);
+        cfg.desktops.get_mut("g").unwrap().font_size_dip = Some(12);
+        assert_eq!(desktop_font_size(&cfg, "g"), 12);
+        // Zero is ignored; other desktops keep the global size.
+        cfg.desktops.get_mut("g").unwrap().font_size_dip = Some(0);
+        assert_eq!(desktop_font_size(&cfg, "g"), 18);
+        assert_eq!(desktop_font_size(&cfg, "unlabeled"), 18);
+    }
+
+    #[test]
+    fn corner_radius_defaults_and_limits() {
+        // Derived from the font size, within 6–20.
+        assert_eq!(corner_radius(None, 16, 200, 40), 8);
+        assert_eq!(corner_radius(None, 8, 200, 40), 6);
+        assert_eq!(corner_radius(None, 60, 400, 100), 20);
+        // Configured values win; 0 keeps square corners.
+        assert_eq!(corner_radius(Some(0), 16, 200, 40), 0);
+        assert_eq!(corner_radius(Some(12), 16, 200, 40), 12);
+        // Never past a full pill.
+        assert_eq!(corner_radius(Some(100), 16, 200, 40), 20);
+        assert_eq!(corner_radius(Some(-3), 16, 200, 40), 0);
+    }
+
+    #[test]
+    fn text_shadow_is_off_by_default_and_falls_back_on_bad_values() {
+        let mut a = crate::config::Appearance::default();
+        assert_eq!(text_shadow(&a), None);
+        a.text_shadow = true;
+        assert_eq!(text_shadow(&a), Some((DEFAULT_TEXT_SHADOW, 2)));
+        a.text_shadow_color = Some("#102030".into());
+        a.text_shadow_offset_px = Some(3);
+        let navy = Rgba {
+            r: 0x10,
+            g: 0x20,
+            b: 0x30,
+            a: 255,
+        };
+        assert_eq!(text_shadow(&a), Some((navy, 3)));
+        a.text_shadow_color = Some("shadowy".into());
+        a.text_shadow_offset_px = Some(0);
+        assert_eq!(text_shadow(&a), Some((DEFAULT_TEXT_SHADOW, 1)));
+        a.text_shadow_offset_px = Some(500);
+        assert_eq!(text_shadow(&a).map(|(_, offset)| offset), Some(10));
+    }
+
+    #[test]
+    fn scale_dip_converts_to_physical_pixels() {
+        assert_eq!(scale_dip(16, 96), 16);
+        assert_eq!(scale_dip(16, 120), 20);
+        assert_eq!(scale_dip(16, 144), 24);
+        assert_eq!(scale_dip(8, 144), 12);
+        // Rounds to the nearest pixel, away from zero on ties.
+        assert_eq!(scale_dip(5, 120), 6);
+        assert_eq!(scale_dip(-5, 120), -6);
+        assert_eq!(scale_dip(7, 120), 9);
+        assert_eq!(scale_dip(0, 144), 0);
+        assert_eq!(scale_dip(16, 0), 16);
+    }
+
+    #[test]
+    fn nudges_move_the_overlay_but_keep_part_of_it_on_screen() {
+        let work = (1920, 0, 3840, 1040);
+        // No nudge, no change; negative values move left/up.
+        assert_eq!(nudge_within((2800, 8), (300, 40), (0, 0), work), (2800, 8));
+        assert_eq!(
+            nudge_within((2800, 8), (300, 40), (-400, 60), work),
+            (2400, 68)
+        );
+        // Pushed past the right edge: 32 px stay visible.
+        assert_eq!(
+            nudge_within((2800, 8), (300, 40), (5000, 0), work),
+            (3808, 8)
+        );
+        // Pushed past the top and left edges.
+        assert_eq!(
+            nudge_within((2800, 8), (300, 40), (-5000, -500), work),
+            (1920 - 300 + 32, -8)
+        );
+        // A window smaller than the minimum stays wholly on screen.
+        assert_eq!(
+            nudge_within((100, 100), (20, 10), (0, 5000), (0, 0, 800, 600)),
+            (100, 590)
+        );
+    }
+
+    #[test]
+    fn description_scale_is_clamped() {
+        assert_eq!(description_scale(0.75), 0.75);
+        assert_eq!(description_scale(0.1), 0.3);
+        assert_eq!(description_scale(2.0), 1.0);
+        assert_eq!(description_scale(f32::NAN), DEFAULT_DESCRIPTION_SCALE);
+    }
+
+    #[test]
+    fn mirrors_cover_every_other_monitor() {
+        // Main overlay on the primary (index 1 of 3).
+        assert_eq!(mirror_monitors(3, None, Some(1)), vec![0, 2]);
+        // An attached `monitor_index` wins over the primary.
+        assert_eq!(mirror_monitors(3, Some(2), Some(1)), vec![0, 1]);
+        // A detached one falls back to the primary.
+        assert_eq!(mirror_monitors(3, Some(5), Some(0)), vec![1, 2]);
+        assert_eq!(mirror_monitors(1, None, Some(0)), Vec::<usize>::new());
+        assert_eq!(mirror_monitors(0, None, None), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn removal_falls_back_to_a_neighbor() {
+        assert_eq!(removal_fallback(0, 1), None);
+        assert_eq!(removal_fallback(0, 0), None);
+        assert_eq!(removal_fallback(0, 3), Some(1));
+        assert_eq!(removal_fallback(2, 3), Some(1));
+        assert_eq!(removal_fallback(1, 2), Some(0));
+        assert_eq!(removal_fallback(3, 3), None);
+    }
+
+    #[test]
+    fn anchor_cycle_wraps() {
+        let mut a = OverlayAnchor::TopCenter;
+        let mut seen = vec![a];
+        for _ in 0..5 {
+            a = a.next();
+            seen.push(a);
+        }
+        assert_eq!(
+            seen,
+            [
+                OverlayAnchor::TopCenter,
+                OverlayAnchor::TopLeft,
+                OverlayAnchor::TopRight,
+                OverlayAnchor::BottomCenter,
+                OverlayAnchor::Taskbar,
+                OverlayAnchor::TopCenter
+            ]
+        );
+    }
+
+    #[test]
+    fn place_dialog_variants() {
+        let work = (0, 0, 1920, 1040);
+        let overlay = (800, 0, 1120, 40);
+        let (x, y) = place_dialog(DialogPlacement::Centered, (0, 0), overlay, work, 420, 140);
+        assert_eq!((x, y), (750, 450)); // middle of the overlay's work area
+        // On a secondary monitor the dialog follows the overlay there.
+        let right = (1920, 0, 4480, 1392);
+        let (x, y) = place_dialog(
+            DialogPlacement::Centered,
+            (0, 0),
+            (3000, 0, 3400, 40),
+            right,
+            420,
+            140,
+        );
+        assert_eq!((x, y), (2990, 626));
+        let (x, y) = place_dialog(
+            DialogPlacement::NearOverlay,
+            (0, 0),
+            overlay,
+            work,
+            420,
+            140,
+        );
+        assert_eq!((x, y), (750, 52));
+        let (x, y) = place_dialog(
+            DialogPlacement::NearCursor,
+            (100, 200),
+            overlay,
+            work,
+            420,
+            140,
+        );
+        assert_eq!((x, y), (112, 212));
+    }
+
+    #[test]
+    fn place_dialog_stays_on_work_area() {
+        let work = (1920, 0, 3840, 1040);
+        let (x, y) = place_dialog(
+            DialogPlacement::NearCursor,
+            (3800, 1030),
+            (0, 0, 0, 0),
+            work,
+            420,
+            140,
+        );
+        assert_eq!((x, y), (3420, 900));
+    }
+
+    #[test]
+    fn contrast_extremes() {
+        let white = relative_luminance(255, 255, 255);
+        let black = relative_luminance(0, 0, 0);
+        assert!((contrast_ratio(white, black) - 21.0).abs() < 0.01);
+        assert!((contrast_ratio(black, black) - 1.0).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn auto_contrast_picks_text_and_boxes_mid_tones() {
+        let white = Rgba {
+            r: 255,
+            g: 255,
+            b: 255,
+            a: 255,
+        };
+        let black = Rgba {
+            r: 0,
+            g: 0,
+            b: 0,
+            a: 255,
+        };
+        let clear = Rgba { a: 0, ..black };
+        assert_eq!(pick_text_color(relative_luminance(10, 20, 40)), white);
+        assert_eq!(pick_text_color(relative_luminance(250, 240, 200)), black);
+        // Readable as is: the configured (here transparent) background stays.
+        let dark = relative_luminance(10, 20, 40);
+        assert_eq!(auto_contrast_colors(dark, 4.5, clear), (white, clear));
+        // Mid-gray misses 7:1 with either color, so a box in the opposite color appears.
+        let mid = relative_luminance(119, 119, 119);
+        let (text, bg) = auto_contrast_colors(mid, 7.0, clear);
+        assert_eq!((bg.r, bg.a), (255 - text.r, 180));
+        // An already solid box is left alone.
+        let solid = Rgba { a: 230, ..black };
+        assert_eq!(auto_contrast_colors(mid, 7.0, solid).1, solid);
+    }
+
+    #[test]
+    fn average_luminance_of_samples() {
+        assert_eq!(average_luminance(&[]), None);
+        let avg = average_luminance(&[(0, 0, 0), (255, 255, 255)]).unwrap();
+        assert!((avg - 0.5).abs() < 0.001);
+    }
+
+    #[test]
+    fn contrast_samples_surround_the_overlay_on_its_monitor() {
+        let monitor = (0, 0, 1920, 1080);
+        let overlay = (800, 100, 1120, 140);
+        let points = contrast_sample_points(overlay, monitor);
+        assert!(!points.is_empty());
+        let inside = |(x, y): (i32, i32), (l, t, r, b): (i32, i32, i32, i32)| {
+            x >= l && x < r && y >= t && y < b
+        };
+        assert!(
+            points
+                .iter()
+                .all(|&p| !inside(p, overlay) && inside(p, monitor))
+        );
+        // Flush against the top of the monitor: nothing is sampled off-screen.
+        let top = contrast_sample_points((800, 0, 1120, 40), monitor);
+        assert!(top.iter().all(|&(_, y)| y >= 0));
+        assert!(top.len() < points.len());
+    }
+
+    #[test]
+    fn pick_contrast_uses_backdrop_on_mid_tones() {
+        let dark = pick_contrast(relative_luminance(20, 20, 30), 4.5);
+        assert!(dark.white_text && !dark.needs_backdrop);
+        let light = pick_contrast(relative_luminance(240, 240, 230), 4.5);
+        assert!(!light.white_text && !light.needs_backdrop);
+        // Mid-grey reaches only ~4.6:1 against black; a stricter minimum forces the box.
+        let mid = pick_contrast(relative_luminance(119, 119, 119), 7.0);
+        assert!(mid.needs_backdrop);
+    }
+
+    #[test]
+    fn theme_colors_follow_mode() {
+        let (fg, bg) = theme_colors(false, false);
+        assert_eq!((fg.r, bg.r, bg.a), (255, 0, 0x59));
+        let (fg, bg) = theme_colors(true, false);
+        assert!(fg.r < 0x40 && bg.r == 255);
+        // High contrast wins over the light/dark setting and is fully opaque.
+        assert_eq!(theme_colors(true, true), theme_colors(false, true));
+        assert_eq!(theme_colors(true, true).1.a, 255);
+    }
+
+    #[test]
+    fn explicit_colors_beat_auto_theme() {
+        let mut ap = Appearance::default();
+        assert_eq!(
+            effective_colors(&ap, true, false),
+            theme_colors(true, false)
+        );
+        ap.text_color = Some("#FF0000".into());
+        let (fg, bg) = effective_colors(&ap, true, false);
+        assert_eq!((fg.r, fg.g), (255, 0));
+        assert_eq!(bg, theme_colors(true, false).1);
+        // With auto_theme off, unset colors are the original white on translucent black.
+        ap.auto_theme = false;
+        assert_eq!(
+            effective_colors(&ap, true, true),
+            (fg, theme_colors(false, false).1)
+        );
+    }
+
+    #[test]
+    fn high_contrast_fills_unset_colors_only() {
+        let mut ap = Appearance::default();
+        let hc = theme_colors(false, true);
+        assert_eq!(effective_colors(&ap, true, true), hc);
+        assert_eq!(effective_colors(&ap, false, true), hc);
+        // An explicit background still wins; an invalid text color follows the theme.
+        ap.background_color = Some("#102030".into());
+        ap.text_color = Some("nope".into());
+        let (fg, bg) = effective_colors(&ap, true, true);
+        assert_eq!(fg, hc.0);
+        assert_eq!((bg.r, bg.g, bg.b, bg.a), (0x10, 0x20, 0x30, 255));
+    }
+
+    #[test]
+    fn ellipsize_fits_budget() {
+        let width = |s: &str| s.chars().count() as i32 * 10;
+        assert_eq!(ellipsize("Build", 50, width), "Build");
+        assert_eq!(ellipsize("Build server", 60, width), "Build…");
+        // Trailing spaces before the ellipsis are dropped.
+        assert_eq!(ellipsize("Build server", 70, width), "Build…");
+        assert_eq!(ellipsize("Build", 5, width), "…");
+        assert_eq!(ellipsize("", 0, width), "");
+    }
+
+    #[test]
+    fn ellipsize_keeps_emoji_sequences_whole() {
+        // One unit per char, like a naive measure; the cut must still land between clusters.
+        let width = |s: &str| s.chars().count() as i32;
+        let dev = "👩\u{200D}💻"; // woman technologist: three chars, one glyph
+        for budget in 1..10 {
+            let out = ellipsize(&format!("🎮 {dev} {dev} games"), budget, width);
+            let head = out.trim_end_matches('…');
+            assert!(!head.ends_with('\u{200D}'), "{out:?}");
+            assert_eq!(
+                head.matches('👩').count(),
+                head.matches('💻').count(),
+                "{out:?}"
+            );
+        }
+        // Flags are pairs of regional indicators.
+        let flags = "🇯🇵🇩🇪🇫🇷";
+        for budget in 1..7 {
+            let out = ellipsize(flags, budget, width);
+            assert_eq!(out.trim_end_matches('…').chars().count() % 2, 0, "{out:?}");
+        }
+        // Accents stay on their letter; variation selectors stay on their emoji.
+        assert_eq!(ellipsize("Cafe\u{301} au lait", 6, width), "Cafe\u{301}…");
+        assert_eq!(ellipsize("ab❤\u{FE0F}cd", 5, width), "ab❤\u{FE0F}…");
+    }
+
+    #[test]
+    fn ellipsize_reserves_room_for_hints() {
+        // The hints trail the line, so they count against the same budget.
+        let hints = " (Ctrl+Alt+T, Ctrl+Alt+D)";
+        let width = |s: &str| (s.chars().count() + hints.chars().count()) as i32;
+        let out = ellipsize("https://example.com/a/very/long/path", 40, width);
+        assert!(width(&out) <= 40);
+        assert!(out.ends_with('…'));
+        assert!(out.starts_with("https://"));
+    }
+
+    #[test]
+    fn clamp_label_counts_chars() {
+        assert_eq!(clamp_label("Build", 64), "Build");
+        assert_eq!(clamp_label("https://example.com/x", 5), "https");
+        assert_eq!(clamp_label("äöü€", 3), "äöü");
+        assert_eq!(clamp_label("abc", 0), "");
+    }
+
+    #[test]
+    fn pick_monitor_falls_back_to_primary() {
+        assert_eq!(pick_monitor(None, 2), None);
+        assert_eq!(pick_monitor(Some(1), 2), Some(1));
+        // Disconnected (or never attached) monitor.
+        assert_eq!(pick_monitor(Some(2), 2), None);
+        assert_eq!(pick_monitor(Some(0), 0), None);
+    }
+
+    #[test]
+    fn instances_are_told_apart_by_config_and_placement() {
+        assert_eq!(
+            instance_window_title(std::path::Path::new("C:\\Users\\Me\\labels.json")),
+            instance_window_title(std::path::Path::new("c:\\users\\me\\LABELS.json"))
+        );
+        assert_ne!(
+            instance_window_title(std::path::Path::new("C:\\a\\labels.json")),
+            instance_window_title(std::path::Path::new("D:\\b\\labels.json"))
+        );
+        let anchors = [
+            OverlayAnchor::TopCenter,
+            OverlayAnchor::TopLeft,
+            OverlayAnchor::TopRight,
+            OverlayAnchor::BottomCenter,
+            OverlayAnchor::Taskbar,
+        ];
+        let mut tags = Vec::new();
+        for monitor in [None, Some(0), Some(1)] {
+            for anchor in anchors {
+                tags.push(placement_tag(anchor, monitor));
+            }
+        }
+        assert!(tags.iter().all(|&t| t != 0));
+        let mut unique = tags.clone();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(unique.len(), tags.len());
+    }
+
+    #[test]
+    fn instance_offset_stacks_by_slot() {
+        assert_eq!(instance_offset(0, 40), 0);
+        assert_eq!(instance_offset(2, 40), 80);
+        assert_eq!(instance_offset(3, -10), 0);
+    }
+
+    #[test]
+    fn badge_text_modes() {
+        assert_eq!(badge_text(TrayBadge::None, Some(2), "Build"), None);
+        assert_eq!(
+            badge_text(TrayBadge::Index, Some(2), "Build"),
+            Some("3".into())
+        );
+        assert_eq!(badge_text(TrayBadge::Index, None, "Build"), None);
+        assert_eq!(
+            badge_text(TrayBadge::FirstLetter, Some(2), "  build"),
+            Some("B".into())
+        );
+    }
+
+    #[test]
+    fn badge_first_letter_falls_back_to_index() {
+        assert_eq!(
+            badge_text(TrayBadge::FirstLetter, Some(0), "   "),
+            Some("1".into())
+        );
+        assert_eq!(badge_text(TrayBadge::FirstLetter, None, ""), None);
+    }
 }
@@ -0,0 +1,181 @@
+use anyhow::{Context, Result, bail};
+use std::process::{Command, ExitStatus, Stdio};
+use std::time::{Duration, Instant};
+
+/// Environment passed to the `on_switch_command` hook.
+pub fn switch_env(index: Option<usize>, guid: &str, title: &str) -> Vec<(&'static str, String)> {
+    vec![
+        // 1-based to match the tray badge; empty when the index is unknown.
+        (
+            "MDDSK_INDEX",
+            index.map(|i| (i + 1).to_string()).unwrap_or_default(),
+        ),
+        ("MDDSK_GUID", guid.to_string()),
+        ("MDDSK_TITLE", title.to_string()),
+    ]
+}
+
+fn shell_command(cmdline: &str) -> Command {
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        let mut c = Command::new("cmd");
+        c.arg("/C").arg(cmdline).creation_flags(CREATE_NO_WINDOW);
+        c
+    }
+    #[cfg(not(windows))]
+    {
+        let mut c = Command::new("sh");
+        c.arg("-c").arg(cmdline);
+        c
+    }
+}
+
+/// A Windows job object holding the hook's process tree, so a timeout ends everything the
+/// hook started and not just the `cmd.exe` wrapper. Processes the shell spawns before the
+/// child is assigned (a few milliseconds) can escape it.
+#[cfg(windows)]
+struct ProcessTree(windows::Win32::Foundation::HANDLE);
+
+#[cfg(windows)]
+impl ProcessTree {
+    /// A job with `child` in it that kills its processes when closed; `None` (logged) if the
+    /// job can't be set up, in which case only the child itself can be killed.
+    fn new(child: &std::process::Child) -> Option<Self> {
+        use std::os::windows::io::AsRawHandle;
+        use windows::Win32::Foundation::HANDLE;
+        use windows::Win32::System::JobObjects::AssignProcessToJobObject;
+        let tree =
+            Self(unsafe { windows::Win32::System::JobObjects::CreateJobObjectW(None, None) }.ok()?);
+        let assigned = tree.set_kill_on_close(true).and_then(|()| unsafe {
+            AssignProcessToJobObject(tree.0, HANDLE(child.as_raw_handle()))
+        });
+        match assigned {
+            Ok(()) => Some(tree),
+            Err(e) => {
+                tracing::warn!(error=?e, "switch hook: no job object; a timeout kills only the shell");
+                None
+            }
+        }
+    }
+
+    fn set_kill_on_close(&self, on: bool) -> windows::core::Result<()> {
+        use windows::Win32::System::JobObjects::*;
+        let mut info = JOBOBJECT_EXTENDED_LIMIT_INFORMATION::default();
+        if on {
+            info.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+        }
+        unsafe {
+            SetInformationJobObject(
+                self.0,
+                JobObjectExtendedLimitInformation,
+                &info as *const _ as *const core::ffi::c_void,
+                size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+            )
+        }
+    }
+
+    /// End every process in the tree.
+    fn kill(&self) {
+        unsafe {
+            let _ = windows::Win32::System::JobObjects::TerminateJobObject(self.0, 1);
+        }
+    }
+
+    /// The hook finished on its own: let anything it deliberately left running (e.g. via
+    /// `start`) outlive the job.
+    fn release(self) {
+        let _ = self.set_kill_on_close(false);
+    }
+}
+
+#[cfg(windows)]
+impl Drop for ProcessTree {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = windows::Win32::Foundation::CloseHandle(self.0);
+        }
+    }
+}
+
+/// Run `cmdline` through the shell and wait for it, killing it (on Windows, with everything it
+/// started) after `timeout`.
+pub fn run_with_timeout(
+    cmdline: &str,
+    env: &[(&str, String)],
+    timeout: Duration,
+) -> Result<ExitStatus> {
+    let mut child = shell_command(cmdline)
+        .envs(env.iter().map(|(k, v)| (*k, v.as_str())))
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| format!("spawn {cmdline:?}"))?;
+    #[cfg(windows)]
+    let tree = ProcessTree::new(&child);
+    let start = Instant::now();
+    loop {
+        if let Some(status) = child.try_wait()? {
+            #[cfg(windows)]
+            if let Some(tree) = tree {
+                tree.release();
+            }
+            return Ok(status);
+        }
+        if start.elapsed() >= timeout {
+            #[cfg(windows)]
+            if let Some(tree) = &tree {
+                tree.kill();
+            }
+            let _ = child.kill();
+            let _ = child.wait();
+            bail!("timed out after {} ms", timeout.as_millis());
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+}
+
+/// Fire the switch hook on a background thread; failures are only logged.
+pub fn spawn_switch_hook(cmdline: String, env: Vec<(&'static str, String)>, timeout: Duration) {
+    let spawned = std::thread::Builder::new()
+        .name("switch-hook".into())
+        .spawn(move || match run_with_timeout(&cmdline, &env, timeout) {
+            Ok(status) if status.success() => tracing::debug!(cmd=%cmdline, "switch hook ok"),
+            Ok(status) => tracing::warn!(cmd=%cmdline, ?status, "switch hook failed"),
+            Err(e) => tracing::warn!(cmd=%cmdline, error=?e, "switch hook error"),
+        });
+    if let Err(e) = spawned {
+        tracing::warn!(error=?e, "spawn switch hook thread failed");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn env_uses_one_based_index() {
+        let env = switch_env(Some(0), "g", "Work");
+        assert_eq!(env[0], ("MDDSK_INDEX", "1".to_string()));
+        assert_eq!(env[1], ("MDDSK_GUID", "g".to_string()));
+        assert_eq!(env[2], ("MDDSK_TITLE", "Work".to_string()));
+        assert_eq!(switch_env(None, "g", "")[0].1, "");
+    }
+
+    #[test]
+    fn reports_exit_status() {
+        let ok = run_with_timeout("exit 0", &[], Duration::from_secs(10)).unwrap();
+        assert!(ok.success());
+        let bad = run_with_timeout("exit 3", &[], Duration::from_secs(10)).unwrap();
+        assert_eq!(bad.code(), Some(3));
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn kills_on_timeout() {
+        let err = run_with_timeout("sleep 5", &[], Duration::from_millis(100));
+        assert!(err.is_err());
+    }
+}
@@ -1,3 +1,4 @@
+use crate::config::{Hotkeys, KeyChord};
 use anyhow::Result;
 #[cfg(windows)]
 use windows::Win32::Foundation::HWND;
@@ -6,16 +7,133 @@ use windows::Win32::UI::Input::KeyboardAndMouse::{
     HOT_KEY_MODIFIERS, MOD_ALT, MOD_CONTROL, MOD_SHIFT, RegisterHotKey, UnregisterHotKey,
 };
 
-#[derive(Debug, Clone, Copy)]
-pub struct Vk(pub u32);
+/// Virtual-key code for a config key name, case-insensitive: letters, digits, `F1`–`F24`,
+/// arrows (`Left`, `Up`, ...), `Space`, `Esc`, `Num0`–`Num9` and a few editing keys.
+/// `None` for anything else.
+pub fn vk_from_str(key: &str) -> Option<u16> {
+    let key = key.trim().to_ascii_uppercase();
+    let mut chars = key.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return c.is_ascii_alphanumeric().then_some(c as u16);
+    }
+    if let Some(n) = key.strip_prefix('F').and_then(|n| n.parse::<u16>().ok()) {
+        return (1..=24).contains(&n).then_some(0x70 + n - 1);
+    }
+    let numpad = key
+        .strip_prefix("NUMPAD")
+        .or_else(|| key.strip_prefix("NUM"));
+    if let Some(n) = numpad.and_then(|n| n.parse::<u16>().ok()) {
+        return (n <= 9).then_some(0x60 + n);
+    }
+    let vk = match key.as_str() {
+        "SPACE" => 0x20,
+        "ESC" | "ESCAPE" => 0x1B,
+        "TAB" => 0x09,
+        "ENTER" | "RETURN" => 0x0D,
+        "BACKSPACE" => 0x08,
+        "LEFT" => 0x25,
+        "UP" => 0x26,
+        "RIGHT" => 0x27,
+        "DOWN" => 0x28,
+        "PAGEUP" | "PGUP" => 0x21,
+        "PAGEDOWN" | "PGDN" => 0x22,
+        "END" => 0x23,
+        "HOME" => 0x24,
+        "INSERT" | "INS" => 0x2D,
+        "DELETE" | "DEL" => 0x2E,
+        _ => return None,
+    };
+    Some(vk)
+}
 
-pub fn vk_from_char(c: &str) -> Vk {
-    // Accept single ASCII letter/digit; fall back to VK_BACK as harmless default.
-    let up = c.trim().to_uppercase();
-    let code = up.bytes().next().unwrap_or(b'B') as u32;
-    Vk(code)
+/// Config key name for a virtual-key code, the inverse of [`vk_from_str`]. `None` for keys a
+/// chord can't use, including the modifiers themselves.
+pub fn key_name(vk: u16) -> Option<String> {
+    let name = match vk {
+        0x30..=0x39 | 0x41..=0x5A => return Some(char::from(vk as u8).to_string()),
+        0x70..=0x87 => return Some(format!("F{}", vk - 0x70 + 1)),
+        0x60..=0x69 => return Some(format!("Num{}", vk - 0x60)),
+        0x20 => "Space",
+        0x1B => "Esc",
+        0x09 => "Tab",
+        0x0D => "Enter",
+        0x08 => "Backspace",
+        0x25 => "Left",
+        0x26 => "Up",
+        0x27 => "Right",
+        0x28 => "Down",
+        0x21 => "PageUp",
+        0x22 => "PageDown",
+        0x23 => "End",
+        0x24 => "Home",
+        0x2D => "Insert",
+        0x2E => "Delete",
+        _ => return None,
+    };
+    Some(name.to_string())
 }
 
+/// Chord for a key pressed while capturing a new binding. `None` without Ctrl, Alt or Shift
+/// (a bare key would swallow normal typing) or when the key has no config name.
+pub fn captured_chord(vk: u16, ctrl: bool, alt: bool, shift: bool) -> Option<KeyChord> {
+    if !(ctrl || alt || shift) {
+        return None;
+    }
+    Some(KeyChord {
+        ctrl,
+        alt,
+        shift,
+        key: key_name(vk)?,
+    })
+}
+
+/// The actions the Edit Hotkeys dialog can rebind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RebindAction {
+    EditTitle,
+    EditDescription,
+    ToggleOverlay,
+    SnapPosition,
+}
+
+impl RebindAction {
+    pub const ALL: [RebindAction; 4] = [
+        RebindAction::EditTitle,
+        RebindAction::EditDescription,
+        RebindAction::ToggleOverlay,
+        RebindAction::SnapPosition,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            RebindAction::EditTitle => "Edit Title",
+            RebindAction::EditDescription => "Edit Description",
+            RebindAction::ToggleOverlay => "Toggle Overlay",
+            RebindAction::SnapPosition => "Snap Position",
+        }
+    }
+
+    pub fn chord(self, hk: &Hotkeys) -> &KeyChord {
+        match self {
+            RebindAction::EditTitle => &hk.edit_title,
+            RebindAction::EditDescription => &hk.edit_description,
+            RebindAction::ToggleOverlay => &hk.toggle_overlay,
+            RebindAction::SnapPosition => &hk.snap_position,
+        }
+    }
+
+    pub fn chord_mut(self, hk: &mut Hotkeys) -> &mut KeyChord {
+        match self {
+            RebindAction::EditTitle => &mut hk.edit_title,
+            RebindAction::EditDescription => &mut hk.edit_description,
+            RebindAction::ToggleOverlay => &mut hk.toggle_overlay,
+            RebindAction::SnapPosition => &mut hk.snap_position,
+        }
+    }
+}
+
+/// Register `key` with the given modifiers under `id`. `Ok(false)` when the key name is not
+/// recognized (see `vk_from_str`) or the OS refuses the chord.
 #[cfg(windows)]
 pub fn register(
     hwnd: HWND,
@@ -35,8 +153,11 @@ pub fn register(
     if shift {
         mods |= MOD_SHIFT;
     }
-    let Vk(vk) = vk_from_char(key);
-    let res = unsafe { RegisterHotKey(hwnd, id, mods, vk) };
+    let Some(vk) = vk_from_str(key) else {
+        tracing::warn!(key, id, "unrecognized hotkey key name");
+        return Ok(false);
+    };
+    let res = unsafe { RegisterHotKey(hwnd, id, mods, vk as u32) };
     Ok(res.is_ok())
 }
 
@@ -46,10 +167,111 @@ pub fn register(
     _ctrl: bool,
     _alt: bool,
     _shift: bool,
-    _key: &str,
+    key: &str,
     _id: i32,
 ) -> Result<bool> {
-    Ok(true)
+    Ok(vk_from_str(key).is_some())
+}
+
+/// Why a chord could not be registered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegisterFailure {
+    /// The key name is not recognized (see `vk_from_str`).
+    UnknownKey,
+    /// Refused, and [`is_probably_reserved`] says Windows keeps the chord for itself.
+    Reserved,
+    /// Refused, most likely because another program already owns the chord.
+    InUse,
+}
+
+/// Outcome of registering one configured chord, named for messages to the user.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Registration {
+    pub name: &'static str,
+    pub chord: KeyChord,
+    pub result: std::result::Result<(), RegisterFailure>,
+}
+
+impl Registration {
+    pub fn ok(&self) -> bool {
+        self.result.is_ok()
+    }
+}
+
+/// Heuristic for chords Windows keeps for itself (Ctrl+Alt+Del, Ctrl+Shift+Esc, Alt+Tab,
+/// Ctrl+Esc, Alt+Esc, and F12, which is reserved for the debugger). Registering them fails
+/// or breaks the system shortcut, so they are flagged before trying.
+pub fn is_probably_reserved(chord: &KeyChord) -> bool {
+    let key = chord.key.trim().to_ascii_uppercase();
+    let key = match key.as_str() {
+        "DEL" => "DELETE",
+        "ESCAPE" => "ESC",
+        other => other,
+    };
+    match key {
+        "DELETE" => chord.ctrl && chord.alt,
+        "ESC" => chord.ctrl || chord.alt,
+        "TAB" => chord.alt,
+        "F12" => true,
+        _ => false,
+    }
+}
+
+/// Register `chord` under `id`, reporting which chord failed and why.
+#[cfg(windows)]
+pub fn register_chord(hwnd: HWND, name: &'static str, chord: &KeyChord, id: i32) -> Registration {
+    let reserved = is_probably_reserved(chord);
+    if reserved {
+        tracing::warn!(name, chord = %format_chord(chord), "hotkey is probably reserved by Windows");
+    }
+    let result = if vk_from_str(&chord.key).is_none() {
+        Err(RegisterFailure::UnknownKey)
+    } else if register(hwnd, chord.ctrl, chord.alt, chord.shift, &chord.key, id).unwrap_or(false) {
+        Ok(())
+    } else if reserved {
+        Err(RegisterFailure::Reserved)
+    } else {
+        Err(RegisterFailure::InUse)
+    };
+    Registration {
+        name,
+        chord: chord.clone(),
+        result,
+    }
+}
+
+/// Without an OS to ask, reserved chords are reported as refused.
+#[cfg(not(windows))]
+pub fn register_chord(_hwnd: (), name: &'static str, chord: &KeyChord, _id: i32) -> Registration {
+    let result = if vk_from_str(&chord.key).is_none() {
+        Err(RegisterFailure::UnknownKey)
+    } else if is_probably_reserved(chord) {
+        Err(RegisterFailure::Reserved)
+    } else {
+        Ok(())
+    };
+    Registration {
+        name,
+        chord: chord.clone(),
+        result,
+    }
+}
+
+/// Balloon text naming each chord that failed, e.g. `Edit Title (Ctrl+Alt+T) failed`;
+/// `None` when everything registered.
+pub fn failure_summary(results: &[Registration]) -> Option<String> {
+    let failures: Vec<String> = results
+        .iter()
+        .filter_map(|r| {
+            let why = match r.result.err()? {
+                RegisterFailure::UnknownKey => "has an unknown key",
+                RegisterFailure::Reserved => "is reserved by Windows",
+                RegisterFailure::InUse => "failed",
+            };
+            Some(format!("{} ({}) {}", r.name, format_chord(&r.chord), why))
+        })
+        .collect();
+    (!failures.is_empty()).then(|| failures.join("; "))
 }
 
 #[cfg(windows)]
@@ -66,20 +288,153 @@ pub const HK_EDIT_TITLE: i32 = 1;
 pub const HK_EDIT_DESC: i32 = 2;
 pub const HK_TOGGLE: i32 = 3;
 pub const HK_SNAP: i32 = 4;
+pub const HK_TOGGLE_AUTORUN: i32 = 5;
+pub const HK_EDIT_BOTH: i32 = 6;
+pub const HK_TOGGLE_DESKTOP_HIDDEN: i32 = 7;
+pub const HK_NEW_DESKTOP: i32 = 8;
+pub const HK_REMOVE_DESKTOP: i32 = 9;
+pub const HK_PANIC: i32 = 10;
+pub const HK_PEEK: i32 = 11;
+
+/// Every hotkey id this app registers.
+pub const ALL_IDS: [i32; 11] = [
+    HK_EDIT_TITLE,
+    HK_EDIT_DESC,
+    HK_TOGGLE,
+    HK_SNAP,
+    HK_TOGGLE_AUTORUN,
+    HK_EDIT_BOTH,
+    HK_TOGGLE_DESKTOP_HIDDEN,
+    HK_NEW_DESKTOP,
+    HK_REMOVE_DESKTOP,
+    HK_PANIC,
+    HK_PEEK,
+];
+
+/// The window hotkeys are registered to; off Windows nothing is registered.
+#[cfg(windows)]
+type Owner = HWND;
+#[cfg(not(windows))]
+type Owner = ();
+
+/// Each configured chord with its display name and id; optional chords only when set.
+fn configured(hk: &Hotkeys) -> Vec<(&'static str, i32, &KeyChord)> {
+    let mut chords = vec![
+        ("Edit Title", HK_EDIT_TITLE, &hk.edit_title),
+        ("Edit Description", HK_EDIT_DESC, &hk.edit_description),
+        ("Toggle Overlay", HK_TOGGLE, &hk.toggle_overlay),
+        ("Snap Position", HK_SNAP, &hk.snap_position),
+        ("Edit Both", HK_EDIT_BOTH, &hk.edit_both),
+    ];
+    let optional = [
+        ("Toggle Autorun", HK_TOGGLE_AUTORUN, &hk.toggle_autorun),
+        (
+            "Hide on This Desktop",
+            HK_TOGGLE_DESKTOP_HIDDEN,
+            &hk.toggle_desktop_hidden,
+        ),
+        ("New Desktop", HK_NEW_DESKTOP, &hk.new_desktop),
+        ("Remove Desktop", HK_REMOVE_DESKTOP, &hk.remove_desktop),
+        ("Panic Hide", HK_PANIC, &hk.panic_hide),
+        ("Peek", HK_PEEK, &hk.peek),
+    ];
+    chords.extend(
+        optional
+            .into_iter()
+            .filter_map(|(name, id, chord)| chord.as_ref().map(|c| (name, id, c))),
+    );
+    chords
+}
+
+/// Unregister every id, then register each configured chord; returns the outcome per chord.
+/// Chords that were dropped from the config stay unregistered.
+pub fn register_all(hwnd: Owner, hk: &Hotkeys) -> Vec<Registration> {
+    unregister_all(hwnd);
+    configured(hk)
+        .into_iter()
+        .map(|(name, id, chord)| register_chord(hwnd, name, chord, id))
+        .collect()
+}
+
+/// Unregister every id in [`ALL_IDS`]; ids that aren't registered are ignored.
+pub fn unregister_all(hwnd: Owner) {
+    for id in ALL_IDS {
+        unregister(hwnd, id);
+    }
+}
 
 // Utility: detect duplicates between hotkey chords (case-insensitive key, same modifiers).
-use crate::config::Hotkeys;
 pub fn has_duplicates(hk: &Hotkeys) -> bool {
-    fn same(a: &crate::config::KeyChord, b: &crate::config::KeyChord) -> bool {
+    fn same(a: &KeyChord, b: &KeyChord) -> bool {
         a.ctrl == b.ctrl
             && a.alt == b.alt
             && a.shift == b.shift
             && a.key.eq_ignore_ascii_case(&b.key)
     }
-    same(&hk.edit_title, &hk.edit_description)
-        || same(&hk.edit_title, &hk.toggle_overlay)
-        || same(&hk.edit_description, &hk.toggle_overlay)
-        || same(&hk.edit_title, &hk.snap_position)
-        || same(&hk.edit_description, &hk.snap_position)
-        || same(&hk.toggle_overlay, &hk.snap_position)
+    let mut chords = vec![
+        &hk.edit_title,
+        &hk.edit_description,
+        &hk.toggle_overlay,
+        &hk.snap_position,
+        &hk.edit_both,
+    ];
+    chords.extend(hk.toggle_autorun.as_ref());
+    chords.extend(hk.toggle_desktop_hidden.as_ref());
+    chords.extend(hk.new_desktop.as_ref());
+    chords.extend(hk.remove_desktop.as_ref());
+    chords.extend(hk.panic_hide.as_ref());
+    chords.extend(hk.peek.as_ref());
+    chords
+        .iter()
+        .enumerate()
+        .any(|(i, a)| chords[i + 1..].iter().any(|b| same(a, b)))
+}
+
+/// Human-readable chord such as `Ctrl+Alt+T`.
+pub fn format_chord(chord: &KeyChord) -> String {
+    let mut parts: Vec<String> = Vec::new();
+    for (on, name) in [
+        (chord.ctrl, "Ctrl"),
+        (chord.alt, "Alt"),
+        (chord.shift, "Shift"),
+    ] {
+        if on {
+            parts.push(name.to_string());
+        }
+    }
+    let key = chord.key.trim();
+    let mut chars = key.chars();
+    let key = match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    };
+    parts.push(key);
+    parts.join("+")
+}
+
+/// Hint shown after the overlay title, built from the configured edit chords.
+pub fn hint_text(hk: &Hotkeys) -> String {
+    format!(
+        "({}, {})",
+        format_chord(&hk.edit_title),
+        format_chord(&hk.edit_description)
+    )
+}
+
+/// Balloon text summarizing which chords re-registered, given `(name, ok)` pairs.
+pub fn rehook_summary(results: &[(&str, bool)]) -> String {
+    let list = |want: bool| {
+        results
+            .iter()
+            .filter(|(_, ok)| *ok == want)
+            .map(|(name, _)| *name)
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let (ok, failed) = (list(true), list(false));
+    match (ok.is_empty(), failed.is_empty()) {
+        (_, true) => format!("All hotkeys registered: {}", ok),
+        (true, false) => format!("No hotkeys registered. Failed: {}", failed),
+        (false, false) => format!("Registered: {}. Failed: {}", ok, failed),
+    }
 }
@@ -0,0 +1,129 @@
+use anyhow::{Result, bail};
+
+/// `COPYDATASTRUCT::dwData` tag for label updates; other `WM_COPYDATA` senders are ignored.
+pub const COPYDATA_SET_LABEL: usize = 0x4D44_0001;
+
+/// Payload layout version; bump when the encoding changes.
+pub const PAYLOAD_VERSION: u8 = 1;
+
+/// Upper bound on the label text in a payload, in bytes of UTF-8.
+pub const MAX_TEXT_BYTES: usize = 16 * 1024;
+
+/// Fixed header: version, field, then the text length as a little-endian `u32`.
+const HEADER_LEN: usize = 1 + 1 + 4;
+
+/// Largest payload the receiver will read.
+pub const MAX_PAYLOAD_BYTES: usize = HEADER_LEN + MAX_TEXT_BYTES;
+
+/// Which part of the current desktop's label to change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LabelField {
+    Title,
+    Description,
+}
+
+impl LabelField {
+    fn tag(self) -> u8 {
+        match self {
+            LabelField::Title => 0,
+            LabelField::Description => 1,
+        }
+    }
+
+    fn from_tag(tag: u8) -> Option<Self> {
+        match tag {
+            0 => Some(LabelField::Title),
+            1 => Some(LabelField::Description),
+            _ => None,
+        }
+    }
+}
+
+/// Encode a label update as `[version][field][len: u32 LE][utf-8 text]`.
+pub fn encode_set_label(field: LabelField, text: &str) -> Result<Vec<u8>> {
+    if text.len() > MAX_TEXT_BYTES {
+        bail!(
+            "label text is too long ({} bytes, max {})",
+            text.len(),
+            MAX_TEXT_BYTES
+        );
+    }
+    let mut out = Vec::with_capacity(HEADER_LEN + text.len());
+    out.push(PAYLOAD_VERSION);
+    out.push(field.tag());
+    out.extend_from_slice(&(text.len() as u32).to_le_bytes());
+    out.extend_from_slice(text.as_bytes());
+    Ok(out)
+}
+
+/// Decode a payload from [`encode_set_label`], rejecting anything malformed.
+pub fn decode_set_label(bytes: &[u8]) -> Result<(LabelField, String)> {
+    if bytes.len() < HEADER_LEN || bytes.len() > MAX_PAYLOAD_BYTES {
+        bail!("bad payload size {}", bytes.len());
+    }
+    if bytes[0] != PAYLOAD_VERSION {
+        bail!("unsupported payload version {}", bytes[0]);
+    }
+    let Some(field) = LabelField::from_tag(bytes[1]) else {
+        bail!("unknown label field {}", bytes[1]);
+    };
+    let len = u32::from_le_bytes([bytes[2], bytes[3], bytes[4], bytes[5]]) as usize;
+    let text = &bytes[HEADER_LEN..];
+    if len != text.len() {
+        bail!("length mismatch: header says {}, got {}", len, text.len());
+    }
+    Ok((field, String::from_utf8(text.to_vec())?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips() {
+        for (field, text) in [
+            (LabelField::Title, "Work"),
+            (LabelField::Description, "Mail · Chat 🚀"),
+            (LabelField::Title, ""),
+        ] {
+            let bytes = encode_set_label(field, text).unwrap();
+            assert_eq!(decode_set_label(&bytes).unwrap(), (field, text.to_string()));
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_payloads() {
+        let good = encode_set_label(LabelField::Title, "Work").unwrap();
+        assert!(decode_set_label(&[]).is_err());
+        assert!(decode_set_label(&good[..HEADER_LEN - 1]).is_err());
+        // Truncated or padded text.
+        assert!(decode_set_label(&good[..good.len() - 1]).is_err());
+        let mut padded = good.clone();
+        padded.push(b'!');
+        assert!(decode_set_label(&padded).is_err());
+        // Unknown version or field.
+        let mut bad = good.clone();
+        bad[0] = 9;
+        assert!(decode_set_label(&bad).is_err());
+        let mut bad = good.clone();
+        bad[1] = 7;
+        assert!(decode_set_label(&bad).is_err());
+        // Invalid UTF-8.
+        let mut bad = good;
+        bad[HEADER_LEN] = 0xFF;
+        assert!(decode_set_label(&bad).is_err());
+    }
+
+    #[test]
+    fn enforces_size_limit() {
+        let long = "x".repeat(MAX_TEXT_BYTES + 1);
+        assert!(encode_set_label(LabelField::Title, &long).is_err());
+        let max = "x".repeat(MAX_TEXT_BYTES);
+        let bytes = encode_set_label(LabelField::Title, &max).unwrap();
+        assert_eq!(bytes.len(), MAX_PAYLOAD_BYTES);
+        assert!(decode_set_label(&bytes).is_ok());
+        let mut oversized = bytes;
+        oversized.push(b'x');
+        assert!(decode_set_label(&oversized).is_err());
+    }
+}
@@ -0,0 +1,124 @@
+/// What a headless overlay (see `Overlay::new_headless`) would have drawn: the text with
+/// any hints, and the window rect.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeadlessFrame {
+    pub text: String,
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+/// Size of `text` (hints trailing its first line) with fixed metrics instead of DirectWrite:
+/// every character advances half its font size and every line is 1.25 times the text size
+/// tall. Headless overlays measure with this, so layout can be tested without a display.
+pub fn fixed_text_size(text: &str, hints: &str, text_px: f32, hint_px: f32) -> (i32, i32) {
+    let advance = |s: &str, px: f32| s.chars().count() as f32 * px * 0.5;
+    let mut lines = text.split('\n');
+    let mut width = advance(lines.next().unwrap_or_default(), text_px);
+    if !hints.is_empty() {
+        width += advance(" ", text_px) + advance(hints, hint_px);
+    }
+    let mut count = 1;
+    for line in lines {
+        width = width.max(advance(line, text_px));
+        count += 1;
+    }
+    let height = count as f32 * text_px * 1.25;
+    ((width.ceil() as i32).max(1), (height.ceil() as i32).max(1))
+}
+
+/// Append `hints` to the first line of `text` (lines split on `\n`); returns the combined
+/// string and the hints' UTF-16 (start, length) so they can be drawn smaller.
+pub fn join_hints(text: &str, hints: &str) -> (String, Option<(u32, u32)>) {
+    if hints.is_empty() {
+        return (text.to_string(), None);
+    }
+    let (first, rest) = match text.split_once('\n') {
+        Some((first, rest)) => (first, Some(rest)),
+        None => (text, None),
+    };
+    let start = first.encode_utf16().count() as u32 + 1; // +1 for the space
+    let length = hints.encode_utf16().count() as u32;
+    let combined = match rest {
+        Some(rest) => format!("{} {}\n{}", first, hints, rest),
+        None => format!("{} {}", first, hints),
+    };
+    (combined, Some((start, length)))
+}
+
+/// Widest text that keeps a window with `pad` around its text inside `work_w`, with
+/// `margin_px` to spare on both sides.
+pub fn text_budget(work_w: i32, pad: i32, margin_px: i32) -> i32 {
+    // Window = text + padding on both sides, plus the outer margin on both sides.
+    work_w - (pad + margin_px) * 2
+}
+
+/// Ellipsize each line of `text` to `budget`, measuring candidates with `measure(line, hints)`.
+/// The hints trail the first line, so they share its budget.
+pub fn fit_to_width(
+    text: &str,
+    hints: &str,
+    budget: i32,
+    measure: impl Fn(&str, &str) -> i32,
+) -> String {
+    text.split('\n')
+        .enumerate()
+        .map(|(i, line)| {
+            let line_hints = if i == 0 { hints } else { "" };
+            crate::core::ellipsize(line, budget, |candidate| measure(candidate, line_hints))
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_metrics_cover_lines_and_hints() {
+        assert_eq!(fixed_text_size("abcd", "", 16.0, 11.2), (32, 20));
+        assert_eq!(fixed_text_size("ab\ncdefgh", "", 16.0, 11.2), (48, 40));
+        // Hints trail the first line at their own size, after a space.
+        assert_eq!(fixed_text_size("ab", "xy", 20.0, 10.0), (40, 25));
+        assert_eq!(fixed_text_size("", "", 16.0, 11.2), (1, 20));
+    }
+
+    #[test]
+    fn hints_join_the_first_line_only() {
+        assert_eq!(join_hints("Work", ""), ("Work".into(), None));
+        assert_eq!(
+            join_hints("Wörk\nsecond", "[T]"),
+            ("Wörk [T]\nsecond".into(), Some((5, 3)))
+        );
+    }
+
+    #[test]
+    fn multi_line_text_is_ellipsized_line_by_line() {
+        // 8 px per character at 16 px; the budget leaves padding and margin on both sides.
+        let measure = |s: &str, hints: &str| fixed_text_size(s, hints, 16.0, 11.2).0;
+        let budget = text_budget(200, 8, 8);
+        assert_eq!(budget, 200 - (8 + 8) * 2);
+        let long = "x".repeat(40);
+        let fitted = fit_to_width(&format!("{long}\nshort"), "", budget, measure);
+        let (first, second) = fitted.split_once('\n').unwrap();
+        assert!(first.ends_with('…'), "{first}");
+        assert!(measure(first, "") <= budget);
+        assert_eq!(second, "short");
+        // Short text is left alone; hints count against the first line only.
+        assert_eq!(fit_to_width("fits", "", budget, measure), "fits");
+        let hinted = fit_to_width(
+            &format!("{}\n{}", "y".repeat(18), "z".repeat(18)),
+            "[T]",
+            budget,
+            measure,
+        );
+        let (first, second) = hinted.split_once('\n').unwrap();
+        assert!(
+            first.ends_with('…') && measure(first, "[T]") <= budget,
+            "{first}"
+        );
+        assert_eq!(second, "z".repeat(18));
+    }
+}
@@ -1,11 +1,19 @@
 pub mod autorun;
+pub mod cli;
 pub mod config;
 pub mod core;
+pub mod hook;
 pub mod hotkeys;
+pub mod ipc;
+pub mod layout;
+pub mod usage;
 pub mod utils;
+pub mod vd_backend;
 
 // Windows-only modules
 #[cfg(windows)]
+pub mod monitors;
+#[cfg(windows)]
 pub mod overlay;
 #[cfg(windows)]
 pub mod tray;
@@ -7,20 +7,42 @@ mod windows_main;
 #[cfg(windows)]
 fn main() -> anyhow::Result<()> {
     // Best-effort tracing setup to a rolling daily log under the app's data dir.
+    // Uses the same path resolution (including its fallback and any `--config` override)
+    // as config loading, and the level/retention/format from the config's `logging` section.
     // Falls back silently if initialization fails (e.g., IO errors).
     {
-        if let Ok((_, paths)) = mddskmgr::config::load_or_default() {
+        if let Ok(invocation) = mddskmgr::cli::parse_invocation(std::env::args().skip(1)) {
+            if invocation.portable {
+                mddskmgr::config::set_portable();
+            }
+            if let Some(path) = invocation.config {
+                mddskmgr::config::set_config_override(path);
+            }
+        }
+        if let Ok(paths) = mddskmgr::config::project_paths() {
+            let logging = mddskmgr::config::read_logging(&paths.cfg_file);
             std::fs::create_dir_all(&paths.log_dir).ok();
-            let file_appender = tracing_appender::rolling::daily(&paths.log_dir, "mddsklbl.log");
+            let pruned = mddskmgr::config::prune_log_files(&paths.log_dir, logging.max_files);
+            let file_appender =
+                tracing_appender::rolling::daily(&paths.log_dir, mddskmgr::config::LOG_FILE_NAME);
             let (nb_writer, _guard) = tracing_appender::non_blocking(file_appender);
+            // RUST_LOG still wins when set.
             let env = tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info"));
-            let _ = tracing_subscriber::fmt()
+                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new(logging.level.as_filter()));
+            let builder = tracing_subscriber::fmt()
                 .with_env_filter(env)
                 .with_ansi(false)
-                .with_writer(nb_writer)
-                .try_init();
+                .with_writer(nb_writer);
+            let _ = match logging.format {
+                mddskmgr::config::LogFormat::Json => builder.json().try_init(),
+                mddskmgr::config::LogFormat::Text => builder.try_init(),
+            };
             tracing::info!("mddsklbl starting");
+            match pruned {
+                Ok(0) => {}
+                Ok(n) => tracing::info!(removed = n, "pruned old log files"),
+                Err(e) => tracing::warn!(error = ?e, "failed to prune old log files"),
+            }
         }
     }
     windows_main::main()
@@ -0,0 +1,133 @@
+use crate::core::TaskbarEdge;
+use std::ffi::c_void;
+use windows::Win32::Foundation::{BOOL, LPARAM, RECT};
+use windows::Win32::Graphics::Gdi::{
+    EnumDisplayMonitors, GetMonitorInfoW, HDC, HMONITOR, MONITOR_DEFAULTTOPRIMARY, MONITORINFO,
+    MONITORINFOEXW, MonitorFromRect,
+};
+use windows::Win32::UI::HiDpi::{GetDpiForMonitor, MDT_EFFECTIVE_DPI};
+use windows::Win32::UI::Shell::{
+    ABE_LEFT, ABE_RIGHT, ABE_TOP, ABM_GETTASKBARPOS, APPBARDATA, SHAppBarMessage,
+};
+use windows::Win32::UI::WindowsAndMessaging::{
+    MONITORINFOF_PRIMARY, SPI_GETWORKAREA, SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS,
+    SystemParametersInfoW,
+};
+
+/// One attached display, in `EnumDisplayMonitors` order.
+#[derive(Debug, Clone)]
+pub struct MonitorInfo {
+    /// Full monitor bounds in virtual-screen coordinates.
+    pub rect: RECT,
+    /// Bounds minus the taskbar and other app bars.
+    pub work: RECT,
+    /// Device name such as `\\.\DISPLAY2`.
+    pub name: String,
+    pub primary: bool,
+    pub handle: HMONITOR,
+}
+
+/// Enumerate the attached monitors; empty if enumeration fails.
+pub fn list() -> Vec<MonitorInfo> {
+    unsafe extern "system" fn collect(
+        hmon: HMONITOR,
+        _hdc: HDC,
+        _clip: *mut RECT,
+        data: LPARAM,
+    ) -> BOOL {
+        let out = unsafe { &mut *(data.0 as *mut Vec<MonitorInfo>) };
+        let mut mi = MONITORINFOEXW::default();
+        mi.monitorInfo.cbSize = std::mem::size_of::<MONITORINFOEXW>() as u32;
+        if unsafe { GetMonitorInfoW(hmon, &mut mi as *mut _ as *mut MONITORINFO) }.as_bool() {
+            let len = mi.szDevice.iter().position(|&c| c == 0).unwrap_or(32);
+            out.push(MonitorInfo {
+                rect: mi.monitorInfo.rcMonitor,
+                work: mi.monitorInfo.rcWork,
+                name: String::from_utf16_lossy(&mi.szDevice[..len]),
+                primary: mi.monitorInfo.dwFlags & MONITORINFOF_PRIMARY != 0,
+                handle: hmon,
+            });
+        }
+        BOOL(1)
+    }
+
+    let mut out: Vec<MonitorInfo> = Vec::new();
+    unsafe {
+        let _ = EnumDisplayMonitors(
+            HDC(std::ptr::null_mut()),
+            None,
+            Some(collect),
+            LPARAM(&mut out as *mut _ as isize),
+        );
+    }
+    out
+}
+
+/// Work area of monitor `index`, or of the primary monitor when `index` is unset or no
+/// longer attached (the latter is logged).
+pub fn work_area(index: Option<usize>) -> RECT {
+    let monitors = if index.is_some() { list() } else { Vec::new() };
+    match crate::core::pick_monitor(index, monitors.len()) {
+        Some(i) => monitors[i].work,
+        None => primary_work_area(),
+    }
+}
+
+/// Log when `index` names a monitor that isn't attached. [`work_area`] falls back to the
+/// primary silently, since it runs on every draw; this runs once per config load.
+pub fn warn_if_missing(index: Option<usize>) {
+    let Some(i) = index else { return };
+    let attached = list().len();
+    if crate::core::pick_monitor(index, attached).is_none() {
+        tracing::warn!(
+            monitor_index = i,
+            attached,
+            "configured monitor not found; using the primary monitor"
+        );
+    }
+}
+
+/// The taskbar's edge and bounds, or `None` when the appbar query fails (e.g. no Explorer).
+pub fn taskbar() -> Option<(TaskbarEdge, RECT)> {
+    let mut data = APPBARDATA {
+        cbSize: std::mem::size_of::<APPBARDATA>() as u32,
+        ..Default::default()
+    };
+    if unsafe { SHAppBarMessage(ABM_GETTASKBARPOS, &mut data) } == 0 {
+        return None;
+    }
+    let edge = match data.uEdge {
+        ABE_LEFT => TaskbarEdge::Left,
+        ABE_TOP => TaskbarEdge::Top,
+        ABE_RIGHT => TaskbarEdge::Right,
+        _ => TaskbarEdge::Bottom,
+    };
+    Some((edge, data.rc))
+}
+
+/// The primary monitor's work area (excludes the taskbar), read fresh on every call.
+pub fn primary_work_area() -> RECT {
+    let mut work = RECT::default();
+    unsafe {
+        let _ = SystemParametersInfoW(
+            SPI_GETWORKAREA,
+            0,
+            Some(&mut work as *mut _ as *mut c_void),
+            SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
+        );
+    }
+    work
+}
+
+/// Effective DPI of the monitor containing most of `rect` (the primary if none), or 96 when
+/// it can't be read.
+pub fn dpi_at(rect: &RECT) -> u32 {
+    let (mut x, mut y) = (0u32, 0u32);
+    unsafe {
+        let hmon = MonitorFromRect(rect, MONITOR_DEFAULTTOPRIMARY);
+        if GetDpiForMonitor(hmon, MDT_EFFECTIVE_DPI, &mut x, &mut y).is_err() || x == 0 {
+            return 96;
+        }
+    }
+    x
+}
@@ -1,21 +1,23 @@
+use crate::config::OverlayAnchor;
+use crate::core::Rgba;
 use crate::utils::to_utf16;
-use anyhow::{Result, anyhow};
+use anyhow::{Context, Result, anyhow};
 use once_cell::sync::OnceCell;
+use std::cell::{Cell, RefCell};
 use std::ffi::c_void;
 use std::mem::{size_of, zeroed};
+use std::rc::Rc;
 use windows::Win32::Foundation::{COLORREF, HWND, POINT, RECT, SIZE};
 use windows::Win32::Graphics::Direct2D::Common::*;
 use windows::Win32::Graphics::Direct2D::*;
 use windows::Win32::Graphics::DirectWrite::*;
 use windows::Win32::Graphics::Dxgi::Common::*;
 use windows::Win32::Graphics::Gdi::*;
+use windows::Win32::System::LibraryLoader::{GetModuleHandleW, GetProcAddress};
 use windows::Win32::UI::WindowsAndMessaging::{
-    HWND_TOPMOST, SWP_NOACTIVATE, SWP_NOMOVE, SWP_NOSIZE, SetWindowPos,
-};
-use windows::Win32::UI::WindowsAndMessaging::{
-    SPI_GETWORKAREA, SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS, SystemParametersInfoW, ULW_ALPHA,
-    UpdateLayeredWindow,
+    GetWindowRect, HWND_TOPMOST, SWP_NOACTIVATE, SWP_NOMOVE, SWP_NOSIZE, SetWindowPos,
 };
+use windows::Win32::UI::WindowsAndMessaging::{ULW_ALPHA, UpdateLayeredWindow};
 use windows::core::{Interface, PCWSTR};
 
 /// RAII wrapper for HDC that automatically releases the DC on drop
@@ -120,21 +122,389 @@ impl Drop for ScopedFont {
     }
 }
 
+pub use crate::layout::HeadlessFrame;
+
+/// Stand-ins a headless overlay uses instead of the display: a fixed work area and DPI, and
+/// the last frame "drawn".
+struct Headless {
+    work: RECT,
+    dpi: u32,
+    frame: RefCell<Option<HeadlessFrame>>,
+}
+
+/// Font settings a cached text format was built for.
+type TextFormatKey = (String, i32, u16, bool);
+
 #[derive(Clone)]
 pub struct Overlay {
     hwnd: HWND,
     font_family: String,
+    /// Font size in DIPs, like the padding, corner radius and shadow offset below;
+    /// [`Overlay::scaled`] converts them to pixels for each draw.
     font_px: i32,
+    font_weight: u16,
+    font_italic: bool,
+    fallback_fonts: Vec<String>,
+    offset_y: i32,
+    /// `appearance.offset_x`/`offset_y`, applied after anchoring.
+    nudge: (i32, i32),
+    anchor: OverlayAnchor,
+    text_color: Rgba,
+    background_color: Rgba,
+    opacity: u8,
+    monitor_index: Option<usize>,
+    /// Space between the text and the window edge; `None` uses the margin passed to each draw.
+    padding_px: Option<i32>,
+    /// Background corner radius; `None` derives it from the font size.
+    corner_radius_px: Option<i32>,
+    /// Shadow color and offset drawn under the text; `None` draws no shadow.
+    text_shadow: Option<(Rgba, i32)>,
+    /// Text format for the current font, shared by all clones of this overlay; see
+    /// [`Overlay::text_format`].
+    text_format: Rc<RefCell<Option<(TextFormatKey, IDWriteTextFormat)>>>,
+    /// Fade multiplier (255 = fully faded in), shared by all clones of this overlay.
+    fade: Rc<Cell<u8>>,
+    /// Unfaded constant alpha and alpha format of the last successful draw, so fades can
+    /// update the blend without repainting.
+    last_blend: Rc<Cell<Option<(u8, u8)>>>,
+    /// Set for headless overlays, shared by all clones. Headless overlays measure with fixed
+    /// metrics and never touch DirectWrite, Direct2D, a window or the monitors.
+    headless: Option<Rc<Headless>>,
 }
 
 impl Overlay {
+    /// Fails when DirectWrite, which measures every label, is unavailable.
     pub fn new(hwnd: HWND, font_family: &str, font_size_dip: u32) -> Result<Self> {
-        // Approximate 1 DIP = 1 px at 100% scale for initial implementation
-        Ok(Self {
+        get_dwrite_factory().context("text rendering (DirectWrite) is unavailable")?;
+        // Created here rather than on the first draw, so a failure is reported at startup.
+        get_d2d_factory().context("drawing (Direct2D) is unavailable")?;
+        Ok(Self::with_defaults(hwnd, font_family, font_size_dip))
+    }
+
+    /// An overlay without a window or render resources, for exercising layout (ellipsizing,
+    /// padding, stacking) where there is no display. It is placed on the given `work` area
+    /// (left, top, right, bottom) at `dpi` rather than on a monitor, and text is measured with
+    /// fixed metrics (see [`crate::layout::fixed_text_size`]); draws compute the layout and
+    /// record it for [`Overlay::last_headless_frame`] instead of rendering.
+    #[cfg(any(test, feature = "headless"))]
+    pub fn new_headless(
+        font_family: &str,
+        font_size_dip: u32,
+        work: (i32, i32, i32, i32),
+        dpi: u32,
+    ) -> Self {
+        let mut ov = Self::with_defaults(HWND(std::ptr::null_mut()), font_family, font_size_dip);
+        let (left, top, right, bottom) = work;
+        ov.headless = Some(Rc::new(Headless {
+            work: RECT {
+                left,
+                top,
+                right,
+                bottom,
+            },
+            dpi,
+            frame: RefCell::new(None),
+        }));
+        ov
+    }
+
+    /// The layout of the last draw of a headless overlay; `None` before the first draw and
+    /// always for real overlays.
+    #[cfg(any(test, feature = "headless"))]
+    pub fn last_headless_frame(&self) -> Option<HeadlessFrame> {
+        self.headless.as_ref()?.frame.borrow().clone()
+    }
+
+    fn with_defaults(hwnd: HWND, font_family: &str, font_size_dip: u32) -> Self {
+        Self {
             hwnd,
             font_family: font_family.to_string(),
             font_px: font_size_dip as i32,
-        })
+            font_weight: 400,
+            font_italic: false,
+            fallback_fonts: Vec::new(),
+            offset_y: 0,
+            nudge: (0, 0),
+            anchor: OverlayAnchor::TopCenter,
+            text_color: Rgba {
+                r: 255,
+                g: 255,
+                b: 255,
+                a: 255,
+            },
+            background_color: Rgba {
+                r: 0,
+                g: 0,
+                b: 0,
+                a: 89,
+            },
+            opacity: 255,
+            monitor_index: None,
+            padding_px: None,
+            corner_radius_px: None,
+            text_shadow: None,
+            text_format: Rc::new(RefCell::new(None)),
+            fade: Rc::new(Cell::new(255)),
+            last_blend: Rc::new(Cell::new(None)),
+            headless: None,
+        }
+    }
+
+    /// Font for the label; the text format is rebuilt from these on the next draw.
+    pub fn set_font(&mut self, family: &str, size_dip: u32, weight: u16, italic: bool) {
+        self.font_family = family.to_string();
+        self.font_px = size_dip as i32;
+        self.font_weight = weight;
+        self.font_italic = italic;
+    }
+
+    /// Families consulted in order for glyphs the main font lacks (see [`font_fallback`]).
+    pub fn set_fallback_fonts(&mut self, fonts: &[String]) {
+        self.fallback_fonts = fonts.to_vec();
+    }
+
+    /// Shift the overlay down by `dy` pixels from its anchor (used to stack multiple instances).
+    pub fn set_offset_y(&mut self, dy: i32) {
+        self.offset_y = dy;
+    }
+
+    /// Move the overlay by (`dx`, `dy`) DIPs from where its anchor puts it; takes effect on the
+    /// next draw.
+    pub fn set_nudge(&mut self, dx: i32, dy: i32) {
+        self.nudge = (dx, dy);
+    }
+
+    /// Where the overlay sits on the work area; takes effect on the next draw.
+    pub fn set_anchor(&mut self, anchor: OverlayAnchor) {
+        self.anchor = anchor;
+    }
+
+    /// The configured text and background colors.
+    pub fn colors(&self) -> (Rgba, Rgba) {
+        (self.text_color, self.background_color)
+    }
+
+    /// Colors for the label and its background box; take effect on the next draw.
+    pub fn set_colors(&mut self, text: Rgba, background: Rgba) {
+        self.text_color = text;
+        self.background_color = background;
+    }
+
+    /// Whole-window opacity (255 = opaque); takes effect on the next draw.
+    pub fn set_opacity(&mut self, opacity: u8) {
+        self.opacity = opacity;
+    }
+
+    /// Current fade level (0 = invisible, 255 = fully shown).
+    pub fn fade(&self) -> u8 {
+        self.fade.get()
+    }
+
+    /// Scale the window's opacity by `level` / 255 right away, without repainting.
+    pub fn set_fade(&self, level: u8) {
+        self.fade.set(level);
+        let Some((constant, format)) = self.last_blend.get() else {
+            return;
+        };
+        let blend = BLENDFUNCTION {
+            BlendOp: 0u8,
+            BlendFlags: 0u8,
+            SourceConstantAlpha: scale_alpha(constant, level),
+            AlphaFormat: format,
+        };
+        // Without a source DC only the blend changes; the last bitmap is kept.
+        unsafe {
+            let _ = UpdateLayeredWindow(
+                self.hwnd,
+                HDC(std::ptr::null_mut()),
+                None,
+                None,
+                HDC(std::ptr::null_mut()),
+                None,
+                COLORREF(0),
+                Some(&blend),
+                ULW_ALPHA,
+            );
+        }
+    }
+
+    /// Monitor to place the overlay on (`None` = primary); takes effect on the next draw.
+    pub fn set_monitor_index(&mut self, index: Option<usize>) {
+        self.monitor_index = index;
+    }
+
+    /// Padding around the text and background corner radius (0 = square corners); `None`
+    /// keeps the defaults. Take effect on the next draw.
+    pub fn set_shape(&mut self, padding_px: Option<u32>, corner_radius_px: Option<u32>) {
+        self.padding_px = padding_px.map(|p| p as i32);
+        self.corner_radius_px = corner_radius_px.map(|r| r as i32);
+    }
+
+    /// Shadow under the text as (color, offset in px), or `None`; takes effect on the next draw.
+    pub fn set_text_shadow(&mut self, shadow: Option<(Rgba, i32)>) {
+        self.text_shadow = shadow;
+    }
+
+    /// A copy with the font size, padding, corner radius, shadow offset and nudge converted from DIPs
+    /// to pixels for the DPI of the monitor holding `work`, plus `margin_dip` converted likewise.
+    /// Only the outermost draw calls scale, so nothing is scaled twice.
+    fn scaled(&self, margin_dip: i32, work: &RECT) -> (Self, i32) {
+        let dpi = match &self.headless {
+            Some(headless) => headless.dpi,
+            None => crate::monitors::dpi_at(work),
+        };
+        let px = |v: i32| crate::core::scale_dip(v, dpi);
+        let mut ov = self.clone();
+        ov.font_px = px(self.font_px);
+        ov.padding_px = self.padding_px.map(px);
+        ov.corner_radius_px = self.corner_radius_px.map(px);
+        ov.text_shadow = self.text_shadow.map(|(color, offset)| (color, px(offset)));
+        ov.nudge = (px(self.nudge.0), px(self.nudge.1));
+        (ov, px(margin_dip))
+    }
+
+    /// Work area to place the overlay on: the configured monitor's (else the primary's), or
+    /// the fixed one of a headless overlay.
+    fn work_area(&self) -> RECT {
+        match &self.headless {
+            Some(headless) => headless.work,
+            None => crate::monitors::work_area(self.monitor_index),
+        }
+    }
+
+    /// Padding for a draw with outer margin `margin_px`.
+    fn padding(&self, margin_px: i32) -> i32 {
+        self.padding_px.unwrap_or(margin_px)
+    }
+
+    /// Stack `lines` vertically (hints trail the first line); the window grows or shrinks to fit.
+    pub fn draw_lines_top_center_with_hints(
+        &self,
+        lines: &[&str],
+        hints: &str,
+        margin_px: i32,
+    ) -> Result<()> {
+        self.draw_line_top_center_with_hints(&lines.join("\n"), hints, margin_px)
+    }
+
+    /// Multi-line variant of `draw_line_anchored_with_hints`.
+    pub fn draw_lines_anchored_with_hints(
+        &self,
+        lines: &[&str],
+        hints: &str,
+        margin_px: i32,
+    ) -> Result<()> {
+        self.draw_line_anchored_with_hints(&lines.join("\n"), hints, margin_px)
+    }
+
+    /// Like `draw_lines_anchored_with_hints`, with `background` (if any) replacing the
+    /// configured background for this draw only.
+    pub fn draw_lines_anchored_colored(
+        &self,
+        lines: &[&str],
+        hints: &str,
+        margin_px: i32,
+        background: Option<Rgba>,
+    ) -> Result<()> {
+        self.with_background(background)
+            .draw_lines_anchored_with_hints(lines, hints, margin_px)
+    }
+
+    /// Draw `title` (with `hints`) at the base font size and the `description` lines beneath
+    /// it at `scale` times that size, sizing the window to fit both. With no description only
+    /// the title row is drawn.
+    pub fn draw_title_and_description(
+        &self,
+        title: &str,
+        description: &[&str],
+        hints: &str,
+        margin_px: i32,
+        background: Option<Rgba>,
+        scale: f32,
+    ) -> Result<()> {
+        let ov = self.with_background(background);
+        if description.is_empty() {
+            return ov.draw_line_anchored_with_hints(title, hints, margin_px);
+        }
+        let work = self.work_area();
+        let (ov, margin_px) = ov.scaled(margin_px, &work);
+        let work_w = work.right - work.left;
+        let title = ov.fit_to_width(title, hints, work_w, margin_px);
+        let budget = ov.text_budget(work_w, margin_px);
+        let description = description
+            .iter()
+            .map(|line| {
+                crate::core::ellipsize(line, budget, |candidate| {
+                    ov.measure_text_scaled(candidate, "", scale)
+                        .map(|(w, _)| w)
+                        .unwrap_or(0)
+                })
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let title_size = ov.measure_text_with_hints(&title, hints)?;
+        let desc_size = ov.measure_text_scaled(&description, "", scale)?;
+        let pad = ov.padding(margin_px);
+        let (w, h) = crate::core::stacked_size(title_size, Some(desc_size), pad);
+        let (x, y) = ov.anchored_origin(w, h, margin_px);
+        let text = format!("{}\n{}", title, description);
+        let res = ov.render_and_update(&text, hints, x, y, w, h, pad, Some(scale));
+        if let Err(e) = &res {
+            tracing::warn!(error=?e, "overlay: render_and_update error");
+        }
+        res
+    }
+
+    pub fn hwnd(&self) -> HWND {
+        self.hwnd
+    }
+
+    pub fn monitor_index(&self) -> Option<usize> {
+        self.monitor_index
+    }
+
+    /// A copy with these settings that draws into `hwnd` on monitor `monitor_index`, with its
+    /// own fade state. The taskbar anchor becomes bottom-center, since only the primary
+    /// taskbar's position is known.
+    pub fn mirrored(&self, hwnd: HWND, monitor_index: usize) -> Self {
+        let mut ov = self.clone();
+        ov.hwnd = hwnd;
+        ov.monitor_index = Some(monitor_index);
+        if ov.anchor == OverlayAnchor::Taskbar {
+            ov.anchor = OverlayAnchor::BottomCenter;
+        }
+        ov.fade = Rc::new(Cell::new(255));
+        ov.last_blend = Rc::new(Cell::new(None));
+        ov
+    }
+
+    /// A copy drawing at `size_dip` instead of the configured size (per-desktop overrides).
+    /// Clones share the text-format cache, so switching between desktops of different sizes
+    /// rebuilds it once per change and redraws at the same size reuse it.
+    pub fn with_font_size(&self, size_dip: u32) -> Self {
+        let mut ov = self.clone();
+        ov.font_px = size_dip as i32;
+        ov
+    }
+
+    /// A copy drawing its text in `text` when set (auto-contrast), keeping the configured
+    /// color otherwise.
+    pub fn with_text_color(&self, text: Option<Rgba>) -> Self {
+        let mut ov = self.clone();
+        if let Some(color) = text {
+            ov.text_color = color;
+        }
+        ov
+    }
+
+    /// A copy with the background overridden; `self` keeps the configured color, so the
+    /// next uncolored draw reverts automatically.
+    fn with_background(&self, background: Option<Rgba>) -> Self {
+        let mut ov = self.clone();
+        if let Some(bg) = background {
+            ov.background_color = bg;
+        }
+        ov
     }
 
     pub fn draw_line_top_center(&self, text: &str, margin_px: i32) -> Result<()> {
@@ -148,68 +518,132 @@ impl Overlay {
         margin_px: i32,
     ) -> Result<()> {
         tracing::debug!(text=%text, hints=%hints, "overlay: draw_line_top_center");
-        let (w, h) = self.measure_text_with_hints(text, hints)?;
-        let w_pad = w + margin_px * 2;
-        let h_pad = h + margin_px * 2;
-
-        // Compute top-center position on the primary work area
-        let mut work: RECT = RECT::default();
-        unsafe {
-            let _ = SystemParametersInfoW(
-                SPI_GETWORKAREA,
-                0,
-                Some(&mut work as *mut _ as *mut c_void),
-                SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
-            );
-        };
+        // Compute top-center position on the configured (else primary) work area
+        let work = self.work_area();
+        let (ov, margin_px) = self.scaled(margin_px, &work);
         let work_w = work.right - work.left;
-        let x = work.left + (work_w - w_pad) / 2;
-        let y = work.top + margin_px;
+        let text = &ov.fit_to_width(text, hints, work_w, margin_px);
+        let (w, h) = ov.measure_text_with_hints(text, hints)?;
+        let pad = ov.padding(margin_px);
+        let w_pad = w + pad * 2;
+        let h_pad = h + pad * 2;
+
+        let (x, y) = crate::core::nudge_within(
+            (
+                work.left + (work_w - w_pad) / 2,
+                work.top + margin_px + ov.offset_y,
+            ),
+            (w_pad, h_pad),
+            ov.nudge,
+            (work.left, work.top, work.right, work.bottom),
+        );
 
-        let res = self.render_and_update(text, hints, x, y, w_pad, h_pad, margin_px);
+        let res = ov.render_and_update(text, hints, x, y, w_pad, h_pad, pad, None);
         if let Err(e) = &res {
             tracing::warn!(error=?e, "overlay: render_and_update error");
         }
         res
     }
 
-    pub fn draw_line_top_anchor_with_hints(
+    /// Draw at the configured anchor, recomputing the rect from the current work area.
+    pub fn draw_line_anchored_with_hints(
         &self,
         text: &str,
         hints: &str,
         margin_px: i32,
-        anchor_ratio: f32,
     ) -> Result<()> {
-        let (w, h) = self.measure_text_with_hints(text, hints)?;
-        let w_pad = w + margin_px * 2;
-        let h_pad = h + margin_px * 2;
-        // Work area
-        let mut work: RECT = RECT::default();
-        unsafe {
-            let _ = SystemParametersInfoW(
-                SPI_GETWORKAREA,
-                0,
-                Some(&mut work as *mut _ as *mut c_void),
-                SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
-            );
-        };
-        let work_w = work.right - work.left;
-        let anchor_x = work.left as f32 + (work_w as f32 * anchor_ratio);
-        let mut x = anchor_x.round() as i32 - w_pad / 2;
-        if x < work.left {
-            x = work.left;
-        }
-        if x + w_pad > work.right {
-            x = work.right - w_pad;
-        }
-        let y = work.top + margin_px;
-        let res = self.render_and_update(text, hints, x, y, w_pad, h_pad, margin_px);
+        let work = self.work_area();
+        let (ov, margin_px) = self.scaled(margin_px, &work);
+        let text = &ov.fit_to_width(text, hints, work.right - work.left, margin_px);
+        let (w, h) = ov.measure_text_with_hints(text, hints)?;
+        let pad = ov.padding(margin_px);
+        let w_pad = w + pad * 2;
+        let h_pad = h + pad * 2;
+        let (x, y) = ov.anchored_origin(w_pad, h_pad, margin_px);
+        let res = ov.render_and_update(text, hints, x, y, w_pad, h_pad, pad, None);
         if let Err(e) = &res {
             tracing::warn!(error=?e, "overlay: render_and_update error");
         }
         res
     }
 
+    /// Move the window to its anchor on the current work area, keeping its size.
+    /// Used after display changes, when the rect computed at the last draw may be stale.
+    pub fn reposition(&self, margin_px: i32) -> Result<()> {
+        let mut rc = RECT::default();
+        unsafe { GetWindowRect(self.hwnd, &mut rc)? };
+        let (ov, margin_px) = self.scaled(margin_px, &rc);
+        let (x, y) = ov.anchored_origin(rc.right - rc.left, rc.bottom - rc.top, margin_px);
+        tracing::debug!(x, y, "overlay: reposition");
+        unsafe {
+            SetWindowPos(
+                self.hwnd,
+                HWND_TOPMOST,
+                x,
+                y,
+                0,
+                0,
+                SWP_NOSIZE | SWP_NOACTIVATE,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Widest text that keeps the window inside `work_w` with `margin_px` to spare on both sides.
+    fn text_budget(&self, work_w: i32, margin_px: i32) -> i32 {
+        crate::layout::text_budget(work_w, self.padding(margin_px), margin_px)
+    }
+
+    /// Ellipsize each line of `text` so the window fits `work_w` with `margin_px` to spare on
+    /// both sides. The hints trail the first line, so they share its budget.
+    fn fit_to_width(&self, text: &str, hints: &str, work_w: i32, margin_px: i32) -> String {
+        let budget = self.text_budget(work_w, margin_px);
+        crate::layout::fit_to_width(text, hints, budget, |candidate, line_hints| {
+            self.measure_text_with_hints(candidate, line_hints)
+                .map(|(w, _)| w)
+                .unwrap_or(0)
+        })
+    }
+
+    /// Top-left corner for a `width` x `height` window at the configured anchor, nudged.
+    fn anchored_origin(&self, width: i32, height: i32, margin_px: i32) -> (i32, i32) {
+        let (origin, work) = self.anchor_origin(width, height, margin_px);
+        crate::core::nudge_within(
+            origin,
+            (width, height),
+            self.nudge,
+            (work.left, work.top, work.right, work.bottom),
+        )
+    }
+
+    /// Top-left corner at the anchor before the nudge, with the work area it was placed in.
+    fn anchor_origin(&self, width: i32, height: i32, margin_px: i32) -> ((i32, i32), RECT) {
+        if self.anchor == OverlayAnchor::Taskbar && self.headless.is_none() {
+            // The taskbar can move at any time; query it fresh and fall back to top-center.
+            if let Some((edge, tb)) = crate::monitors::taskbar() {
+                let origin = crate::core::taskbar_dock_position(
+                    edge,
+                    (tb.left, tb.top, tb.right, tb.bottom),
+                    width,
+                    height,
+                    self.offset_y,
+                );
+                return (origin, crate::monitors::primary_work_area());
+            }
+            tracing::debug!("taskbar position unavailable; anchoring top-center");
+        }
+        let work = self.work_area();
+        let origin = crate::core::anchor_position(
+            self.anchor,
+            (work.left, work.top, work.right, work.bottom),
+            width,
+            height,
+            margin_px,
+            self.offset_y,
+        );
+        (origin, work)
+    }
+
     #[allow(clippy::too_many_arguments)]
     fn render_and_update(
         &self,
@@ -220,7 +654,19 @@ impl Overlay {
         width: i32,
         height: i32,
         pad: i32,
+        secondary_scale: Option<f32>,
     ) -> Result<()> {
+        if let Some(headless) = &self.headless {
+            let (text, _) = crate::layout::join_hints(text, hints);
+            *headless.frame.borrow_mut() = Some(HeadlessFrame {
+                text,
+                x,
+                y,
+                width,
+                height,
+            });
+            return Ok(());
+        }
         // Create device contexts with RAII wrappers for automatic cleanup
         let screen_dc = ScopedDC::from_get_dc(None)?;
         let mem_dc = ScopedDC::from_create_compatible(screen_dc.handle())?;
@@ -241,23 +687,26 @@ impl Overlay {
         // RAII wrapper for bitmap - will automatically restore old object and delete bitmap
         let _scoped_bitmap = ScopedBitmap::new(mem_dc.handle(), hbm);
 
-        // Fill background (black) — no per-pixel alpha; use global alpha in blend
+        // Fill background color — no per-pixel alpha; use global alpha in blend
         let stride = (width * 4) as usize;
         let total = (height as usize) * stride;
+        let bg = self.background_color;
         unsafe {
             let buf = std::slice::from_raw_parts_mut(bits as *mut u8, total);
             for y in 0..height as usize {
                 let row = &mut buf[y * stride..(y + 1) * stride];
                 for px in row.chunks_exact_mut(4) {
                     // BGRA order; opaque RGB, alpha ignored because we use global SourceConstantAlpha
-                    px[0] = 0; // B
-                    px[1] = 0; // G
-                    px[2] = 0; // R
+                    px[0] = bg.b; // B
+                    px[1] = bg.g; // G
+                    px[2] = bg.r; // R
                     px[3] = 0; // A (transparent; D2D will draw alpha)
                 }
             }
         }
 
+        let radius = crate::core::corner_radius(self.corner_radius_px, self.font_px, width, height);
+
         // Prefer Direct2D per-pixel alpha; fallback to GDI if it fails
         let d2d_result = render_d2d_with_hints(
             mem_dc.handle(),
@@ -268,6 +717,14 @@ impl Overlay {
             hints,
             &self.font_family,
             self.font_px,
+            self.font_weight,
+            self.font_italic,
+            &self.fallback_fonts,
+            self.text_color,
+            self.background_color,
+            radius,
+            secondary_scale,
+            self.text_shadow,
         );
 
         let d2d_ok = d2d_result.is_ok();
@@ -278,37 +735,75 @@ impl Overlay {
             );
 
             // Fallback to GDI rendering
-            let font = create_font(&self.font_family, self.font_px);
+            let font = create_font(
+                &self.font_family,
+                self.font_px,
+                self.font_weight,
+                self.font_italic,
+            );
             let _scoped_font = ScopedFont::new(mem_dc.handle(), font);
 
-            unsafe {
-                SetBkMode(mem_dc.handle(), TRANSPARENT);
-                let white = COLORREF(0x00FFFFFF);
-                let _ = SetTextColor(mem_dc.handle(), white);
-                let mut rc = RECT {
-                    left: pad,
-                    top: pad,
-                    right: width - pad,
-                    bottom: height - pad,
-                };
-                let mut wtext: Vec<u16> = if hints.is_empty() {
-                    text.encode_utf16().collect()
-                } else {
-                    format!("{} {}", text, hints).encode_utf16().collect()
-                };
-                let _ = DrawTextW(
-                    mem_dc.handle(),
-                    &mut wtext,
-                    &mut rc,
-                    DT_LEFT | DT_VCENTER | DT_SINGLELINE | DT_NOPREFIX,
-                );
+            let (combined, _) = compose_with_hints(text, hints);
+            let (first, secondary) = match (secondary_scale, combined.split_once('\n')) {
+                (Some(scale), Some((first, rest))) => (first, Some((scale, rest))),
+                _ => (combined.as_str(), None),
+            };
+            let format = if secondary.is_some() {
+                DT_LEFT | DT_TOP | DT_SINGLELINE | DT_NOPREFIX
+            } else if first.contains('\n') {
+                DT_LEFT | DT_TOP | DT_NOPREFIX
+            } else {
+                DT_LEFT | DT_VCENTER | DT_SINGLELINE | DT_NOPREFIX
+            };
+            // The shadow pass (if any) first, offset, then the text on top.
+            let passes = self
+                .text_shadow
+                .into_iter()
+                .chain(std::iter::once((self.text_color, 0)));
+            for (fg, offset) in passes {
+                unsafe {
+                    SetBkMode(mem_dc.handle(), TRANSPARENT);
+                    let color = COLORREF(fg.r as u32 | (fg.g as u32) << 8 | (fg.b as u32) << 16);
+                    let _ = SetTextColor(mem_dc.handle(), color);
+                    let mut rc = RECT {
+                        left: pad + offset,
+                        top: pad + offset,
+                        right: width - pad + offset,
+                        bottom: height - pad + offset,
+                    };
+                    let mut wtext: Vec<u16> = first.encode_utf16().collect();
+                    let title_h = DrawTextW(mem_dc.handle(), &mut wtext, &mut rc, format);
+                    if let Some((scale, rest)) = secondary {
+                        // The description goes under the title row in a smaller font.
+                        let small = create_font(
+                            &self.font_family,
+                            ((self.font_px as f32 * scale) as i32).max(8),
+                            self.font_weight,
+                            self.font_italic,
+                        );
+                        let _scoped_small = ScopedFont::new(mem_dc.handle(), small);
+                        rc.top += title_h;
+                        let mut wrest: Vec<u16> = rest.encode_utf16().collect();
+                        let _ = DrawTextW(
+                            mem_dc.handle(),
+                            &mut wrest,
+                            &mut rc,
+                            DT_LEFT | DT_TOP | DT_NOPREFIX,
+                        );
+                    }
+                }
             }
             // Font is automatically cleaned up by ScopedFont
         }
 
         // Apply a rounded window region to clip hit-testing and visuals
-        let radius = (self.font_px / 2).clamp(6, 20);
-        let hrgn = unsafe { CreateRoundRectRgn(0, 0, width, height, radius * 2, radius * 2) };
+        let hrgn = unsafe {
+            if radius > 0 {
+                CreateRoundRectRgn(0, 0, width, height, radius * 2, radius * 2)
+            } else {
+                CreateRectRgn(0, 0, width, height)
+            }
+        };
         unsafe {
             let _ = SetWindowRgn(self.hwnd, hrgn, true);
         }
@@ -321,11 +816,14 @@ impl Overlay {
             cy: height,
         };
         let alpha_format = if d2d_ok { 1u8 } else { 0u8 };
-        let src_const = if d2d_ok { 255u8 } else { 200u8 };
+        // Opacity goes through the blend's constant alpha: SetLayeredWindowAttributes has no
+        // effect on windows updated with UpdateLayeredWindow. Hit-testing is unchanged.
+        let base_const: u32 = if d2d_ok { 255 } else { 200 };
+        let src_const = (base_const * self.opacity as u32 / 255) as u8;
         let blend = BLENDFUNCTION {
             BlendOp: 0u8,
             BlendFlags: 0u8,
-            SourceConstantAlpha: src_const,
+            SourceConstantAlpha: scale_alpha(src_const, self.fade.get()),
             AlphaFormat: alpha_format,
         };
 
@@ -342,8 +840,9 @@ impl Overlay {
                 ULW_ALPHA,
             )
         };
-        if let Err(e) = &ulw_res {
-            tracing::warn!(error=?e, "overlay: UpdateLayeredWindow failed");
+        match &ulw_res {
+            Ok(()) => self.last_blend.set(Some((src_const, alpha_format))),
+            Err(e) => tracing::warn!(error=?e, "overlay: UpdateLayeredWindow failed"),
         }
 
         // Reassert topmost after painting without activating
@@ -365,33 +864,75 @@ impl Overlay {
     }
 
     fn measure_text_with_hints(&self, text: &str, hints: &str) -> Result<(i32, i32)> {
-        // Use DirectWrite for accurate measurement (apply smaller font to hints)
+        self.measure_text_scaled(text, hints, 1.0)
+    }
+
+    /// Text format for the label font at its current size.
+    ///
+    /// Ellipsizing measures many candidates per draw, so the format is cached and rebuilt only
+    /// when the family, size, weight or style differs from the last one built, e.g. after a
+    /// switch to a desktop with its own `font_size_dip`.
+    fn text_format(&self) -> Result<IDWriteTextFormat> {
+        let key = (
+            self.font_family.clone(),
+            self.font_px,
+            self.font_weight,
+            self.font_italic,
+        );
+        let mut cache = self.text_format.borrow_mut();
+        let hit = cache
+            .as_ref()
+            .filter(|(cached, _)| *cached == key)
+            .map(|(_, tf)| tf.clone());
+        if let Some(tf) = hit {
+            return Ok(tf);
+        }
+        tracing::debug!(font_px = self.font_px, "overlay: building text format");
+        let tf = self.create_text_format(self.font_px as f32)?;
+        *cache = Some((key, tf.clone()));
+        Ok(tf)
+    }
+
+    fn create_text_format(&self, px: f32) -> Result<IDWriteTextFormat> {
         let factory = get_dwrite_factory()?;
         unsafe {
-            let tf = factory.CreateTextFormat(
+            Ok(factory.CreateTextFormat(
                 PCWSTR(to_utf16(&self.font_family).as_ptr()),
                 None,
-                DWRITE_FONT_WEIGHT_NORMAL,
-                DWRITE_FONT_STYLE_NORMAL,
+                DWRITE_FONT_WEIGHT(self.font_weight as i32),
+                font_style(self.font_italic),
                 DWRITE_FONT_STRETCH_NORMAL,
-                self.font_px as f32,
+                px,
                 PCWSTR(windows::core::w!("en-US").as_wide().as_ptr()),
-            )?;
-            let combined = if hints.is_empty() {
-                text.to_string()
-            } else {
-                format!("{} {}", text, hints)
-            };
+            )?)
+        }
+    }
+
+    /// Measure `text` at `scale` times the configured font size; hints stay at their usual size.
+    fn measure_text_scaled(&self, text: &str, hints: &str, scale: f32) -> Result<(i32, i32)> {
+        if self.headless.is_some() {
+            let px = self.font_px as f32;
+            return Ok(crate::layout::fixed_text_size(
+                text,
+                hints,
+                (px * scale).max(8.0),
+                (px * 0.7).max(8.0),
+            ));
+        }
+        // Use DirectWrite for accurate measurement (apply smaller font to hints)
+        let factory = get_dwrite_factory()?;
+        let tf = if scale == 1.0 {
+            self.text_format()?
+        } else {
+            self.create_text_format((self.font_px as f32 * scale).max(8.0))?
+        };
+        unsafe {
+            let (combined, hint_range) = compose_with_hints(text, hints);
             let s = to_utf16(&combined);
             let layout = factory.CreateTextLayout(&s[..s.len() - 1], &tf, 4096.0, 4096.0)?;
-            if !hints.is_empty() {
-                let hint_start = text.encode_utf16().count() + 1; // +1 for the space
-                let hint_len = hints.encode_utf16().count();
+            apply_font_fallback(&layout, &self.fallback_fonts);
+            if let Some(range) = hint_range {
                 let small = (self.font_px as f32 * 0.7).max(8.0);
-                let range = DWRITE_TEXT_RANGE {
-                    startPosition: hint_start as u32,
-                    length: hint_len as u32,
-                };
                 let _ = layout.SetFontSize(small, range);
             }
             let mut m = DWRITE_TEXT_METRICS::default();
@@ -403,7 +944,116 @@ impl Overlay {
     }
 }
 
-fn create_font(face: &str, px: i32) -> HFONT {
+/// `alpha` scaled by a fade `level` (255 = unchanged).
+fn scale_alpha(alpha: u8, level: u8) -> u8 {
+    (alpha as u32 * level as u32 / 255) as u8
+}
+
+#[repr(C)]
+struct AccentPolicy {
+    accent_state: u32,
+    accent_flags: u32,
+    gradient_color: u32,
+    animation_id: u32,
+}
+
+#[repr(C)]
+struct WindowCompositionAttribData {
+    attrib: u32,
+    data: *mut c_void,
+    size: usize,
+}
+
+const WCA_ACCENT_POLICY: u32 = 19;
+const ACCENT_DISABLED: u32 = 0;
+const ACCENT_ENABLE_BLURBEHIND: u32 = 3;
+
+type SetWindowCompositionAttributeFn =
+    unsafe extern "system" fn(HWND, *mut WindowCompositionAttribData) -> i32;
+
+/// Screen colors at `points` (virtual-screen coordinates), skipping any that can't be read.
+pub fn sample_screen(points: &[(i32, i32)]) -> Vec<(u8, u8, u8)> {
+    let Ok(dc) = ScopedDC::from_get_dc(None) else {
+        return Vec::new();
+    };
+    points
+        .iter()
+        .filter_map(|&(x, y)| {
+            let c = unsafe { GetPixel(dc.hdc, x, y) }.0;
+            // CLR_INVALID: off-screen or unreadable.
+            (c != 0xFFFF_FFFF).then_some((
+                (c & 0xFF) as u8,
+                (c >> 8 & 0xFF) as u8,
+                (c >> 16 & 0xFF) as u8,
+            ))
+        })
+        .collect()
+}
+
+/// Turn the blurred backdrop behind the overlay on or off.
+///
+/// Uses the undocumented `SetWindowCompositionAttribute`, looked up at runtime; returns false
+/// when it is missing or fails, in which case the solid background box is all that shows.
+/// The blur is clipped to the window region, so it follows the rounded corners.
+pub fn set_backdrop_blur(hwnd: HWND, enable: bool) -> bool {
+    unsafe {
+        let Ok(user32) = GetModuleHandleW(windows::core::w!("user32.dll")) else {
+            return false;
+        };
+        let Some(proc) = GetProcAddress(user32, windows::core::s!("SetWindowCompositionAttribute"))
+        else {
+            tracing::debug!("SetWindowCompositionAttribute unavailable");
+            return false;
+        };
+        let set_wca: SetWindowCompositionAttributeFn = std::mem::transmute(proc);
+        let mut policy = AccentPolicy {
+            accent_state: if enable {
+                ACCENT_ENABLE_BLURBEHIND
+            } else {
+                ACCENT_DISABLED
+            },
+            accent_flags: 0,
+            gradient_color: 0,
+            animation_id: 0,
+        };
+        let mut data = WindowCompositionAttribData {
+            attrib: WCA_ACCENT_POLICY,
+            data: &mut policy as *mut _ as *mut c_void,
+            size: size_of::<AccentPolicy>(),
+        };
+        set_wca(hwnd, &mut data) != 0
+    }
+}
+
+/// [`crate::layout::join_hints`] with the hints' range as a DirectWrite text range.
+fn compose_with_hints(text: &str, hints: &str) -> (String, Option<DWRITE_TEXT_RANGE>) {
+    let (combined, range) = crate::layout::join_hints(text, hints);
+    let range = range.map(|(start, length)| DWRITE_TEXT_RANGE {
+        startPosition: start,
+        length,
+    });
+    (combined, range)
+}
+
+/// The UTF-16 range of everything after the first line of `combined`: the stacked
+/// description, drawn smaller.
+fn secondary_range(combined: &str) -> Option<DWRITE_TEXT_RANGE> {
+    let (first, rest) = combined.split_once('\n')?;
+    Some(DWRITE_TEXT_RANGE {
+        startPosition: first.encode_utf16().count() as u32 + 1, // +1 for the newline
+        length: rest.encode_utf16().count() as u32,
+    })
+}
+
+fn font_style(italic: bool) -> DWRITE_FONT_STYLE {
+    if italic {
+        DWRITE_FONT_STYLE_ITALIC
+    } else {
+        DWRITE_FONT_STYLE_NORMAL
+    }
+}
+
+fn create_font(face: &str, px: i32, weight: u16, italic: bool) -> HFONT {
     let height = -px; // negative height means character height in logical units
     let wface = to_utf16(face);
     unsafe {
@@ -412,8 +1062,8 @@ fn create_font(face: &str, px: i32) -> HFONT {
             0,
             0,
             0,
-            FW_NORMAL.0 as i32,
-            0,
+            weight as i32,
+            italic as u32,
             0,
             0,
             DEFAULT_CHARSET.0 as u32,
@@ -430,18 +1080,83 @@ fn get_dwrite_factory() -> Result<&'static IDWriteFactory> {
     static FACTORY: OnceCell<IDWriteFactory> = OnceCell::new();
     FACTORY.get_or_try_init(|| {
         unsafe { DWriteCreateFactory::<IDWriteFactory>(DWRITE_FACTORY_TYPE_SHARED) }
-            .map_err(|e| anyhow!("DWriteCreateFactory failed: {:?}", e))
+            .context("DWriteCreateFactory failed")
+    })
+}
+
+/// A fallback chain of `fonts` (in order, for any character) followed by the system fallback.
+/// Cached per thread; rebuilt only when the list changes. `None` before Windows 8.1.
+fn font_fallback(fonts: &[String]) -> Option<IDWriteFontFallback> {
+    thread_local! {
+        static CACHE: RefCell<Option<(Vec<String>, IDWriteFontFallback)>> =
+            const { RefCell::new(None) };
+    }
+    CACHE.with(|cache| {
+        let hit = cache
+            .borrow()
+            .as_ref()
+            .filter(|(cached, _)| cached.as_slice() == fonts)
+            .map(|(_, fallback)| fallback.clone());
+        if hit.is_some() {
+            return hit;
+        }
+        let built = build_font_fallback(fonts)
+            .map_err(|e| tracing::warn!(error=?e, "overlay: font fallback unavailable"))
+            .ok()?;
+        *cache.borrow_mut() = Some((fonts.to_vec(), built.clone()));
+        Some(built)
     })
 }
 
+fn build_font_fallback(fonts: &[String]) -> Result<IDWriteFontFallback> {
+    let factory: IDWriteFactory2 = get_dwrite_factory()?.cast()?;
+    unsafe {
+        let builder = factory.CreateFontFallbackBuilder()?;
+        let names: Vec<Vec<u16>> = fonts
+            .iter()
+            .filter(|f| !f.trim().is_empty())
+            .map(|f| to_utf16(f.trim()))
+            .collect();
+        if !names.is_empty() {
+            let ptrs: Vec<*const u16> = names.iter().map(|n| n.as_ptr()).collect();
+            let all = DWRITE_UNICODE_RANGE {
+                first: 0,
+                last: 0x10FFFF,
+            };
+            builder.AddMapping(&[all], &ptrs, None, PCWSTR::null(), PCWSTR::null(), 1.0)?;
+        }
+        builder.AddMappings(&factory.GetSystemFontFallback()?)?;
+        Ok(builder.CreateFontFallback()?)
+    }
+}
+
+/// Use the [`font_fallback`] chain for `layout`; leaves the default fallback on failure.
+fn apply_font_fallback(layout: &IDWriteTextLayout, fonts: &[String]) {
+    let Some(fallback) = font_fallback(fonts) else {
+        return;
+    };
+    if let Ok(layout2) = layout.cast::<IDWriteTextLayout2>() {
+        let _ = unsafe { layout2.SetFontFallback(&fallback) };
+    }
+}
+
 fn get_d2d_factory() -> Result<&'static ID2D1Factory> {
     static FACTORY: OnceCell<ID2D1Factory> = OnceCell::new();
     FACTORY.get_or_try_init(|| {
         unsafe { D2D1CreateFactory::<ID2D1Factory>(D2D1_FACTORY_TYPE_SINGLE_THREADED, None) }
-            .map_err(|e| anyhow!("D2D1CreateFactory failed: {:?}", e))
+            .context("D2D1CreateFactory failed")
     })
 }
 
+fn d2d_color(c: Rgba) -> D2D1_COLOR_F {
+    D2D1_COLOR_F {
+        r: c.r as f32 / 255.0,
+        g: c.g as f32 / 255.0,
+        b: c.b as f32 / 255.0,
+        a: c.a as f32 / 255.0,
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 fn render_d2d_with_hints(
     hdc: HDC,
@@ -452,6 +1167,14 @@ fn render_d2d_with_hints(
     hints: &str,
     font: &str,
     font_px: i32,
+    font_weight: u16,
+    font_italic: bool,
+    fallback_fonts: &[String],
+    text_color: Rgba,
+    background_color: Rgba,
+    corner_radius: i32,
+    secondary_scale: Option<f32>,
+    text_shadow: Option<(Rgba, i32)>,
 ) -> Result<()> {
     let factory = get_d2d_factory()?;
     unsafe {
@@ -461,8 +1184,9 @@ fn render_d2d_with_hints(
                 format: DXGI_FORMAT_B8G8R8A8_UNORM,
                 alphaMode: D2D1_ALPHA_MODE_PREMULTIPLIED,
             },
-            dpiX: 0.0,
-            dpiY: 0.0,
+            // Sizes are already in pixels (see `Overlay::scaled`); don't let D2D scale again.
+            dpiX: 96.0,
+            dpiY: 96.0,
             usage: D2D1_RENDER_TARGET_USAGE_NONE,
             minLevel: D2D1_FEATURE_LEVEL_DEFAULT,
         };
@@ -484,15 +1208,7 @@ fn render_d2d_with_hints(
         }));
 
         let base: ID2D1RenderTarget = rt.cast()?;
-        let bg = base.CreateSolidColorBrush(
-            &D2D1_COLOR_F {
-                r: 0.0,
-                g: 0.0,
-                b: 0.0,
-                a: 0.35,
-            },
-            None,
-        )?;
+        let bg = base.CreateSolidColorBrush(&d2d_color(background_color), None)?;
         let rounded = D2D1_ROUNDED_RECT {
             rect: D2D_RECT_F {
                 left: 0.0,
@@ -500,8 +1216,8 @@ fn render_d2d_with_hints(
                 right: width as f32,
                 bottom: height as f32,
             },
-            radiusX: (font_px as f32 * 0.5).clamp(6.0, 20.0),
-            radiusY: (font_px as f32 * 0.5).clamp(6.0, 20.0),
+            radiusX: corner_radius as f32,
+            radiusY: corner_radius as f32,
         };
         base.FillRoundedRectangle(&rounded, &bg);
 
@@ -509,17 +1225,13 @@ fn render_d2d_with_hints(
         let tf = dwrite.CreateTextFormat(
             PCWSTR(to_utf16(font).as_ptr()),
             None,
-            DWRITE_FONT_WEIGHT_NORMAL,
-            DWRITE_FONT_STYLE_NORMAL,
+            DWRITE_FONT_WEIGHT(font_weight as i32),
+            font_style(font_italic),
             DWRITE_FONT_STRETCH_NORMAL,
             font_px as f32,
             PCWSTR(windows::core::w!("en-US").as_wide().as_ptr()),
         )?;
-        let combined = if hints.is_empty() {
-            text.to_string()
-        } else {
-            format!("{} {}", text, hints)
-        };
+        let (combined, hint_range) = compose_with_hints(text, hints);
         let s16 = to_utf16(&combined);
         let layout = dwrite.CreateTextLayout(
             &s16[..s16.len() - 1],
@@ -527,32 +1239,94 @@ fn render_d2d_with_hints(
             (width - pad) as f32,
             (height - pad) as f32,
         )?;
-        if !hints.is_empty() {
-            let hint_start = text.encode_utf16().count() + 1;
-            let hint_len = hints.encode_utf16().count();
+        apply_font_fallback(&layout, fallback_fonts);
+        if let Some(range) = hint_range {
             let small = (font_px as f32 * 0.7).max(8.0);
-            let range = DWRITE_TEXT_RANGE {
-                startPosition: hint_start as u32,
-                length: hint_len as u32,
-            };
             let _ = layout.SetFontSize(small, range);
         }
-        let fg = base.CreateSolidColorBrush(
-            &D2D1_COLOR_F {
-                r: 1.0,
-                g: 1.0,
-                b: 1.0,
-                a: 1.0,
-            },
-            None,
-        )?;
+        if let (Some(scale), Some(range)) = (secondary_scale, secondary_range(&combined)) {
+            let _ = layout.SetFontSize((font_px as f32 * scale).max(8.0), range);
+        }
+        if let Some((color, offset)) = text_shadow {
+            let shadow = base.CreateSolidColorBrush(&d2d_color(color), None)?;
+            let at = D2D_POINT_2F {
+                x: (pad + offset) as f32,
+                y: (pad + offset) as f32,
+            };
+            base.DrawTextLayout(at, &layout, &shadow, D2D1_DRAW_TEXT_OPTIONS_NONE);
+        }
+        let fg = base.CreateSolidColorBrush(&d2d_color(text_color), None)?;
         let origin = D2D_POINT_2F {
             x: pad as f32,
             y: pad as f32,
         };
-        base.DrawTextLayout(origin, &layout, &fg, D2D1_DRAW_TEXT_OPTIONS_NONE);
+        // Color fonts draw emoji in color instead of as monochrome outlines.
+        base.DrawTextLayout(
+            origin,
+            &layout,
+            &fg,
+            D2D1_DRAW_TEXT_OPTIONS_ENABLE_COLOR_FONT,
+        );
 
         base.EndDraw(None, None)?;
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 1920x1040 work area at 144 DPI (150%), so scaling is exercised too.
+    fn headless() -> Overlay {
+        Overlay::new_headless("Segoe UI", 16, (0, 0, 1920, 1040), 144)
+    }
+
+    #[test]
+    fn headless_overlay_ellipsizes_to_the_budget() {
+        let ov = headless();
+        let work = ov.work_area();
+        let (scaled, margin) = ov.scaled(8, &work);
+        assert_eq!(margin, 12);
+        let budget = scaled.text_budget(1920, margin);
+        let long = "x".repeat(400);
+        let fitted = scaled.fit_to_width(&format!("{long}\nshort"), "", 1920, margin);
+        let (first, second) = fitted.split_once('\n').unwrap();
+        assert!(first.ends_with('…'), "{first}");
+        assert!(scaled.measure_text_with_hints(first, "").unwrap().0 <= budget);
+        assert_eq!(second, "short");
+    }
+
+    #[test]
+    fn headless_draws_record_the_layout() {
+        let mut ov = headless();
+        assert_eq!(ov.last_headless_frame(), None);
+        ov.set_shape(Some(4), None);
+        ov.draw_lines_anchored_with_hints(&["Work : tickets", "second"], "[T]", 8)
+            .unwrap();
+        let frame = ov.last_headless_frame().unwrap();
+        assert_eq!(frame.text, "Work : tickets [T]\nsecond");
+        // The window is the measured text plus the padding on each side, at the given DPI:
+        // 24 px text, 6 px padding, centered 12 px below the top of the work area.
+        let (w, h) = crate::layout::fixed_text_size("Work : tickets\nsecond", "[T]", 24.0, 16.8);
+        assert_eq!((frame.width, frame.height), (w + 12, h + 12));
+        assert_eq!((frame.x, frame.y), ((1920 - frame.width) / 2, 12));
+        // Clones share the recorded frame.
+        ov.clone().draw_line_top_center("x", 8).unwrap();
+        assert_eq!(ov.last_headless_frame().unwrap().text, "x");
+    }
+
+    #[test]
+    fn emoji_and_cjk_labels_measure_through_the_fallback_fonts() {
+        // Real DirectWrite measurement; needs no window, only the shared factory.
+        let mut ov = Overlay::with_defaults(HWND(std::ptr::null_mut()), "Segoe UI", 16);
+        ov.set_fallback_fonts(&crate::config::Appearance::default().fallback_fonts);
+        let (plain, _) = ov.measure_text_with_hints("Deploy", "").unwrap();
+        for label in ["🚀 Deploy", "設計 Deploy", "👩‍💻\n日本語"] {
+            let (w, h) = ov.measure_text_with_hints(label, "").unwrap();
+            assert!(w > 1 && h > 1, "{label}: {w}x{h}");
+        }
+        let (emoji, _) = ov.measure_text_with_hints("🚀 Deploy", "").unwrap();
+        assert!(emoji > plain, "{emoji} <= {plain}");
+    }
+}
@@ -1,7 +1,12 @@
 use crate::utils::to_utf16;
 use anyhow::{Result, anyhow};
-use std::mem::size_of;
-use windows::Win32::Foundation::{HWND, POINT};
+use std::ffi::c_void;
+use std::mem::{size_of, zeroed};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use windows::Win32::Foundation::{BOOL, HANDLE, HINSTANCE, HWND, POINT, RECT};
+use windows::Win32::Graphics::Gdi::*;
 use windows::Win32::System::LibraryLoader::GetModuleHandleW;
 use windows::Win32::UI::Shell::ExtractIconW;
 use windows::Win32::UI::Shell::{
@@ -20,7 +25,57 @@ pub const CMD_TOGGLE: u16 = 1003;
 pub const CMD_OPEN_CONFIG: u16 = 1004;
 pub const CMD_EXIT: u16 = 1005;
 pub const CMD_ABOUT: u16 = 1006;
-pub const CMD_RUN_AT_STARTUP: u16 = 1007;
+pub const CMD_TOGGLE_AUTORUN: u16 = 1007;
+pub const CMD_REHOOK: u16 = 1008;
+pub const CMD_MANAGE_LABELS: u16 = 1009;
+pub const CMD_EXPORT_CONFIG: u16 = 1010;
+pub const CMD_IMPORT_CONFIG: u16 = 1011;
+pub const CMD_TOGGLE_DESKTOP_HIDDEN: u16 = 1012;
+pub const CMD_RESET_CONFIG: u16 = 1013;
+pub const CMD_NEW_DESKTOP: u16 = 1014;
+pub const CMD_REMOVE_DESKTOP: u16 = 1015;
+pub const CMD_TOGGLE_HIDE_FULLSCREEN: u16 = 1016;
+pub const CMD_EDIT_HOTKEYS: u16 = 1017;
+pub const CMD_COPY_LABELS: u16 = 1018;
+
+/// Icon loaded from `appearance.tray_icon_path` as (file, raw HICON); used in place of the
+/// app icon, badges included.
+static CUSTOM_ICON: Mutex<Option<(PathBuf, isize)>> = Mutex::new(None);
+
+/// The executable's own icon (or the stock app icon) as a raw HICON, extracted once and kept
+/// for the life of the process; every badge and plain-icon restore reuses it.
+static APP_ICON: Mutex<Option<isize>> = Mutex::new(None);
+
+/// Badged tray icon as (badge text, raw HICON), kept so balloons and re-adds preserve the badge.
+static BADGE_ICON: Mutex<Option<(String, isize)>> = Mutex::new(None);
+
+/// Tooltip text, kept so re-adding the icon (Explorer restart) restores it.
+static TOOLTIP: Mutex<String> = Mutex::new(String::new());
+
+/// Longest tooltip `NOTIFYICONDATAW::szTip` holds, excluding the terminating nul.
+const MAX_TIP_UNITS: usize = 127;
+
+/// Set while the config is read-only so the edit items render greyed out.
+static EDITS_LOCKED: AtomicBool = AtomicBool::new(false);
+
+/// Cleared when virtual desktops are unavailable so the desktop items render greyed out.
+static DESKTOPS_AVAILABLE: AtomicBool = AtomicBool::new(true);
+
+/// Checkmark states for the tray menu, snapshotted from the app state before it opens.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MenuChecks {
+    /// The user's overlay toggle is on ("Toggle Overlay").
+    pub overlay_visible: bool,
+    /// The current desktop has `hidden` set ("Hide on This Desktop").
+    pub desktop_hidden: bool,
+    /// `appearance.hide_on_fullscreen` ("Hide When Fullscreen").
+    pub hide_on_fullscreen: bool,
+}
+
+/// `flags` plus a checkmark when `checked`.
+fn checked(flags: MENU_ITEM_FLAGS, checked: bool) -> MENU_ITEM_FLAGS {
+    if checked { flags | MF_CHECKED } else { flags }
+}
 
 pub struct Tray {
     pub nid: NOTIFYICONDATAW,
@@ -28,6 +83,23 @@ pub struct Tray {
 
 impl Tray {
     fn load_app_icon() -> HICON {
+        let custom = CUSTOM_ICON
+            .lock()
+            .ok()
+            .and_then(|g| g.as_ref().map(|(_, h)| *h));
+        if let Some(h) = custom {
+            return HICON(h as *mut c_void);
+        }
+        let Ok(mut cached) = APP_ICON.lock() else {
+            return Self::extract_app_icon();
+        };
+        let raw = *cached.get_or_insert_with(|| Self::extract_app_icon().0 as isize);
+        HICON(raw as *mut c_void)
+    }
+
+    /// A new handle to the executable's icon, or the stock app icon. Each `ExtractIconW`
+    /// handle stays allocated until destroyed, so only `load_app_icon` should call this.
+    fn extract_app_icon() -> HICON {
         unsafe {
             let hinst = GetModuleHandleW(None).unwrap_or_default();
             // Try extracting the primary icon from our executable
@@ -43,7 +115,24 @@ impl Tray {
         }
     }
 
-    pub fn new(hwnd: HWND, tip: &str) -> Result<Self> {
+    /// Icon to show in the tray: the badged icon when one is set, else the app icon.
+    fn current_icon() -> HICON {
+        let cached = BADGE_ICON
+            .lock()
+            .ok()
+            .and_then(|g| g.as_ref().map(|(_, h)| *h));
+        match cached {
+            Some(h) => HICON(h as *mut c_void),
+            None => Self::load_app_icon(),
+        }
+    }
+
+    /// Add the icon, from the `.ico` at `icon` if given; a file that won't load is logged and
+    /// the built-in icon is shown instead.
+    pub fn new(hwnd: HWND, tip: &str, icon: Option<&Path>) -> Result<Self> {
+        if let Err(e) = Self::set_icon_path(icon) {
+            tracing::warn!(error = ?e, "custom tray icon unavailable; using the built-in icon");
+        }
         unsafe {
             // SAFETY: NOTIFYICONDATAW is a C struct from the Windows API that can be safely zero-initialized.
             // All fields are explicitly set below, and Windows API documentation specifies this initialization pattern.
@@ -53,12 +142,11 @@ impl Tray {
             nid.uID = TRAY_UID;
             nid.uFlags = NIF_MESSAGE | NIF_TIP | NIF_ICON;
             nid.uCallbackMessage = TRAY_MSG;
-            // Load our embedded app icon; fallback to stock if needed
-            nid.hIcon = Self::load_app_icon();
+            // The custom or embedded app icon; fallback to stock if needed
+            nid.hIcon = Self::current_icon();
             // Set tooltip
-            let wtip = to_utf16(tip);
-            let lt = wtip.len().min(nid.szTip.len());
-            nid.szTip[..lt].copy_from_slice(&wtip[..lt]);
+            Self::store_tip(tip);
+            Self::fill_tip(&mut nid);
             if !Shell_NotifyIconW(NIM_ADD, &nid).as_bool() {
                 return Err(anyhow!("Shell_NotifyIconW(NIM_ADD) failed"));
             }
@@ -66,10 +154,54 @@ impl Tray {
         }
     }
 
-    pub fn remove_icon(&mut self) {
+    /// Remember `tip` (cut to fit `szTip`) as the current tooltip.
+    fn store_tip(tip: &str) -> bool {
+        let tip = crate::core::truncate_utf16(tip, MAX_TIP_UNITS);
+        let Ok(mut guard) = TOOLTIP.lock() else {
+            return false;
+        };
+        if *guard == tip {
+            return false;
+        }
+        *guard = tip;
+        true
+    }
+
+    /// Copy the current tooltip (nul-terminated) into `nid.szTip`.
+    fn fill_tip(nid: &mut NOTIFYICONDATAW) {
+        let tip = TOOLTIP.lock().map(|g| g.clone()).unwrap_or_default();
+        let tip = if tip.is_empty() {
+            "Desktop Labeler".to_string()
+        } else {
+            tip
+        };
+        let wtip = to_utf16(&tip);
+        let lt = wtip.len().min(nid.szTip.len());
+        nid.szTip[..lt].copy_from_slice(&wtip[..lt]);
+    }
+
+    /// Change the tooltip shown when hovering `hwnd`'s tray icon. Takes the window rather
+    /// than a `Tray`, so no `RefCell` borrow is needed; skips unchanged tips.
+    pub fn set_tooltip_for(hwnd: HWND, tip: &str) -> Result<()> {
+        if !Self::store_tip(tip) {
+            return Ok(());
+        }
         unsafe {
-            let _ = Shell_NotifyIconW(NIM_DELETE, &self.nid);
+            let mut nid: NOTIFYICONDATAW = zeroed();
+            nid.cbSize = size_of::<NOTIFYICONDATAW>() as u32;
+            nid.hWnd = hwnd;
+            nid.uID = TRAY_UID;
+            nid.uFlags = NIF_TIP;
+            Self::fill_tip(&mut nid);
+            if !Shell_NotifyIconW(NIM_MODIFY, &nid).as_bool() {
+                return Err(anyhow!("Shell_NotifyIconW(NIM_MODIFY) failed"));
+            }
         }
+        Ok(())
+    }
+
+    pub fn remove_icon(&mut self) {
+        let _ = Self::remove_for(self.nid.hWnd);
     }
 
     pub fn re_add(&mut self) {
@@ -87,51 +219,199 @@ impl Tray {
             self.nid.szInfoTitle[..lt].copy_from_slice(&wtitle[..lt]);
             let li = wtext.len().min(self.nid.szInfo.len());
             self.nid.szInfo[..li].copy_from_slice(&wtext[..li]);
-            self.nid.hIcon = Self::load_app_icon();
+            self.nid.hIcon = Self::current_icon();
             let _ = Shell_NotifyIconW(NIM_MODIFY, &self.nid);
         }
     }
 
-    pub fn show_menu(&self, hwnd: HWND) -> Result<()> {
+    /// Show the `.ico` at `path` instead of the app icon from the next add or modify on (see
+    /// [`Tray::refresh_icon_for`]); `None` goes back to the app icon. A file that is missing or
+    /// not an icon leaves the app icon in place and is returned as an error.
+    pub fn set_icon_path(path: Option<&Path>) -> Result<()> {
+        let mut guard = CUSTOM_ICON
+            .lock()
+            .map_err(|_| anyhow!("tray icon lock poisoned"))?;
+        if guard.as_ref().map(|(p, _)| p.as_path()) == path {
+            return Ok(());
+        }
+        if let Some((_, old)) = guard.take() {
+            unsafe {
+                let _ = DestroyIcon(HICON(old as *mut c_void));
+            }
+        }
+        let Some(path) = path else {
+            return Ok(());
+        };
+        let wpath = to_utf16(&path.display().to_string());
+        let icon = unsafe {
+            LoadImageW(
+                HINSTANCE::default(),
+                PCWSTR(wpath.as_ptr()),
+                IMAGE_ICON,
+                GetSystemMetrics(SM_CXSMICON),
+                GetSystemMetrics(SM_CYSMICON),
+                LR_LOADFROMFILE,
+            )
+        }
+        .map_err(|e| anyhow!("load tray icon {}: {e}", path.display()))?;
+        *guard = Some((path.to_path_buf(), icon.0 as isize));
+        Ok(())
+    }
+
+    /// Push the current icon (custom or built-in, re-badged) to the tray after
+    /// [`Tray::set_icon_path`].
+    pub fn refresh_icon_for(hwnd: HWND) -> Result<()> {
+        // Drop the badge composed on the old icon, then compose it again on the new one.
+        let badge = BADGE_ICON
+            .lock()
+            .map_err(|_| anyhow!("tray badge lock poisoned"))?
+            .take();
+        if let Some((text, old)) = badge {
+            unsafe {
+                let _ = DestroyIcon(HICON(old as *mut c_void));
+            }
+            return Self::set_badge_for(hwnd, Some(&text));
+        }
+        unsafe {
+            let mut nid: NOTIFYICONDATAW = zeroed();
+            nid.cbSize = size_of::<NOTIFYICONDATAW>() as u32;
+            nid.hWnd = hwnd;
+            nid.uID = TRAY_UID;
+            nid.uFlags = NIF_ICON;
+            nid.hIcon = Self::current_icon();
+            if !Shell_NotifyIconW(NIM_MODIFY, &nid).as_bool() {
+                return Err(anyhow!("Shell_NotifyIconW(NIM_MODIFY) failed"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Grey out (or re-enable) the items that change labels (edit, manage, import).
+    pub fn set_edits_locked(locked: bool) {
+        EDITS_LOCKED.store(locked, Ordering::Relaxed);
+    }
+
+    /// Grey out (or re-enable) New Desktop / Remove This Desktop.
+    pub fn set_desktops_available(available: bool) {
+        DESKTOPS_AVAILABLE.store(available, Ordering::Relaxed);
+    }
+
+    pub fn show_menu(&self, hwnd: HWND, checks: MenuChecks) -> Result<()> {
         unsafe {
             let hmenu = CreatePopupMenu()?;
+            let edit_flags = if EDITS_LOCKED.load(Ordering::Relaxed) {
+                MF_STRING | MF_GRAYED
+            } else {
+                MF_STRING
+            };
             AppendMenuW(
                 hmenu,
-                MF_STRING,
+                edit_flags,
                 CMD_EDIT_TITLE as usize,
                 PCWSTR(windows::core::w!("Edit Title").as_wide().as_ptr()),
             )?;
             AppendMenuW(
                 hmenu,
-                MF_STRING,
+                edit_flags,
                 CMD_EDIT_DESC as usize,
                 PCWSTR(windows::core::w!("Edit Description").as_wide().as_ptr()),
             )?;
+            AppendMenuW(
+                hmenu,
+                edit_flags,
+                CMD_MANAGE_LABELS as usize,
+                PCWSTR(windows::core::w!("Manage Labels...").as_wide().as_ptr()),
+            )?;
             AppendMenuW(hmenu, MF_SEPARATOR, 0, PCWSTR::null())?;
             AppendMenuW(
                 hmenu,
-                MF_STRING,
+                checked(MF_STRING, checks.overlay_visible),
                 CMD_TOGGLE as usize,
                 PCWSTR(windows::core::w!("Toggle Overlay").as_wide().as_ptr()),
             )?;
+            AppendMenuW(
+                hmenu,
+                checked(edit_flags, checks.hide_on_fullscreen),
+                CMD_TOGGLE_HIDE_FULLSCREEN as usize,
+                PCWSTR(windows::core::w!("Hide When Fullscreen").as_wide().as_ptr()),
+            )?;
+            AppendMenuW(
+                hmenu,
+                checked(edit_flags, checks.desktop_hidden),
+                CMD_TOGGLE_DESKTOP_HIDDEN as usize,
+                PCWSTR(windows::core::w!("Hide on This Desktop").as_wide().as_ptr()),
+            )?;
+            let desktop_flags = if DESKTOPS_AVAILABLE.load(Ordering::Relaxed) {
+                MF_STRING
+            } else {
+                MF_STRING | MF_GRAYED
+            };
+            AppendMenuW(
+                hmenu,
+                desktop_flags,
+                CMD_NEW_DESKTOP as usize,
+                PCWSTR(windows::core::w!("New Desktop...").as_wide().as_ptr()),
+            )?;
+            AppendMenuW(
+                hmenu,
+                desktop_flags,
+                CMD_REMOVE_DESKTOP as usize,
+                PCWSTR(windows::core::w!("Remove This Desktop").as_wide().as_ptr()),
+            )?;
             AppendMenuW(
                 hmenu,
                 MF_STRING,
                 CMD_OPEN_CONFIG as usize,
                 PCWSTR(windows::core::w!("Open Config").as_wide().as_ptr()),
             )?;
+            AppendMenuW(
+                hmenu,
+                MF_STRING,
+                CMD_EXPORT_CONFIG as usize,
+                PCWSTR(windows::core::w!("Export Config...").as_wide().as_ptr()),
+            )?;
+            AppendMenuW(
+                hmenu,
+                MF_STRING,
+                CMD_COPY_LABELS as usize,
+                PCWSTR(windows::core::w!("Copy Labels").as_wide().as_ptr()),
+            )?;
+            AppendMenuW(
+                hmenu,
+                edit_flags,
+                CMD_IMPORT_CONFIG as usize,
+                PCWSTR(windows::core::w!("Import Config...").as_wide().as_ptr()),
+            )?;
+            AppendMenuW(
+                hmenu,
+                edit_flags,
+                CMD_RESET_CONFIG as usize,
+                PCWSTR(windows::core::w!("Reset Config...").as_wide().as_ptr()),
+            )?;
+            AppendMenuW(
+                hmenu,
+                edit_flags,
+                CMD_EDIT_HOTKEYS as usize,
+                PCWSTR(windows::core::w!("Edit Hotkeys...").as_wide().as_ptr()),
+            )?;
+            AppendMenuW(
+                hmenu,
+                MF_STRING,
+                CMD_REHOOK as usize,
+                PCWSTR(windows::core::w!("Re-register Hotkeys").as_wide().as_ptr()),
+            )?;
             AppendMenuW(hmenu, MF_SEPARATOR, 0, PCWSTR::null())?;
             AppendMenuW(
                 hmenu,
                 MF_STRING,
-                CMD_RUN_AT_STARTUP as usize,
-                PCWSTR(windows::core::w!("Run at startup").as_wide().as_ptr()),
+                CMD_TOGGLE_AUTORUN as usize,
+                PCWSTR(windows::core::w!("Run at login").as_wide().as_ptr()),
             )?;
-            // Reflect current autorun state
+            // Reflect current autorun state; read on every open since the registry can change externally
             let enabled = crate::autorun::get_run_at_login();
             let _ = CheckMenuItem(
                 hmenu,
-                CMD_RUN_AT_STARTUP as u32,
+                CMD_TOGGLE_AUTORUN as u32,
                 (MF_BYCOMMAND | if enabled { MF_CHECKED } else { MF_UNCHECKED }).0,
             );
             AppendMenuW(
@@ -157,11 +437,13 @@ impl Tray {
     }
 
     // Static helpers to avoid borrowing AppState across re-entrant shell calls
-    pub fn show_popup_menu(hwnd: HWND) -> Result<()> {
+    /// Build the menu from `checks` and track it; the caller snapshots them first, since
+    /// `TrackPopupMenu` pumps messages and must not run under a borrow.
+    pub fn show_popup_menu_with_state(hwnd: HWND, checks: MenuChecks) -> Result<()> {
         Self {
             nid: unsafe { std::mem::zeroed() },
         }
-        .show_menu(hwnd)
+        .show_menu(hwnd, checks)
     }
 
     pub fn balloon_for(hwnd: HWND, title: &str, text: &str) -> Result<()> {
@@ -177,22 +459,32 @@ impl Tray {
             nid.szInfoTitle[..lt].copy_from_slice(&wtitle[..lt]);
             let li = wtext.len().min(nid.szInfo.len());
             nid.szInfo[..li].copy_from_slice(&wtext[..li]);
-            nid.hIcon = Self::load_app_icon();
+            nid.hIcon = Self::current_icon();
             if !Shell_NotifyIconW(NIM_MODIFY, &nid).as_bool() {
                 // In case the icon is missing (e.g., Explorer restart), re-add then modify.
                 nid.uFlags = NIF_MESSAGE | NIF_TIP | NIF_ICON | NIF_INFO;
                 nid.uCallbackMessage = TRAY_MSG;
-                nid.hIcon = Self::load_app_icon();
-                let tip = "Desktop Labeler";
-                let wtip = to_utf16(tip);
-                let lt2 = wtip.len().min(nid.szTip.len());
-                nid.szTip[..lt2].copy_from_slice(&wtip[..lt2]);
+                nid.hIcon = Self::current_icon();
+                Self::fill_tip(&mut nid);
                 let _ = Shell_NotifyIconW(NIM_ADD, &nid);
             }
         }
         Ok(())
     }
 
+    /// Delete the icon owned by `hwnd` (NIM_DELETE) so no ghost is left in the notification
+    /// area after exit. Needs only the window, like `re_add_for`.
+    pub fn remove_for(hwnd: HWND) -> Result<()> {
+        unsafe {
+            let mut nid: NOTIFYICONDATAW = std::mem::zeroed();
+            nid.cbSize = size_of::<NOTIFYICONDATAW>() as u32;
+            nid.hWnd = hwnd;
+            nid.uID = TRAY_UID;
+            let _ = Shell_NotifyIconW(NIM_DELETE, &nid);
+        }
+        Ok(())
+    }
+
     pub fn re_add_for(hwnd: HWND) -> Result<()> {
         unsafe {
             let mut nid: NOTIFYICONDATAW = std::mem::zeroed();
@@ -201,13 +493,154 @@ impl Tray {
             nid.uID = TRAY_UID;
             nid.uFlags = NIF_MESSAGE | NIF_TIP | NIF_ICON;
             nid.uCallbackMessage = TRAY_MSG;
-            nid.hIcon = Self::load_app_icon();
-            let tip = "Desktop Labeler";
-            let wtip = to_utf16(tip);
-            let lt = wtip.len().min(nid.szTip.len());
-            nid.szTip[..lt].copy_from_slice(&wtip[..lt]);
+            nid.hIcon = Self::current_icon();
+            Self::fill_tip(&mut nid);
             let _ = Shell_NotifyIconW(NIM_ADD, &nid);
         }
         Ok(())
     }
+
+    /// Composite `badge` (e.g. "3" or "B") onto the tray icon, or restore the plain icon for `None`.
+    pub fn set_badge_for(hwnd: HWND, badge: Option<&str>) -> Result<()> {
+        let mut guard = BADGE_ICON
+            .lock()
+            .map_err(|_| anyhow!("tray badge lock poisoned"))?;
+        if guard.as_ref().map(|(t, _)| t.as_str()) == badge {
+            return Ok(());
+        }
+        let new_icon = match badge {
+            Some(text) => Some((
+                text.to_string(),
+                compose_badge_icon(Self::load_app_icon(), text)?,
+            )),
+            None => None,
+        };
+        unsafe {
+            let mut nid: NOTIFYICONDATAW = zeroed();
+            nid.cbSize = size_of::<NOTIFYICONDATAW>() as u32;
+            nid.hWnd = hwnd;
+            nid.uID = TRAY_UID;
+            nid.uFlags = NIF_ICON;
+            nid.hIcon = match &new_icon {
+                Some((_, h)) => *h,
+                None => Self::load_app_icon(),
+            };
+            let _ = Shell_NotifyIconW(NIM_MODIFY, &nid);
+            // The shell keeps its own copy, so the previous badge icon can go.
+            if let Some((_, old)) = guard.take() {
+                let _ = DestroyIcon(HICON(old as *mut c_void));
+            }
+        }
+        *guard = new_icon.map(|(t, h)| (t, h.0 as isize));
+        Ok(())
+    }
+}
+
+/// Draw `base` at small-icon size with `text` in a filled box in the bottom-right corner.
+fn compose_badge_icon(base: HICON, text: &str) -> Result<HICON> {
+    unsafe {
+        let size = GetSystemMetrics(SM_CXSMICON).max(16);
+        let dc = CreateCompatibleDC(HDC(std::ptr::null_mut()));
+        if dc.0.is_null() {
+            return Err(anyhow!("CreateCompatibleDC failed"));
+        }
+        let mut bi: BITMAPINFO = zeroed();
+        bi.bmiHeader.biSize = size_of::<BITMAPINFOHEADER>() as u32;
+        bi.bmiHeader.biWidth = size;
+        bi.bmiHeader.biHeight = -size; // top-down
+        bi.bmiHeader.biPlanes = 1;
+        bi.bmiHeader.biBitCount = 32;
+        bi.bmiHeader.biCompression = BI_RGB.0;
+        let mut bits: *mut c_void = std::ptr::null_mut();
+        let color = match CreateDIBSection(dc, &bi, DIB_RGB_COLORS, &mut bits, HANDLE::default(), 0)
+        {
+            Ok(b) => b,
+            Err(e) => {
+                let _ = DeleteDC(dc);
+                return Err(e.into());
+            }
+        };
+        let old_bmp = SelectObject(dc, HGDIOBJ(color.0));
+        let _ = DrawIconEx(dc, 0, 0, base, size, size, 0, HBRUSH::default(), DI_NORMAL);
+
+        // Badge box: a square for one glyph, the full width for longer text (e.g. "10").
+        let box_h = size * 3 / 5;
+        let box_w = if text.chars().count() > 1 {
+            size
+        } else {
+            box_h
+        };
+        let badge = RECT {
+            left: size - box_w,
+            top: size - box_h,
+            right: size,
+            bottom: size,
+        };
+        let _ = GdiFlush();
+        let buf = std::slice::from_raw_parts_mut(bits as *mut u8, (size * size * 4) as usize);
+        let fill_badge = |buf: &mut [u8], bg: Option<[u8; 3]>| {
+            for y in badge.top..badge.bottom {
+                for x in badge.left..badge.right {
+                    let i = ((y * size + x) * 4) as usize;
+                    if let Some([b, g, r]) = bg {
+                        buf[i] = b;
+                        buf[i + 1] = g;
+                        buf[i + 2] = r;
+                    }
+                    buf[i + 3] = 255;
+                }
+            }
+        };
+        fill_badge(buf, Some([0x30, 0x30, 0xC8]));
+
+        let wface = to_utf16("Segoe UI");
+        let font = CreateFontW(
+            -box_h,
+            0,
+            0,
+            0,
+            FW_BOLD.0 as i32,
+            0,
+            0,
+            0,
+            DEFAULT_CHARSET.0 as u32,
+            0,
+            0,
+            CLEARTYPE_QUALITY.0 as u32,
+            DEFAULT_PITCH.0 as u32,
+            PCWSTR(wface.as_ptr()),
+        );
+        let old_font = SelectObject(dc, HGDIOBJ(font.0));
+        SetBkMode(dc, TRANSPARENT);
+        let _ = SetTextColor(dc, windows::Win32::Foundation::COLORREF(0x00FFFFFF));
+        let mut rc = badge;
+        let mut wtext: Vec<u16> = text.encode_utf16().collect();
+        let _ = DrawTextW(
+            dc,
+            &mut wtext,
+            &mut rc,
+            DT_CENTER | DT_VCENTER | DT_SINGLELINE | DT_NOPREFIX,
+        );
+        let _ = GdiFlush();
+        // GDI text leaves alpha at 0; make the whole badge opaque again.
+        fill_badge(buf, None);
+
+        SelectObject(dc, old_font);
+        let _ = DeleteObject(HGDIOBJ(font.0));
+        SelectObject(dc, old_bmp);
+
+        let mask = CreateBitmap(size, size, 1, 1, None);
+        let info = ICONINFO {
+            fIcon: BOOL(1),
+            xHotspot: 0,
+            yHotspot: 0,
+            hbmMask: mask,
+            hbmColor: color,
+        };
+        let icon = CreateIconIndirect(&info);
+        let _ = DeleteObject(HGDIOBJ(mask.0));
+        let _ = DeleteObject(HGDIOBJ(color.0));
+        let _ = DeleteDC(dc);
+        Ok(icon?)
+    }
 }
@@ -1,12 +1,27 @@
+use crate::config::{DialogPlacement, ImportMode, KeyChord, LabelEdit};
+use crate::hotkeys;
 use crate::utils::to_utf16;
 use core::ffi::c_void;
-use windows::Win32::Foundation::{HWND, LPARAM, LRESULT, RECT, WPARAM};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use windows::Win32::Foundation::{COLORREF, HWND, LPARAM, LRESULT, POINT, RECT, WPARAM};
 use windows::Win32::Foundation::{LPARAM as LPARAM_T, WPARAM as WPARAM_T};
-use windows::Win32::Graphics::Gdi::{DEFAULT_GUI_FONT, GetStockObject};
+use windows::Win32::Graphics::Gdi::{
+    COLOR_BTNFACE, COLOR_HIGHLIGHT, COLOR_HIGHLIGHTTEXT, DEFAULT_GUI_FONT, GetMonitorInfoW,
+    GetStockObject, GetSysColor, GetSysColorBrush, HDC, MONITOR_DEFAULTTONEAREST, MONITORINFO,
+    MonitorFromPoint, MonitorFromWindow, SetBkColor, SetTextColor,
+};
 use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+use windows::Win32::UI::Controls::Dialogs::{
+    GetOpenFileNameW, GetSaveFileNameW, OFN_FILEMUSTEXIST, OFN_NOCHANGEDIR, OFN_OVERWRITEPROMPT,
+    OFN_PATHMUSTEXIST, OPENFILENAMEW,
+};
 use windows::Win32::UI::HiDpi::{AdjustWindowRectExForDpi, GetDpiForWindow};
+use windows::Win32::UI::Input::KeyboardAndMouse::{
+    GetKeyState, VIRTUAL_KEY, VK_CONTROL, VK_MENU, VK_SHIFT,
+};
 use windows::Win32::UI::WindowsAndMessaging::*;
-use windows::core::PCWSTR;
+use windows::core::{PCWSTR, PWSTR};
 
 // Fallback FFI for SetFocus: windows crate may not expose it in all builds
 #[cfg(windows)]
@@ -20,11 +35,18 @@ struct DialogState {
     hint: String,
     done: bool,
     edit_hwnd: HWND,
+    max_len: usize,
 }
 
+/// Edit cap used when the caller does not pass one.
+const DEFAULT_MAX_LEN: usize = 200;
+
 const EM_LIMITTEXT: u32 = 0x00C5;
 const EM_SETSEL: u32 = 0x00B1;
 const SS_LEFT: u32 = 0x0000;
+const SS_CENTERIMAGE: u32 = 0x0200;
+const BM_GETCHECK: u32 = 0x00F0;
+const BST_CHECKED: usize = 1;
 
 fn scale(dpi: u32, v: i32) -> i32 {
     ((v as i64 * dpi as i64 + 48) / 96) as i32
@@ -44,6 +66,13 @@ unsafe fn set_ctrl_font(hwnd: HWND) {
 
 #[allow(unsafe_op_in_unsafe_fn)]
 unsafe fn layout_dialog(hwnd: HWND) {
+    layout_dialog_rows(hwnd, &[(1000, 1001)]);
+}
+
+/// Stack one `(label id, edit id)` row per field above the OK/Cancel buttons, then size
+/// the window to fit.
+#[allow(unsafe_op_in_unsafe_fn)]
+unsafe fn layout_dialog_rows(hwnd: HWND, rows: &[(i32, i32)]) {
     let dpi = GetDpiForWindow(hwnd);
     let margin = scale(dpi, 12);
     let gap = scale(dpi, 8);
@@ -53,60 +82,31 @@ unsafe fn layout_dialog(hwnd: HWND) {
     let btn_h = scale(dpi, 28);
     let client_w = scale(dpi, 460);
 
-    // Controls
-    let hlabel = GetDlgItem(hwnd, 1000)
-        .ok()
-        .unwrap_or(HWND(std::ptr::null_mut()));
-    let hedit = GetDlgItem(hwnd, 1001)
-        .ok()
-        .unwrap_or(HWND(std::ptr::null_mut()));
-    let hok = GetDlgItem(hwnd, 1)
-        .ok()
-        .unwrap_or(HWND(std::ptr::null_mut()));
-    let hcancel = GetDlgItem(hwnd, 2)
-        .ok()
-        .unwrap_or(HWND(std::ptr::null_mut()));
-
-    let y_label = margin;
-    let y_edit = y_label + label_h + gap;
-    let y_btn = y_edit + edit_h + gap + scale(dpi, 6);
-
-    let ok_x = client_w - margin - btn_w * 2 - gap;
-    let cancel_x = client_w - margin - btn_w;
+    let item = |id: i32| {
+        GetDlgItem(hwnd, id)
+            .ok()
+            .unwrap_or(HWND(std::ptr::null_mut()))
+    };
+    let place = |h: HWND, x: i32, y: i32, w: i32, hh: i32| {
+        if !h.0.is_null() {
+            let _ = MoveWindow(h, x, y, w, hh, true);
+            set_ctrl_font(h);
+        }
+    };
 
-    if !hlabel.0.is_null() {
-        let _ = MoveWindow(
-            hlabel,
-            margin,
-            y_label,
-            client_w - margin * 2,
-            label_h,
-            true,
-        );
-    }
-    if !hedit.0.is_null() {
-        let _ = MoveWindow(hedit, margin, y_edit, client_w - margin * 2, edit_h, true);
-    }
-    if !hok.0.is_null() {
-        let _ = MoveWindow(hok, ok_x, y_btn, btn_w, btn_h, true);
-    }
-    if !hcancel.0.is_null() {
-        let _ = MoveWindow(hcancel, cancel_x, y_btn, btn_w, btn_h, true);
+    let mut y = margin;
+    for &(label_id, edit_id) in rows {
+        place(item(label_id), margin, y, client_w - margin * 2, label_h);
+        y += label_h + gap;
+        place(item(edit_id), margin, y, client_w - margin * 2, edit_h);
+        y += edit_h + gap;
     }
+    let y_btn = y + scale(dpi, 6);
 
-    // Set fonts
-    if !hlabel.0.is_null() {
-        set_ctrl_font(hlabel);
-    }
-    if !hedit.0.is_null() {
-        set_ctrl_font(hedit);
-    }
-    if !hok.0.is_null() {
-        set_ctrl_font(hok);
-    }
-    if !hcancel.0.is_null() {
-        set_ctrl_font(hcancel);
-    }
+    let ok_x = client_w - margin - btn_w * 2 - gap;
+    let cancel_x = client_w - margin - btn_w;
+    place(item(1), ok_x, y_btn, btn_w, btn_h);
+    place(item(2), cancel_x, y_btn, btn_w, btn_h);
 
     // Resize window to fit client + margins
     let client_h = y_btn + btn_h + margin;
@@ -137,6 +137,30 @@ unsafe fn layout_dialog(hwnd: HWND) {
 }
 
 pub fn prompt_text(parent: HWND, caption: &str, hint: &str, initial: &str) -> Option<String> {
+    prompt_text_at(parent, caption, hint, initial, DialogPlacement::Centered)
+}
+
+/// Like `prompt_text`, but positions the dialog according to `placement`.
+pub fn prompt_text_at(
+    parent: HWND,
+    caption: &str,
+    hint: &str,
+    initial: &str,
+    placement: DialogPlacement,
+) -> Option<String> {
+    prompt_text_limited(parent, caption, hint, initial, placement, DEFAULT_MAX_LEN)
+}
+
+/// Like `prompt_text_at`, with the edit control capped at `max_len` characters. Pasted
+/// text beyond the cap is cut off, and an over-long `initial` is truncated.
+pub fn prompt_text_limited(
+    parent: HWND,
+    caption: &str,
+    hint: &str,
+    initial: &str,
+    placement: DialogPlacement,
+    max_len: usize,
+) -> Option<String> {
     unsafe {
         tracing::debug!(caption=%caption, hint=%hint, initial=%initial, "prompt_text");
         let class = windows::core::w!("OverlayInputDlg");
@@ -151,17 +175,18 @@ pub fn prompt_text(parent: HWND, caption: &str, hint: &str, initial: &str) -> Op
         // Ignore error if already registered
         let _ = RegisterClassW(&wc);
 
-        // Center 420x140 window near the parent
+        // Place the 420x140 window per the configured placement
         let (w, h) = (420, 140);
-        let (x, y) = center_on_parent(parent, w, h);
+        let (x, y) = dialog_position(parent, placement, w, h);
         // Remember previous foreground to restore later
         let prev_fg = GetForegroundWindow();
         // Prepare initial state and pass pointer via lpParam so WM_CREATE can use it
         let state = Box::new(DialogState {
-            text: initial.to_string(),
+            text: crate::core::clamp_label(initial, max_len),
             hint: hint.to_string(),
             done: false,
             edit_hwnd: HWND(0 as _),
+            max_len,
         });
         let state_ptr = Box::into_raw(state);
 
@@ -204,7 +229,7 @@ pub fn prompt_text(parent: HWND, caption: &str, hint: &str, initial: &str) -> Op
                 let res = if boxed.text.is_empty() {
                     None
                 } else {
-                    Some(boxed.text)
+                    Some(crate::core::clamp_label(&boxed.text, boxed.max_len))
                 };
                 tracing::debug!(res=?res.as_deref(), "prompt_text: returning");
                 // Restore previous foreground window if valid
@@ -269,8 +294,10 @@ extern "system" fn dlg_wndproc(hwnd: HWND, msg: u32, w: WPARAM, l: LPARAM) -> LR
                     None,
                 )
                 .unwrap();
-                // Limit text length to 200 chars
-                let _ = SendMessageW(edit, EM_LIMITTEXT, WPARAM_T(200), LPARAM_T(0));
+                // Cap the text length (also cuts off oversized pastes)
+                let p = GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *mut DialogState;
+                let max_len = if p.is_null() { DEFAULT_MAX_LEN } else { (*p).max_len };
+                let _ = SendMessageW(edit, EM_LIMITTEXT, WPARAM_T(max_len), LPARAM_T(0));
                 let _ok = CreateWindowExW(
                     WINDOW_EX_STYLE(0),
                     PCWSTR(windows::core::w!("BUTTON").as_wide().as_ptr()),
@@ -395,16 +422,989 @@ extern "system" fn dlg_wndproc(hwnd: HWND, msg: u32, w: WPARAM, l: LPARAM) -> LR
     }
 }
 
-fn center_on_parent(parent: HWND, w: i32, h: i32) -> (i32, i32) {
+struct TwoFieldState {
+    title: String,
+    title_label: String,
+    description: String,
+    desc_label: String,
+    done: bool,
+    accepted: bool,
+    limits: (usize, usize),
+}
+
+const TWO_TITLE_LABEL: i32 = 1000;
+const TWO_TITLE_EDIT: i32 = 1001;
+const TWO_DESC_LABEL: i32 = 1002;
+const TWO_DESC_EDIT: i32 = 1003;
+
+/// Title and description in one dialog. Tab moves between the fields and Enter confirms.
+/// Returns `None` on Cancel; either field may come back empty.
+pub fn prompt_two_fields(
+    parent: HWND,
+    caption: &str,
+    title_label: &str,
+    title_initial: &str,
+    desc_label: &str,
+    desc_initial: &str,
+) -> Option<(String, String)> {
+    prompt_two_fields_at(
+        parent,
+        caption,
+        title_label,
+        title_initial,
+        desc_label,
+        desc_initial,
+        DialogPlacement::Centered,
+        (DEFAULT_MAX_LEN, DEFAULT_MAX_LEN),
+    )
+}
+
+/// Like `prompt_two_fields`, but positions the dialog according to `placement` and caps
+/// the fields at `limits` (title, description) characters.
+#[allow(clippy::too_many_arguments)]
+pub fn prompt_two_fields_at(
+    parent: HWND,
+    caption: &str,
+    title_label: &str,
+    title_initial: &str,
+    desc_label: &str,
+    desc_initial: &str,
+    placement: DialogPlacement,
+    limits: (usize, usize),
+) -> Option<(String, String)> {
     unsafe {
-        let mut rc: RECT = RECT::default();
-        if parent.0.is_null() || GetWindowRect(parent, &mut rc).is_err() {
-            let x = (GetSystemMetrics(SM_CXSCREEN) - w) / 2;
-            let y = (GetSystemMetrics(SM_CYSCREEN) - h) / 2;
-            return (x, y);
+        tracing::debug!(caption=%caption, "prompt_two_fields");
+        let class = windows::core::w!("OverlayInputDlg2");
+        let hinst = GetModuleHandleW(None).unwrap();
+        let wc = WNDCLASSW {
+            lpfnWndProc: Some(two_field_wndproc),
+            hInstance: hinst.into(),
+            hCursor: LoadCursorW(None, IDC_ARROW).unwrap_or_default(),
+            lpszClassName: class,
+            ..Default::default()
+        };
+        // Ignore error if already registered
+        let _ = RegisterClassW(&wc);
+
+        let (w, h) = (420, 200);
+        let (x, y) = dialog_position(parent, placement, w, h);
+        let prev_fg = GetForegroundWindow();
+        let state_ptr = Box::into_raw(Box::new(TwoFieldState {
+            title: crate::core::clamp_label(title_initial, limits.0),
+            title_label: title_label.to_string(),
+            description: crate::core::clamp_label(desc_initial, limits.1),
+            desc_label: desc_label.to_string(),
+            done: false,
+            accepted: false,
+            limits,
+        }));
+
+        let hwnd = match CreateWindowExW(
+            WINDOW_EX_STYLE(WS_EX_TOOLWINDOW.0 | WS_EX_TOPMOST.0 | WS_EX_CONTROLPARENT.0),
+            class,
+            PCWSTR(to_utf16(caption).as_ptr()),
+            WS_CAPTION | WS_SYSMENU | WS_POPUPWINDOW,
+            x,
+            y,
+            w,
+            h,
+            parent,
+            None,
+            hinst,
+            Some(state_ptr as *mut core::ffi::c_void),
+        ) {
+            Ok(h) => h,
+            Err(_) => {
+                let _ = Box::from_raw(state_ptr);
+                return None;
+            }
+        };
+
+        let _ = ShowWindow(hwnd, SW_SHOW);
+        let _ = SetForegroundWindow(hwnd);
+
+        // Modal loop; IsDialogMessageW provides Tab/Shift+Tab and Enter -> IDOK.
+        let mut msg = MSG::default();
+        while GetMessageW(&mut msg, HWND(0 as _), 0, 0).into() {
+            if !IsDialogMessageW(hwnd, &msg).as_bool() {
+                let _ = TranslateMessage(&msg);
+                DispatchMessageW(&msg);
+            }
+            if (*state_ptr).done {
+                break;
+            }
+        }
+        let state = Box::from_raw(state_ptr);
+        if !prev_fg.0.is_null() && prev_fg != hwnd {
+            let _ = SetForegroundWindow(prev_fg);
+        }
+        tracing::debug!(accepted = state.accepted, "prompt_two_fields: returning");
+        if state.done && state.accepted {
+            Some((
+                crate::core::clamp_label(&state.title, limits.0),
+                crate::core::clamp_label(&state.description, limits.1),
+            ))
+        } else {
+            None
         }
-        let cx = rc.left + ((rc.right - rc.left - w) / 2);
-        let cy = rc.top + ((rc.bottom - rc.top - h) / 2);
-        (cx, cy)
+    }
+}
+
+#[allow(unsafe_op_in_unsafe_fn)]
+unsafe fn edit_text(hwnd: HWND, id: i32) -> String {
+    let Ok(edit) = GetDlgItem(hwnd, id) else {
+        return String::new();
+    };
+    let len = GetWindowTextLengthW(edit);
+    let mut buf: Vec<u16> = vec![0; (len + 1) as usize];
+    let n = GetWindowTextW(edit, &mut buf);
+    String::from_utf16_lossy(&buf[..n.max(0) as usize])
+}
+
+extern "system" fn two_field_wndproc(hwnd: HWND, msg: u32, w: WPARAM, l: LPARAM) -> LRESULT {
+    unsafe {
+        let state = || GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *mut TwoFieldState;
+        match msg {
+            WM_CREATE => {
+                let cs: &CREATESTRUCTW = &*(l.0 as *const CREATESTRUCTW);
+                SetWindowLongPtrW(hwnd, GWLP_USERDATA, cs.lpCreateParams as isize);
+                let p = state();
+                if p.is_null() {
+                    return LRESULT(-1);
+                }
+                let hinst = GetModuleHandleW(None).unwrap();
+                let child = |ex: u32, class: PCWSTR, text: &str, style: u32, id: i32| {
+                    CreateWindowExW(
+                        WINDOW_EX_STYLE(ex),
+                        class,
+                        PCWSTR(to_utf16(text).as_ptr()),
+                        WINDOW_STYLE(WS_CHILD.0 | WS_VISIBLE.0 | style),
+                        0,
+                        0,
+                        0,
+                        0,
+                        hwnd,
+                        menu_id(id as usize),
+                        hinst,
+                        None,
+                    )
+                    .unwrap_or_default()
+                };
+                let edit_style = WS_BORDER.0 | WS_TABSTOP.0 | (ES_LEFT as u32) | (ES_AUTOHSCROLL as u32);
+                let static_class = PCWSTR(windows::core::w!("STATIC").as_wide().as_ptr());
+                let edit_class = PCWSTR(windows::core::w!("EDIT").as_wide().as_ptr());
+                let button_class = PCWSTR(windows::core::w!("BUTTON").as_wide().as_ptr());
+                child(0, static_class, &(*p).title_label, SS_LEFT, TWO_TITLE_LABEL);
+                let title_edit = child(WS_EX_CLIENTEDGE.0, edit_class, &(*p).title, edit_style, TWO_TITLE_EDIT);
+                child(0, static_class, &(*p).desc_label, SS_LEFT, TWO_DESC_LABEL);
+                let desc_edit = child(WS_EX_CLIENTEDGE.0, edit_class, &(*p).description, edit_style, TWO_DESC_EDIT);
+                let (title_max, desc_max) = (*p).limits;
+                for (edit, max_len) in [(title_edit, title_max), (desc_edit, desc_max)] {
+                    let _ = SendMessageW(edit, EM_LIMITTEXT, WPARAM_T(max_len), LPARAM_T(0));
+                }
+                child(0, button_class, "OK", WS_TABSTOP.0 | (BS_DEFPUSHBUTTON as u32), 1);
+                child(0, button_class, "Cancel", WS_TABSTOP.0, 2);
+                layout_dialog_rows(hwnd, &[(TWO_TITLE_LABEL, TWO_TITLE_EDIT), (TWO_DESC_LABEL, TWO_DESC_EDIT)]);
+                let _ = SetFocus(title_edit);
+                let _ = SendMessageW(title_edit, EM_SETSEL, WPARAM_T(0), LPARAM_T(-1));
+                LRESULT(0)
+            }
+            0x02E0 /* WM_DPICHANGED */ => {
+                layout_dialog_rows(hwnd, &[(TWO_TITLE_LABEL, TWO_TITLE_EDIT), (TWO_DESC_LABEL, TWO_DESC_EDIT)]);
+                LRESULT(0)
+            }
+            WM_COMMAND => {
+                let id = (w.0 & 0xFFFF) as u16;
+                if id != 1 && id != 2 {
+                    return DefWindowProcW(hwnd, msg, w, l);
+                }
+                let p = state();
+                if !p.is_null() {
+                    if id == 1 {
+                        (*p).title = edit_text(hwnd, TWO_TITLE_EDIT);
+                        (*p).description = edit_text(hwnd, TWO_DESC_EDIT);
+                        (*p).accepted = true;
+                    }
+                    (*p).done = true;
+                }
+                let _ = DestroyWindow(hwnd);
+                LRESULT(0)
+            }
+            WM_CLOSE => {
+                let _ = SendMessageW(hwnd, WM_COMMAND, WPARAM_T(2), LPARAM_T(0));
+                LRESULT(0)
+            }
+            _ => DefWindowProcW(hwnd, msg, w, l),
+        }
+    }
+}
+
+struct ManageState {
+    rows: Vec<LabelEdit>,
+    current_guid: String,
+    visits: HashMap<String, u64>,
+    limits: (usize, usize),
+    done: bool,
+    accepted: bool,
+}
+
+/// First control id of the Manage Labels rows; each row uses four consecutive ids.
+const MANAGE_ROW_BASE: i32 = 2000;
+const MANAGE_HEADER_BASE: i32 = 1900;
+
+fn manage_row_id(row: usize, col: i32) -> i32 {
+    MANAGE_ROW_BASE + row as i32 * 4 + col
+}
+
+/// Modal window listing every desktop label with inline title/description edits and a
+/// Remove checkbox per row. The row for `current_guid` is highlighted, and rows with an entry
+/// in `visits` show their switch count.
+///
+/// Returns the edited rows on OK (see `config::apply_label_edits`), `None` on Cancel.
+pub fn manage_labels(
+    parent: HWND,
+    rows: Vec<LabelEdit>,
+    current_guid: &str,
+    visits: &HashMap<String, u64>,
+    placement: DialogPlacement,
+    limits: (usize, usize),
+) -> Option<Vec<LabelEdit>> {
+    unsafe {
+        tracing::debug!(rows = rows.len(), "manage_labels");
+        let class = windows::core::w!("OverlayManageLabelsDlg");
+        let hinst = GetModuleHandleW(None).unwrap();
+        let wc = WNDCLASSW {
+            lpfnWndProc: Some(manage_wndproc),
+            hInstance: hinst.into(),
+            hCursor: LoadCursorW(None, IDC_ARROW).unwrap_or_default(),
+            hbrBackground: GetSysColorBrush(COLOR_BTNFACE),
+            lpszClassName: class,
+            ..Default::default()
+        };
+        // Ignore error if already registered
+        let _ = RegisterClassW(&wc);
+
+        let (w, h) = (720, 120 + rows.len() as i32 * 36);
+        let (x, y) = dialog_position(parent, placement, w, h);
+        let prev_fg = GetForegroundWindow();
+        let state_ptr = Box::into_raw(Box::new(ManageState {
+            rows,
+            current_guid: current_guid.to_string(),
+            visits: visits.clone(),
+            limits,
+            done: false,
+            accepted: false,
+        }));
+
+        let hwnd = match CreateWindowExW(
+            WINDOW_EX_STYLE(WS_EX_TOOLWINDOW.0 | WS_EX_TOPMOST.0 | WS_EX_CONTROLPARENT.0),
+            class,
+            windows::core::w!("Manage Labels"),
+            WS_CAPTION | WS_SYSMENU | WS_POPUPWINDOW,
+            x,
+            y,
+            w,
+            h,
+            parent,
+            None,
+            hinst,
+            Some(state_ptr as *mut core::ffi::c_void),
+        ) {
+            Ok(h) => h,
+            Err(_) => {
+                let _ = Box::from_raw(state_ptr);
+                return None;
+            }
+        };
+
+        let _ = ShowWindow(hwnd, SW_SHOW);
+        let _ = SetForegroundWindow(hwnd);
+
+        let mut msg = MSG::default();
+        while GetMessageW(&mut msg, HWND(0 as _), 0, 0).into() {
+            if !IsDialogMessageW(hwnd, &msg).as_bool() {
+                let _ = TranslateMessage(&msg);
+                DispatchMessageW(&msg);
+            }
+            if (*state_ptr).done {
+                break;
+            }
+        }
+        let state = Box::from_raw(state_ptr);
+        if !prev_fg.0.is_null() && prev_fg != hwnd {
+            let _ = SetForegroundWindow(prev_fg);
+        }
+        tracing::debug!(accepted = state.accepted, "manage_labels: returning");
+        (state.done && state.accepted).then_some(state.rows)
+    }
+}
+
+#[allow(unsafe_op_in_unsafe_fn)]
+unsafe fn layout_manage(hwnd: HWND, rows: usize) {
+    let dpi = GetDpiForWindow(hwnd);
+    let margin = scale(dpi, 12);
+    let gap = scale(dpi, 8);
+    let row_h = scale(dpi, 26);
+    let guid_w = scale(dpi, 270);
+    let title_w = scale(dpi, 150);
+    let desc_w = scale(dpi, 200);
+    let remove_w = scale(dpi, 70);
+    let btn_w = scale(dpi, 88);
+    let btn_h = scale(dpi, 28);
+    let client_w = margin * 2 + guid_w + title_w + desc_w + remove_w + gap * 3;
+
+    let cols = [
+        (margin, guid_w),
+        (margin + guid_w + gap, title_w),
+        (margin + guid_w + title_w + gap * 2, desc_w),
+        (margin + guid_w + title_w + desc_w + gap * 3, remove_w),
+    ];
+    let place = |id: i32, x: i32, y: i32, w: i32, h: i32| {
+        if let Ok(ctrl) = GetDlgItem(hwnd, id) {
+            let _ = MoveWindow(ctrl, x, y, w, h, true);
+            set_ctrl_font(ctrl);
+        }
+    };
+    for (col, &(x, w)) in cols.iter().enumerate() {
+        place(
+            MANAGE_HEADER_BASE + col as i32,
+            x,
+            margin,
+            w,
+            scale(dpi, 20),
+        );
+    }
+    let mut y = margin + scale(dpi, 20) + gap;
+    for row in 0..rows {
+        for (col, &(x, w)) in cols.iter().enumerate() {
+            place(manage_row_id(row, col as i32), x, y, w, row_h);
+        }
+        y += row_h + gap / 2;
+    }
+    let y_btn = y + gap;
+    place(1, client_w - margin - btn_w * 2 - gap, y_btn, btn_w, btn_h);
+    place(2, client_w - margin - btn_w, y_btn, btn_w, btn_h);
+
+    let mut rc = RECT {
+        left: 0,
+        top: 0,
+        right: client_w,
+        bottom: y_btn + btn_h + margin,
+    };
+    let _ = AdjustWindowRectExForDpi(
+        &mut rc,
+        WS_CAPTION | WS_SYSMENU | WS_POPUPWINDOW,
+        false,
+        WINDOW_EX_STYLE(WS_EX_TOOLWINDOW.0 | WS_EX_TOPMOST.0),
+        dpi,
+    );
+    let _ = SetWindowPos(
+        hwnd,
+        None,
+        0,
+        0,
+        rc.right - rc.left,
+        rc.bottom - rc.top,
+        SWP_NOMOVE | SWP_NOZORDER | SWP_NOACTIVATE,
+    );
+}
+
+extern "system" fn manage_wndproc(hwnd: HWND, msg: u32, w: WPARAM, l: LPARAM) -> LRESULT {
+    unsafe {
+        let state = || GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *mut ManageState;
+        match msg {
+            WM_CREATE => {
+                let cs: &CREATESTRUCTW = &*(l.0 as *const CREATESTRUCTW);
+                SetWindowLongPtrW(hwnd, GWLP_USERDATA, cs.lpCreateParams as isize);
+                let p = state();
+                if p.is_null() {
+                    return LRESULT(-1);
+                }
+                let hinst = GetModuleHandleW(None).unwrap();
+                let child = |ex: u32, class: PCWSTR, text: &str, style: u32, id: i32| {
+                    CreateWindowExW(
+                        WINDOW_EX_STYLE(ex),
+                        class,
+                        PCWSTR(to_utf16(text).as_ptr()),
+                        WINDOW_STYLE(WS_CHILD.0 | WS_VISIBLE.0 | style),
+                        0,
+                        0,
+                        0,
+                        0,
+                        hwnd,
+                        menu_id(id as usize),
+                        hinst,
+                        None,
+                    )
+                    .unwrap_or_default()
+                };
+                let static_class = PCWSTR(windows::core::w!("STATIC").as_wide().as_ptr());
+                let edit_class = PCWSTR(windows::core::w!("EDIT").as_wide().as_ptr());
+                let button_class = PCWSTR(windows::core::w!("BUTTON").as_wide().as_ptr());
+                let edit_style = WS_BORDER.0 | WS_TABSTOP.0 | (ES_LEFT as u32) | (ES_AUTOHSCROLL as u32);
+                for (col, text) in ["Desktop", "Title", "Description", "Remove"].iter().enumerate() {
+                    child(0, static_class, text, SS_LEFT, MANAGE_HEADER_BASE + col as i32);
+                }
+                let (title_max, desc_max) = (*p).limits;
+                let mut first_edit = HWND(std::ptr::null_mut());
+                for (i, row) in (*p).rows.iter().enumerate() {
+                    let mut guid_text = if row.guid == (*p).current_guid {
+                        format!("{} (current)", row.guid)
+                    } else {
+                        row.guid.clone()
+                    };
+                    if let Some(&n) = (*p).visits.get(&row.guid) {
+                        let times = if n == 1 { "time" } else { "times" };
+                        guid_text.push_str(&format!(" \u{b7} visited {n} {times}"));
+                    }
+                    child(0, static_class, &guid_text, SS_LEFT | SS_CENTERIMAGE, manage_row_id(i, 0));
+                    let title = child(WS_EX_CLIENTEDGE.0, edit_class, &row.title, edit_style, manage_row_id(i, 1));
+                    let desc = child(WS_EX_CLIENTEDGE.0, edit_class, &row.description, edit_style, manage_row_id(i, 2));
+                    let _ = SendMessageW(title, EM_LIMITTEXT, WPARAM_T(title_max), LPARAM_T(0));
+                    let _ = SendMessageW(desc, EM_LIMITTEXT, WPARAM_T(desc_max), LPARAM_T(0));
+                    child(0, button_class, "Remove", WS_TABSTOP.0 | (BS_AUTOCHECKBOX as u32), manage_row_id(i, 3));
+                    if first_edit.0.is_null() || row.guid == (*p).current_guid {
+                        first_edit = title;
+                    }
+                }
+                child(0, button_class, "OK", WS_TABSTOP.0 | (BS_DEFPUSHBUTTON as u32), 1);
+                child(0, button_class, "Cancel", WS_TABSTOP.0, 2);
+                layout_manage(hwnd, (*p).rows.len());
+                if !first_edit.0.is_null() {
+                    let _ = SetFocus(first_edit);
+                    let _ = SendMessageW(first_edit, EM_SETSEL, WPARAM_T(0), LPARAM_T(-1));
+                }
+                LRESULT(0)
+            }
+            0x02E0 /* WM_DPICHANGED */ => {
+                let p = state();
+                if !p.is_null() {
+                    layout_manage(hwnd, (*p).rows.len());
+                }
+                LRESULT(0)
+            }
+            WM_CTLCOLORSTATIC => {
+                // Highlight the current desktop's row label.
+                let p = state();
+                let ctrl = HWND(l.0 as *mut c_void);
+                let current_row = if p.is_null() {
+                    None
+                } else {
+                    (*p).rows.iter().position(|r| r.guid == (*p).current_guid)
+                };
+                match current_row {
+                    Some(i) if GetDlgCtrlID(ctrl) == manage_row_id(i, 0) => {
+                        let hdc = HDC(w.0 as *mut c_void);
+                        let _ = SetTextColor(hdc, COLORREF(GetSysColor(COLOR_HIGHLIGHTTEXT)));
+                        let _ = SetBkColor(hdc, COLORREF(GetSysColor(COLOR_HIGHLIGHT)));
+                        LRESULT(GetSysColorBrush(COLOR_HIGHLIGHT).0 as isize)
+                    }
+                    _ => DefWindowProcW(hwnd, msg, w, l),
+                }
+            }
+            WM_COMMAND => {
+                let id = (w.0 & 0xFFFF) as u16;
+                if id != 1 && id != 2 {
+                    return DefWindowProcW(hwnd, msg, w, l);
+                }
+                let p = state();
+                if !p.is_null() {
+                    if id == 1 {
+                        for (i, row) in (*p).rows.iter_mut().enumerate() {
+                            row.title = edit_text(hwnd, manage_row_id(i, 1));
+                            row.description = edit_text(hwnd, manage_row_id(i, 2));
+                            row.remove = GetDlgItem(hwnd, manage_row_id(i, 3)).is_ok_and(|cb| {
+                                SendMessageW(cb, BM_GETCHECK, WPARAM_T(0), LPARAM_T(0)).0 as usize == BST_CHECKED
+                            });
+                        }
+                        (*p).accepted = true;
+                    }
+                    (*p).done = true;
+                }
+                let _ = DestroyWindow(hwnd);
+                LRESULT(0)
+            }
+            WM_CLOSE => {
+                let _ = SendMessageW(hwnd, WM_COMMAND, WPARAM_T(2), LPARAM_T(0));
+                LRESULT(0)
+            }
+            _ => DefWindowProcW(hwnd, msg, w, l),
+        }
+    }
+}
+
+struct CaptureState {
+    /// `(action name, current chord)` per combo entry.
+    actions: Vec<(String, KeyChord)>,
+    selected: usize,
+    captured: Option<KeyChord>,
+    edit_proc: WNDPROC,
+    done: bool,
+    accepted: bool,
+}
+
+const CAPTURE_ACTION_LABEL: i32 = 1000;
+const CAPTURE_ACTION_COMBO: i32 = 1001;
+const CAPTURE_KEY_LABEL: i32 = 1002;
+const CAPTURE_KEY_EDIT: i32 = 1003;
+const CAPTURE_ROWS: [(i32, i32); 2] = [
+    (CAPTURE_ACTION_LABEL, CAPTURE_ACTION_COMBO),
+    (CAPTURE_KEY_LABEL, CAPTURE_KEY_EDIT),
+];
+const CAPTURE_NEEDS_MODIFIER: &str = "Hold Ctrl, Alt or Shift with the key";
+
+/// Pick one of `actions` (`(name, current chord)`) and press a new chord for it. The key box
+/// records the combination as it is pressed; a key without Ctrl, Alt or Shift is refused.
+///
+/// Returns the chosen action's index and the new chord on OK, `None` on Cancel.
+pub fn capture_hotkey(
+    parent: HWND,
+    actions: Vec<(String, KeyChord)>,
+    placement: DialogPlacement,
+) -> Option<(usize, KeyChord)> {
+    if actions.is_empty() {
+        return None;
+    }
+    unsafe {
+        tracing::debug!(actions = actions.len(), "capture_hotkey");
+        let class = windows::core::w!("OverlayHotkeyDlg");
+        let hinst = GetModuleHandleW(None).unwrap();
+        let wc = WNDCLASSW {
+            lpfnWndProc: Some(capture_wndproc),
+            hInstance: hinst.into(),
+            hCursor: LoadCursorW(None, IDC_ARROW).unwrap_or_default(),
+            lpszClassName: class,
+            ..Default::default()
+        };
+        // Ignore error if already registered
+        let _ = RegisterClassW(&wc);
+
+        let (w, h) = (420, 200);
+        let (x, y) = dialog_position(parent, placement, w, h);
+        let prev_fg = GetForegroundWindow();
+        let state_ptr = Box::into_raw(Box::new(CaptureState {
+            actions,
+            selected: 0,
+            captured: None,
+            edit_proc: None,
+            done: false,
+            accepted: false,
+        }));
+
+        let hwnd = match CreateWindowExW(
+            WINDOW_EX_STYLE(WS_EX_TOOLWINDOW.0 | WS_EX_TOPMOST.0 | WS_EX_CONTROLPARENT.0),
+            class,
+            PCWSTR(windows::core::w!("Edit Hotkeys").as_wide().as_ptr()),
+            WS_CAPTION | WS_SYSMENU | WS_POPUPWINDOW,
+            x,
+            y,
+            w,
+            h,
+            parent,
+            None,
+            hinst,
+            Some(state_ptr as *mut core::ffi::c_void),
+        ) {
+            Ok(h) => h,
+            Err(_) => {
+                let _ = Box::from_raw(state_ptr);
+                return None;
+            }
+        };
+
+        let _ = ShowWindow(hwnd, SW_SHOW);
+        let _ = SetForegroundWindow(hwnd);
+
+        let mut msg = MSG::default();
+        while GetMessageW(&mut msg, HWND(0 as _), 0, 0).into() {
+            if !IsDialogMessageW(hwnd, &msg).as_bool() {
+                let _ = TranslateMessage(&msg);
+                DispatchMessageW(&msg);
+            }
+            if (*state_ptr).done {
+                break;
+            }
+        }
+        let state = Box::from_raw(state_ptr);
+        if !prev_fg.0.is_null() && prev_fg != hwnd {
+            let _ = SetForegroundWindow(prev_fg);
+        }
+        tracing::debug!(accepted = state.accepted, "capture_hotkey: returning");
+        if state.done && state.accepted {
+            state.captured.map(|chord| (state.selected, chord))
+        } else {
+            None
+        }
+    }
+}
+
+/// Show `text` in the key box of the capture dialog.
+#[allow(unsafe_op_in_unsafe_fn)]
+unsafe fn set_capture_text(dlg: HWND, text: &str) {
+    if let Ok(edit) = GetDlgItem(dlg, CAPTURE_KEY_EDIT) {
+        let _ = SetWindowTextW(edit, PCWSTR(to_utf16(text).as_ptr()));
+    }
+}
+
+/// Size the controls, then give the action combo room for its drop-down list.
+#[allow(unsafe_op_in_unsafe_fn)]
+unsafe fn layout_capture_dialog(hwnd: HWND) {
+    layout_dialog_rows(hwnd, &CAPTURE_ROWS);
+    if let Ok(combo) = GetDlgItem(hwnd, CAPTURE_ACTION_COMBO) {
+        let mut rc = RECT::default();
+        let _ = GetWindowRect(combo, &mut rc);
+        let list_h = scale(GetDpiForWindow(hwnd), 160);
+        let _ = SetWindowPos(
+            combo,
+            None,
+            0,
+            0,
+            rc.right - rc.left,
+            list_h,
+            SWP_NOMOVE | SWP_NOZORDER | SWP_NOACTIVATE,
+        );
+    }
+}
+
+/// Subclass proc for the key box: turns key presses into a chord instead of text.
+extern "system" fn capture_edit_proc(hwnd: HWND, msg: u32, w: WPARAM, l: LPARAM) -> LRESULT {
+    unsafe {
+        let dlg = GetParent(hwnd).unwrap_or_default();
+        let p = GetWindowLongPtrW(dlg, GWLP_USERDATA) as *mut CaptureState;
+        if p.is_null() {
+            return DefWindowProcW(hwnd, msg, w, l);
+        }
+        match msg {
+            // Keep Tab, Enter and Esc (with modifiers) away from the dialog manager.
+            WM_GETDLGCODE => LRESULT(DLGC_WANTALLKEYS as isize),
+            WM_KEYDOWN | WM_SYSKEYDOWN => {
+                let down = |vk: VIRTUAL_KEY| GetKeyState(vk.0 as i32) < 0;
+                let (ctrl, alt, shift) = (down(VK_CONTROL), down(VK_MENU), down(VK_SHIFT));
+                let vk = w.0 as u16;
+                // Bare Enter/Esc/Tab keep their dialog meaning.
+                if !(ctrl || alt || shift) {
+                    match vk {
+                        0x0D => {
+                            let _ = PostMessageW(dlg, WM_COMMAND, WPARAM_T(1), LPARAM_T(0));
+                            return LRESULT(0);
+                        }
+                        0x1B => {
+                            let _ = PostMessageW(dlg, WM_COMMAND, WPARAM_T(2), LPARAM_T(0));
+                            return LRESULT(0);
+                        }
+                        0x09 => {
+                            if let Ok(next) = GetNextDlgTabItem(dlg, hwnd, false) {
+                                let _ = SetFocus(next);
+                            }
+                            return LRESULT(0);
+                        }
+                        _ => {}
+                    }
+                }
+                match hotkeys::captured_chord(vk, ctrl, alt, shift) {
+                    Some(chord) => {
+                        set_capture_text(dlg, &hotkeys::format_chord(&chord));
+                        (*p).captured = Some(chord);
+                    }
+                    // Only modifiers so far: echo them and wait for the key.
+                    None if matches!(vk, 0x10..=0x12 | 0xA0..=0xA5) => {
+                        let held: Vec<&str> = [(ctrl, "Ctrl"), (alt, "Alt"), (shift, "Shift")]
+                            .into_iter()
+                            .filter_map(|(on, name)| on.then_some(name))
+                            .collect();
+                        set_capture_text(dlg, &format!("{}+", held.join("+")));
+                    }
+                    None if ctrl || alt || shift => {
+                        set_capture_text(dlg, "That key can't be used in a hotkey");
+                    }
+                    None => set_capture_text(dlg, CAPTURE_NEEDS_MODIFIER),
+                }
+                LRESULT(0)
+            }
+            // Swallow typed characters and Alt menu activation; the box is not for text.
+            WM_CHAR | WM_SYSCHAR | WM_KEYUP | WM_SYSKEYUP => LRESULT(0),
+            _ => CallWindowProcW((*p).edit_proc, hwnd, msg, w, l),
+        }
+    }
+}
+
+extern "system" fn capture_wndproc(hwnd: HWND, msg: u32, w: WPARAM, l: LPARAM) -> LRESULT {
+    unsafe {
+        let state = || GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *mut CaptureState;
+        match msg {
+            WM_CREATE => {
+                let cs: &CREATESTRUCTW = &*(l.0 as *const CREATESTRUCTW);
+                SetWindowLongPtrW(hwnd, GWLP_USERDATA, cs.lpCreateParams as isize);
+                let p = state();
+                if p.is_null() {
+                    return LRESULT(-1);
+                }
+                let hinst = GetModuleHandleW(None).unwrap();
+                let child = |ex: u32, class: PCWSTR, text: &str, style: u32, id: i32| {
+                    CreateWindowExW(
+                        WINDOW_EX_STYLE(ex),
+                        class,
+                        PCWSTR(to_utf16(text).as_ptr()),
+                        WINDOW_STYLE(WS_CHILD.0 | WS_VISIBLE.0 | style),
+                        0,
+                        0,
+                        0,
+                        0,
+                        hwnd,
+                        menu_id(id as usize),
+                        hinst,
+                        None,
+                    )
+                    .unwrap_or_default()
+                };
+                let static_class = PCWSTR(windows::core::w!("STATIC").as_wide().as_ptr());
+                let combo_class = PCWSTR(windows::core::w!("COMBOBOX").as_wide().as_ptr());
+                let edit_class = PCWSTR(windows::core::w!("EDIT").as_wide().as_ptr());
+                let button_class = PCWSTR(windows::core::w!("BUTTON").as_wide().as_ptr());
+                child(0, static_class, "Action:", SS_LEFT, CAPTURE_ACTION_LABEL);
+                let combo = child(
+                    0,
+                    combo_class,
+                    "",
+                    WS_TABSTOP.0 | WS_VSCROLL.0 | CBS_DROPDOWNLIST as u32,
+                    CAPTURE_ACTION_COMBO,
+                );
+                for (name, chord) in &(*p).actions {
+                    let item = to_utf16(&format!("{name} ({})", hotkeys::format_chord(chord)));
+                    let _ = SendMessageW(combo, CB_ADDSTRING, WPARAM_T(0), LPARAM_T(item.as_ptr() as isize));
+                }
+                let _ = SendMessageW(combo, CB_SETCURSEL, WPARAM_T(0), LPARAM_T(0));
+                child(
+                    0,
+                    static_class,
+                    "Press the new key combination:",
+                    SS_LEFT,
+                    CAPTURE_KEY_LABEL,
+                );
+                let current = hotkeys::format_chord(&(&(*p).actions)[0].1);
+                let edit = child(
+                    WS_EX_CLIENTEDGE.0,
+                    edit_class,
+                    &current,
+                    WS_BORDER.0 | WS_TABSTOP.0 | (ES_LEFT as u32) | (ES_AUTOHSCROLL as u32),
+                    CAPTURE_KEY_EDIT,
+                );
+                let prev = SetWindowLongPtrW(edit, GWLP_WNDPROC, capture_edit_proc as *const () as isize);
+                (*p).edit_proc = std::mem::transmute::<isize, WNDPROC>(prev);
+                child(0, button_class, "OK", WS_TABSTOP.0 | (BS_DEFPUSHBUTTON as u32), 1);
+                child(0, button_class, "Cancel", WS_TABSTOP.0, 2);
+                layout_capture_dialog(hwnd);
+                let _ = SetFocus(edit);
+                LRESULT(0)
+            }
+            0x02E0 /* WM_DPICHANGED */ => {
+                layout_capture_dialog(hwnd);
+                LRESULT(0)
+            }
+            WM_COMMAND => {
+                let id = (w.0 & 0xFFFF) as i32;
+                let code = ((w.0 >> 16) & 0xFFFF) as u32;
+                let p = state();
+                if p.is_null() {
+                    return DefWindowProcW(hwnd, msg, w, l);
+                }
+                if id == CAPTURE_ACTION_COMBO && code == CBN_SELCHANGE {
+                    // A new action starts from its current chord; nothing captured yet.
+                    let combo = HWND(l.0 as *mut c_void);
+                    let sel = SendMessageW(combo, CB_GETCURSEL, WPARAM_T(0), LPARAM_T(0)).0;
+                    let actions = &(*p).actions;
+                    if let Some((_, chord)) = usize::try_from(sel).ok().and_then(|i| actions.get(i)) {
+                        (*p).selected = sel as usize;
+                        (*p).captured = None;
+                        set_capture_text(hwnd, &hotkeys::format_chord(chord));
+                    }
+                    return LRESULT(0);
+                }
+                if id != 1 && id != 2 {
+                    return DefWindowProcW(hwnd, msg, w, l);
+                }
+                if id == 1 && (*p).captured.is_none() {
+                    // Nothing pressed yet: keep the dialog open and point at the key box.
+                    set_capture_text(hwnd, CAPTURE_NEEDS_MODIFIER);
+                    if let Ok(edit) = GetDlgItem(hwnd, CAPTURE_KEY_EDIT) {
+                        let _ = SetFocus(edit);
+                    }
+                    return LRESULT(0);
+                }
+                (*p).accepted = id == 1;
+                (*p).done = true;
+                let _ = DestroyWindow(hwnd);
+                LRESULT(0)
+            }
+            WM_CLOSE => {
+                let _ = SendMessageW(hwnd, WM_COMMAND, WPARAM_T(2), LPARAM_T(0));
+                LRESULT(0)
+            }
+            _ => DefWindowProcW(hwnd, msg, w, l),
+        }
+    }
+}
+
+/// Common Save dialog for a `.json` file; `None` when cancelled.
+pub fn save_json_dialog(parent: HWND, title: &str, default_name: &str) -> Option<PathBuf> {
+    json_file_dialog(parent, title, default_name, true)
+}
+
+/// Common Open dialog for an existing `.json` file; `None` when cancelled.
+pub fn open_json_dialog(parent: HWND, title: &str) -> Option<PathBuf> {
+    json_file_dialog(parent, title, "", false)
+}
+
+fn json_file_dialog(parent: HWND, title: &str, default_name: &str, save: bool) -> Option<PathBuf> {
+    // Filter pairs are NUL-separated and the list ends with a double NUL.
+    let filter: Vec<u16> = "JSON files (*.json)\0*.json\0All files (*.*)\0*.*\0\0"
+        .encode_utf16()
+        .collect();
+    let mut file = [0u16; 1024];
+    let max_name = file.len() - 1;
+    for (dst, src) in file[..max_name].iter_mut().zip(default_name.encode_utf16()) {
+        *dst = src;
+    }
+    let wtitle = to_utf16(title);
+    let mut ofn = OPENFILENAMEW {
+        lStructSize: std::mem::size_of::<OPENFILENAMEW>() as u32,
+        hwndOwner: parent,
+        lpstrFilter: PCWSTR(filter.as_ptr()),
+        lpstrFile: PWSTR(file.as_mut_ptr()),
+        nMaxFile: file.len() as u32,
+        lpstrTitle: PCWSTR(wtitle.as_ptr()),
+        lpstrDefExt: windows::core::w!("json"),
+        Flags: if save {
+            OFN_OVERWRITEPROMPT | OFN_PATHMUSTEXIST | OFN_NOCHANGEDIR
+        } else {
+            OFN_FILEMUSTEXIST | OFN_PATHMUSTEXIST | OFN_NOCHANGEDIR
+        },
+        ..Default::default()
+    };
+    let ok = unsafe {
+        if save {
+            GetSaveFileNameW(&mut ofn)
+        } else {
+            GetOpenFileNameW(&mut ofn)
+        }
+    };
+    if !ok.as_bool() {
+        return None;
+    }
+    let len = file.iter().position(|&c| c == 0).unwrap_or(file.len());
+    Some(PathBuf::from(String::from_utf16_lossy(&file[..len])))
+}
+
+/// Ask whether an import should merge with or replace the current labels.
+/// `None` when the user cancels.
+pub fn ask_import_mode(parent: HWND) -> Option<ImportMode> {
+    let text = to_utf16(
+        "Merge the imported labels with your current ones?\n\n\
+         Yes: merge (imported labels win for the same desktop)\n\
+         No: replace all current labels",
+    );
+    let choice = unsafe {
+        MessageBoxW(
+            parent,
+            PCWSTR(text.as_ptr()),
+            windows::core::w!("Import Config"),
+            MB_YESNOCANCEL | MB_ICONQUESTION | MB_TOPMOST,
+        )
+    };
+    match choice {
+        IDYES => Some(ImportMode::Merge),
+        IDNO => Some(ImportMode::Replace),
+        _ => None,
+    }
+}
+
+/// Confirm resetting the whole config to defaults. "No" is the default button.
+pub fn confirm_reset(parent: HWND) -> bool {
+    let text = to_utf16(
+        "Reset all labels, hotkeys and appearance settings to their defaults?\n\n\
+         The current labels.json is backed up next to it first.",
+    );
+    let choice = unsafe {
+        MessageBoxW(
+            parent,
+            PCWSTR(text.as_ptr()),
+            windows::core::w!("Reset Config"),
+            MB_YESNO | MB_ICONWARNING | MB_DEFBUTTON2 | MB_TOPMOST,
+        )
+    };
+    choice == IDYES
+}
+
+/// Modal error box for failures the app can't recover from.
+pub fn show_error(parent: HWND, title: &str, text: &str) {
+    let title = to_utf16(title);
+    let text = to_utf16(text);
+    unsafe {
+        let _ = MessageBoxW(
+            parent,
+            PCWSTR(text.as_ptr()),
+            PCWSTR(title.as_ptr()),
+            MB_OK | MB_ICONERROR | MB_TOPMOST,
+        );
+    }
+}
+
+/// Confirm quitting the app. "No" is the default button.
+pub fn confirm_exit(parent: HWND) -> bool {
+    let choice = unsafe {
+        MessageBoxW(
+            parent,
+            windows::core::w!(
+                "Exit Desktop Labeler? The overlay will stop until it is started again."
+            ),
+            windows::core::w!("Exit"),
+            MB_YESNO | MB_ICONQUESTION | MB_DEFBUTTON2 | MB_TOPMOST,
+        )
+    };
+    choice == IDYES
+}
+
+/// Resolve the dialog's top-left from the cursor, the parent (overlay) rect and the
+/// work area of the monitor the dialog will land on.
+fn dialog_position(parent: HWND, placement: DialogPlacement, w: i32, h: i32) -> (i32, i32) {
+    unsafe {
+        let mut cursor = POINT::default();
+        let _ = GetCursorPos(&mut cursor);
+        let mut anchor = RECT::default();
+        let has_anchor = !parent.0.is_null() && GetWindowRect(parent, &mut anchor).is_ok();
+        // Pick the monitor the dialog belongs to: the cursor's for NearCursor, else the one
+        // the overlay window is on (by its largest overlap, so a label docked against a screen
+        // edge still counts as being on that screen).
+        let mon = if placement == DialogPlacement::NearCursor || !has_anchor {
+            MonitorFromPoint(cursor, MONITOR_DEFAULTTONEAREST)
+        } else {
+            MonitorFromWindow(parent, MONITOR_DEFAULTTONEAREST)
+        };
+        let mut mi = MONITORINFO {
+            cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+            ..Default::default()
+        };
+        let work = if GetMonitorInfoW(mon, &mut mi).as_bool() {
+            mi.rcWork
+        } else {
+            RECT {
+                left: 0,
+                top: 0,
+                right: GetSystemMetrics(SM_CXSCREEN),
+                bottom: GetSystemMetrics(SM_CYSCREEN),
+            }
+        };
+        if !has_anchor {
+            // No overlay rect: NearOverlay falls back to the work area as well.
+            anchor = work;
+        }
+        crate::core::place_dialog(
+            placement,
+            (cursor.x, cursor.y),
+            (anchor.left, anchor.top, anchor.right, anchor.bottom),
+            (work.left, work.top, work.right, work.bottom),
+            w,
+            h,
+        )
     }
 }
@@ -0,0 +1,86 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Sidecar file in `cfg_dir`.
+pub const USAGE_FILE_NAME: &str = "usage.json";
+
+/// How long switches may sit in memory before they are written out.
+pub const FLUSH_INTERVAL_MS: u32 = 30_000;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DesktopUsage {
+    /// Switches into this desktop.
+    pub switches: u64,
+    /// Seconds since the Unix epoch of the latest switch into it.
+    pub last_visited: u64,
+}
+
+/// Opt-in (`track_usage`) switch counts per desktop, keyed like `cfg.desktops` (GUID, or
+/// index with `key_by_index`). Purely local: kept in `usage.json` and never sent anywhere.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Usage {
+    #[serde(default)]
+    pub desktops: BTreeMap<String, DesktopUsage>,
+    /// Recorded since the last save.
+    #[serde(skip)]
+    dirty: bool,
+}
+
+/// `usage.json` in `cfg_dir`.
+pub fn usage_path(cfg_dir: &Path) -> PathBuf {
+    cfg_dir.join(USAGE_FILE_NAME)
+}
+
+/// Seconds since the Unix epoch.
+pub fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+impl Usage {
+    /// Stats from `path`; empty if the file is missing or unreadable.
+    pub fn load(path: &Path) -> Usage {
+        match fs::read_to_string(path) {
+            Ok(s) => serde_json::from_str(&s).unwrap_or_else(|e| {
+                tracing::warn!(error = %e, path = %path.display(), "ignoring unreadable usage stats");
+                Usage::default()
+            }),
+            Err(_) => Usage::default(),
+        }
+    }
+
+    /// Count a switch into `key` at `now` (Unix seconds).
+    pub fn record_switch(&mut self, key: &str, now: u64) {
+        let entry = self.desktops.entry(key.to_string()).or_default();
+        entry.switches += 1;
+        entry.last_visited = now;
+        self.dirty = true;
+    }
+
+    /// Whether there are switches not yet saved.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Switches into `key` so far.
+    pub fn visits(&self, key: &str) -> u64 {
+        self.desktops.get(key).map_or(0, |u| u.switches)
+    }
+
+    /// Write to `path` the way the config is saved (see [`crate::config::write_atomic`]) and
+    /// clear the dirty flag.
+    pub fn save(&mut self, path: &Path) -> Result<()> {
+        crate::config::write_atomic(path, &serde_json::to_vec_pretty(self)?)
+            .context("save usage")?;
+        self.dirty = false;
+        Ok(())
+    }
+}
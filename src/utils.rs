@@ -2,3 +2,54 @@
 pub fn to_utf16(s: &str) -> Vec<u16> {
     s.encode_utf16().chain(std::iter::once(0)).collect()
 }
+
+/// Replace the clipboard contents with `text` as `CF_UNICODETEXT`, owned by `hwnd`.
+#[cfg(windows)]
+pub fn set_clipboard_text(
+    hwnd: windows::Win32::Foundation::HWND,
+    text: &str,
+) -> anyhow::Result<()> {
+    use windows::Win32::Foundation::{GlobalFree, HANDLE};
+    use windows::Win32::System::DataExchange::{
+        CloseClipboard, EmptyClipboard, OpenClipboard, SetClipboardData,
+    };
+    use windows::Win32::System::Memory::{GMEM_MOVEABLE, GlobalAlloc, GlobalLock, GlobalUnlock};
+    const CF_UNICODETEXT: u32 = 13;
+    let wide = to_utf16(text);
+    unsafe {
+        OpenClipboard(hwnd)?;
+        let result = (|| -> anyhow::Result<()> {
+            EmptyClipboard()?;
+            let mem = GlobalAlloc(GMEM_MOVEABLE, wide.len() * std::mem::size_of::<u16>())?;
+            let dst = GlobalLock(mem) as *mut u16;
+            if dst.is_null() {
+                let _ = GlobalFree(mem);
+                anyhow::bail!("GlobalLock failed");
+            }
+            std::ptr::copy_nonoverlapping(wide.as_ptr(), dst, wide.len());
+            let _ = GlobalUnlock(mem);
+            // On success the clipboard owns the memory; otherwise it's still ours to free.
+            if let Err(e) = SetClipboardData(CF_UNICODETEXT, HANDLE(mem.0)) {
+                let _ = GlobalFree(mem);
+                return Err(e.into());
+            }
+            Ok(())
+        })();
+        let _ = CloseClipboard();
+        result
+    }
+}
+
+/// Block until `quiet` passes with no new message on `rx`, swallowing the burst.
+///
+/// Returns false if the sender hung up.
+pub fn drain_burst<T>(rx: &std::sync::mpsc::Receiver<T>, quiet: std::time::Duration) -> bool {
+    use std::sync::mpsc::RecvTimeoutError;
+    loop {
+        match rx.recv_timeout(quiet) {
+            Ok(_) => continue,
+            Err(RecvTimeoutError::Timeout) => return true,
+            Err(RecvTimeoutError::Disconnected) => return false,
+        }
+    }
+}
@@ -1,24 +1,160 @@
 use anyhow::Result;
 use core::ffi::c_void;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use windows::Win32::Foundation::{HWND, LPARAM, WPARAM};
+use windows::Win32::Graphics::Gdi::HMONITOR;
 use windows::Win32::UI::WindowsAndMessaging::PostMessageW;
 use winvd::{DesktopEvent, DesktopEventThread, listen_desktop_events};
 
+use crate::vd_backend::VdBackend;
+
+/// Production `VdBackend` backed by winvd.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WinVd;
+
+impl VdBackend for WinVd {
+    fn current_guid(&self) -> String {
+        get_current_desktop_guid()
+    }
+
+    fn name(&self) -> Option<String> {
+        get_current_desktop_name()
+    }
+
+    fn index_and_count(&self) -> Option<(usize, usize)> {
+        let index = current_desktop_index()?;
+        let count = winvd::get_desktop_count().ok()? as usize;
+        Some((index, count))
+    }
+}
+
+/// Synthetic id used for the single pseudo-desktop when virtual desktops are unavailable.
+pub const FALLBACK_GUID: &str = "default";
+
+/// Id of the current desktop. Fails when the OS lacks the virtual desktop COM interfaces
+/// winvd needs (some Windows 10 builds).
+pub fn try_current_desktop_guid() -> Result<String> {
+    winvd::get_current_desktop()
+        .map(|d| format!("{:?}", d))
+        .map_err(|e| anyhow::anyhow!("virtual desktops unavailable: {:?}", e))
+}
+
+/// Id of the desktop currently shown on monitor `hmon`, where desktops are tracked per
+/// monitor. `None` otherwise; callers then use the global [`get_current_desktop_guid`].
+///
+/// winvd (and the shell interfaces it wraps up to Windows 11 24H2) only knows one current
+/// desktop shared by all monitors, so this always returns `None` for now. Mirrors already
+/// ask for their own monitor's desktop and pick up a per-monitor query once one exists.
+pub fn current_desktop_guid_for_monitor(_hmon: HMONITOR) -> Option<String> {
+    None
+}
+
+/// Id of the current desktop, or [`FALLBACK_GUID`] when virtual desktops are unavailable.
 pub fn get_current_desktop_guid() -> String {
-    #[cfg(windows)]
-    {
-        if let Ok(d) = winvd::get_current_desktop() {
-            return format!("{:?}", d);
+    try_current_desktop_guid().unwrap_or_else(|_| FALLBACK_GUID.to_string())
+}
+
+/// Ids of all current desktops, in the same format as `get_current_desktop_guid`.
+/// Empty when winvd is unavailable.
+pub fn list_desktop_guids() -> Vec<String> {
+    winvd::get_desktops()
+        .map(|desktops| {
+            desktops
+                .iter()
+                .filter_map(|d| d.get_id().ok())
+                // Re-wrap the bare GUID so the Debug form matches get_current_desktop's.
+                .map(|id| format!("{:?}", winvd::Desktop::from(id)))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Name given to the current desktop in Task View; `None` when unnamed or unsupported (Windows 10).
+pub fn get_current_desktop_name() -> Option<String> {
+    winvd::get_current_desktop()
+        .and_then(|d| d.get_name())
+        .ok()
+        .filter(|n| !n.trim().is_empty())
+}
+
+/// Rename the current desktop in Task View. Returns `Ok(false)` (after a warning) when the OS
+/// has no desktop names, e.g. Windows 10.
+pub fn set_current_desktop_name(name: &str) -> Result<bool> {
+    use windows::Win32::Foundation::{E_NOINTERFACE, E_NOTIMPL};
+    match winvd::get_current_desktop().and_then(|d| d.set_name(name)) {
+        Ok(()) => Ok(true),
+        Err(winvd::Error::ClassNotRegistered) => {
+            tracing::warn!("desktop names unsupported on this system; not renaming");
+            Ok(false)
+        }
+        Err(winvd::Error::ComError(hr)) if hr == E_NOINTERFACE || hr == E_NOTIMPL => {
+            tracing::warn!(
+                ?hr,
+                "desktop names unsupported on this system; not renaming"
+            );
+            Ok(false)
         }
+        Err(e) => Err(anyhow::anyhow!("set desktop name: {:?}", e)),
     }
-    // Fallback if API unavailable
-    "default".to_string()
 }
 
-pub fn start_vd_poller(hwnd: HWND, msg: u32) {
+/// 0-based index of the current virtual desktop, if the OS reports it.
+pub fn current_desktop_index() -> Option<usize> {
+    winvd::get_current_desktop()
+        .and_then(|d| d.get_index())
+        .ok()
+        .map(|i| i as usize)
+}
+
+/// Create a virtual desktop (at the end of the list) and return its id.
+pub fn create_desktop() -> Result<String> {
+    let id = winvd::create_desktop()
+        .and_then(|d| d.get_id())
+        .map_err(|e| anyhow::anyhow!("create desktop: {:?}", e))?;
+    Ok(format!("{:?}", winvd::Desktop::from(id)))
+}
+
+/// Remove the current virtual desktop, moving its windows to a neighbor (see
+/// [`crate::core::removal_fallback`]). Returns the removed desktop's id, or `None` without
+/// removing anything when it is the only desktop.
+pub fn remove_current_desktop() -> Result<Option<String>> {
+    let desktops = winvd::get_desktops().map_err(|e| anyhow::anyhow!("list desktops: {:?}", e))?;
+    let index = current_desktop_index().ok_or_else(|| anyhow::anyhow!("no current desktop"))?;
+    let Some(fallback) = crate::core::removal_fallback(index, desktops.len()) else {
+        return Ok(None);
+    };
+    let current = desktops[index];
+    let id = current
+        .get_id()
+        .map_err(|e| anyhow::anyhow!("desktop id: {:?}", e))?;
+    winvd::remove_desktop(current, desktops[fallback])
+        .map_err(|e| anyhow::anyhow!("remove desktop: {:?}", e))?;
+    Ok(Some(format!("{:?}", winvd::Desktop::from(id))))
+}
+
+/// Pin `hwnd` so it shows on every virtual desktop.
+pub fn pin_window(hwnd: HWND) -> bool {
+    winvd::pin_window(hwnd).is_ok()
+}
+
+/// Undo `pin_window`; the window then lives on a single desktop.
+pub fn unpin_window(hwnd: HWND) -> bool {
+    winvd::unpin_window(hwnd).is_ok()
+}
+
+/// Move `hwnd` onto the current virtual desktop.
+pub fn move_to_current_desktop(hwnd: HWND) -> bool {
+    winvd::get_current_desktop()
+        .and_then(|d| d.get_id())
+        .and_then(|id| winvd::move_window_to_desktop(id, &hwnd))
+        .is_ok()
+}
+
+pub fn start_vd_poller(hwnd: HWND, msg: u32, interval_ms: u32) {
     let hwnd_raw = hwnd.0 as usize; // make Send
     thread::spawn(move || {
         let mut last = super::vd::get_current_desktop_guid();
@@ -31,18 +167,41 @@ pub fn start_vd_poller(hwnd: HWND, msg: u32) {
                 }
                 last = now;
             }
-            thread::sleep(Duration::from_millis(250));
+            thread::sleep(Duration::from_millis(interval_ms as u64));
         }
     });
 }
 
-pub fn start_vd_events(hwnd: HWND, msg: u32) -> Option<DesktopEventThread> {
+/// A running desktop-event subscription from [`start_vd_events`]. Dropping it stops the
+/// listener.
+pub struct VdEvents {
+    _thread: DesktopEventThread,
+    alive: Arc<AtomicBool>,
+    started: Instant,
+}
+
+impl VdEvents {
+    /// False once winvd's listener thread has died (its event channel closed); switches are
+    /// then no longer reported.
+    pub fn is_alive(&self) -> bool {
+        self.alive.load(Ordering::Relaxed)
+    }
+
+    /// Time since the subscription started.
+    pub fn uptime(&self) -> Duration {
+        self.started.elapsed()
+    }
+}
+
+/// Post `msg` to `hwnd` on every desktop switch. Fails when winvd can't subscribe to
+/// desktop events; callers then fall back to `start_vd_poller`.
+pub fn start_vd_events(hwnd: HWND, msg: u32) -> Result<VdEvents> {
     let (tx, rx) = mpsc::channel::<DesktopEvent>();
-    let thread = match listen_desktop_events::<DesktopEvent, _>(tx) {
-        Ok(t) => t,
-        Err(_) => return None,
-    };
+    let thread = listen_desktop_events::<DesktopEvent, _>(tx)
+        .map_err(|e| anyhow::anyhow!("listen for desktop events: {:?}", e))?;
     let hwnd_raw = hwnd.0 as usize;
+    let alive = Arc::new(AtomicBool::new(true));
+    let flag = alive.clone();
     thread::spawn(move || {
         for evt in rx {
             match evt {
@@ -52,6 +211,12 @@ pub fn start_vd_events(hwnd: HWND, msg: u32) -> Option<DesktopEventThread> {
                 _ => {}
             }
         }
+        // The sender lives in winvd's listener thread; the channel only closes when it exits.
+        flag.store(false, Ordering::Relaxed);
     });
-    Some(thread)
+    Ok(VdEvents {
+        _thread: thread,
+        alive,
+        started: Instant::now(),
+    })
 }
@@ -0,0 +1,46 @@
+/// Read-only view of the virtual desktop state the overlay logic depends on.
+///
+/// The production implementation (`vd::WinVd`) wraps winvd; `MockVd` lets the
+/// switch/formatting logic run in tests without Windows or real desktops.
+pub trait VdBackend {
+    /// Identifier of the current desktop (`"default"` when unavailable).
+    fn current_guid(&self) -> String;
+    /// Name the user gave the current desktop in Task View, if any.
+    fn name(&self) -> Option<String>;
+    /// 0-based index of the current desktop and the total desktop count.
+    fn index_and_count(&self) -> Option<(usize, usize)>;
+}
+
+/// In-memory test double; mutate the fields to simulate desktop switches.
+#[derive(Debug, Clone)]
+pub struct MockVd {
+    pub guid: String,
+    pub name: Option<String>,
+    pub index: Option<usize>,
+    pub count: usize,
+}
+
+impl Default for MockVd {
+    fn default() -> Self {
+        Self {
+            guid: "default".into(),
+            name: None,
+            index: Some(0),
+            count: 1,
+        }
+    }
+}
+
+impl VdBackend for MockVd {
+    fn current_guid(&self) -> String {
+        self.guid.clone()
+    }
+
+    fn name(&self) -> Option<String> {
+        self.name.clone()
+    }
+
+    fn index_and_count(&self) -> Option<(usize, usize)> {
+        self.index.map(|i| (i, self.count))
+    }
+}
@@ -1,27 +1,40 @@
 // Windows-only module compiled via cfg in the binary's main.rs
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use windows::Win32::Foundation::HANDLE;
 use windows::Win32::Foundation::{HWND, LPARAM, LRESULT, RECT, WPARAM};
 use windows::Win32::Graphics::Gdi::{
-    GetMonitorInfoW, MONITOR_DEFAULTTONEAREST, MONITORINFO, MonitorFromWindow,
+    GetMonitorInfoW, HMONITOR, MONITOR_DEFAULTTONEAREST, MONITORINFO, MonitorFromWindow,
 };
 use windows::Win32::System::Com::{COINIT_APARTMENTTHREADED, CoInitializeEx, CoUninitialize};
+use windows::Win32::System::Console::{ATTACH_PARENT_PROCESS, AttachConsole};
+use windows::Win32::System::DataExchange::COPYDATASTRUCT;
 use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+use windows::Win32::System::Power::{
+    HPOWERNOTIFY, POWERBROADCAST_SETTING, RegisterPowerSettingNotification,
+    UnregisterPowerSettingNotification,
+};
 use windows::Win32::System::RemoteDesktop::{
     NOTIFY_FOR_THIS_SESSION, WTSRegisterSessionNotification, WTSUnRegisterSessionNotification,
 };
 use windows::Win32::UI::WindowsAndMessaging::*;
 
 use mddskmgr::autorun;
+use mddskmgr::cli;
 use mddskmgr::config::{self, Config, Paths};
+use mddskmgr::core::{HideReason, VisibilityState};
 use mddskmgr::hotkeys::{self, HK_EDIT_DESC, HK_EDIT_TITLE, HK_TOGGLE};
+use mddskmgr::ipc::{self, LabelField};
 use mddskmgr::overlay::Overlay;
 use mddskmgr::tray;
 use mddskmgr::tray::{
     CMD_EDIT_DESC, CMD_EDIT_TITLE, CMD_EXIT, CMD_OPEN_CONFIG, CMD_TOGGLE, TRAY_MSG, Tray,
 };
 use mddskmgr::ui;
+use mddskmgr::usage::{self, Usage};
 use mddskmgr::vd;
 use notify::{RecommendedWatcher, RecursiveMode, Watcher};
 use std::sync::mpsc as std_mpsc;
@@ -30,6 +43,17 @@ use windows::core::PCWSTR;
 
 const WM_VD_SWITCHED: u32 = WM_APP + 2;
 const WM_CFG_CHANGED: u32 = WM_APP + 3;
+// Posted by `mddsklbl rehook` to the running instance's overlay window.
+const WM_REHOOK: u32 = WM_APP + 4;
+// Posted by a second launch so the running instance shows its overlay.
+const WM_SHOW_OVERLAY: u32 = WM_APP + 5;
+const OVERLAY_WND_CLASS: PCWSTR = windows::core::w!("DesktopOverlayWndClass");
+/// Class of the copies shown on other monitors with `appearance.mirror_all_monitors`.
+const MIRROR_WND_CLASS: PCWSTR = windows::core::w!("DesktopOverlayMirrorWndClass");
+
+// GUID_CONSOLE_DISPLAY_STATE (Win32_System_SystemServices): 0 = off, 1 = on, 2 = dimmed.
+const GUID_CONSOLE_DISPLAY_STATE: windows::core::GUID =
+    windows::core::GUID::from_u128(0x6fe69556_704a_47a0_8f24_c28d936fda47);
 
 thread_local! {
     static APP: RefCell<Option<AppState>> = const { RefCell::new(None) };
@@ -40,48 +64,621 @@ struct AppState {
     cfg: Config,
     cfg_paths: Paths,
     overlay: Overlay,
+    mirrors: Vec<Overlay>, // copies on the other monitors (`mirror_all_monitors`)
+    mirror_handles: Vec<HMONITOR>, // each mirror's monitor, cached by `sync_mirrors`
     current_guid: String,
     visible: bool,
     tray: Tray,
     taskbar_created_msg: u32,
-    vd_thread: Option<winvd::DesktopEventThread>,
+    vd_thread: Option<vd::VdEvents>,
+    vd_supported: bool, // false: a single synthetic "default" desktop stands in
     hide_for_accessibility: bool,
     hide_for_fullscreen: bool,
-    anchor_index: u8, // 0=1/4,1=1/2,2=3/4
+    hide_for_app: bool, // a `hide_for_processes` app is in the foreground
+    power_notify: Option<HPOWERNOTIFY>,
+    display_off: bool,
+    remote_disconnected: bool, // RDP session disconnected; hidden until reconnect
+    panic_hidden: bool,        // panic-hide hotkey engaged; never persisted
+    shown_by_switch: bool,     // auto-hide: inside the window after a switch
+    manual_override: bool,     // auto-hide: toggled by hand since the last switch
+    fade_anim: Option<FadeAnim>, // in-flight fade, stepped by timer 5
+    usage: Usage,              // `track_usage` stats; flushed by timer 7 and on exit
+    contrast: HashMap<String, f32>, // auto-contrast: sampled luminance per desktop GUID
+    switch_pending: bool,      // a switch event's redraw is waiting on timer 9
+    peek_until: Option<std::time::Instant>, // peek hotkey: shown regardless until then (timer 10)
+    vd_restarts: u32,          // event-thread restart attempts since it last ran stably
+    vd_retry_at: Option<std::time::Instant>, // next restart attempt; checked by timer 2
+    peer_slot: u32,            // overlays sharing this one's placement at startup
+}
+
+/// A fade of the overlay from one level to another (see `start_fade`).
+struct FadeAnim {
+    from: u8,
+    to: u8,
+    start: std::time::Instant,
+    duration_ms: u32,
+}
+
+impl AppState {
+    /// Key of the current desktop's entry in `cfg.desktops`.
+    fn label_key(&self) -> String {
+        label_key(&self.cfg, &self.current_guid)
+    }
+
+    /// Count a switch into the current desktop (`track_usage`). The first unsaved switch arms
+    /// timer 7, so stats reach the disk at most every `FLUSH_INTERVAL_MS`.
+    fn record_usage(&mut self) {
+        if !self.cfg.track_usage {
+            return;
+        }
+        let first = !self.usage.is_dirty();
+        self.usage
+            .record_switch(&self.label_key(), usage::now_secs());
+        if first {
+            unsafe {
+                SetTimer(self.hwnd, 7, usage::FLUSH_INTERVAL_MS, None);
+            }
+        }
+    }
+
+    /// With `auto_contrast`, arm timer 8 to sample the screen once the current desktop has
+    /// settled, unless it already has a sample.
+    fn want_contrast_sample(&self) {
+        if self.cfg.appearance.auto_contrast && !self.contrast.contains_key(&self.current_guid) {
+            unsafe {
+                SetTimer(self.hwnd, 8, CONTRAST_SAMPLE_DELAY_MS, None);
+            }
+        }
+    }
+
+    /// Write usage stats recorded since the last flush.
+    fn flush_usage(&mut self) {
+        if !self.usage.is_dirty() {
+            return;
+        }
+        let path = usage::usage_path(&self.cfg_paths.cfg_dir);
+        if let Err(e) = self.usage.save(&path) {
+            tracing::warn!(error = ?e, path = %path.display(), "failed to save usage stats");
+        }
+    }
+
+    /// Hold a display on/off notification registration exactly while `suspend_on_display_off`
+    /// is set. Returns true when turning the setting off ended a suspension, so the caller
+    /// restarts the timers.
+    fn sync_power_notify(&mut self) -> bool {
+        if self.cfg.suspend_on_display_off {
+            if self.power_notify.is_none() {
+                self.power_notify = unsafe {
+                    RegisterPowerSettingNotification(
+                        HANDLE(self.hwnd.0),
+                        &GUID_CONSOLE_DISPLAY_STATE,
+                        DEVICE_NOTIFY_WINDOW_HANDLE,
+                    )
+                }
+                .ok();
+            }
+            return false;
+        }
+        if let Some(h) = self.power_notify.take() {
+            unsafe {
+                let _ = UnregisterPowerSettingNotification(h);
+            }
+        }
+        std::mem::take(&mut self.display_off)
+    }
+
+    /// A restart of the dead desktop-event thread is scheduled and due at `now`.
+    fn retry_at_due(&self, now: std::time::Instant) -> bool {
+        self.vd_thread.is_none() && self.vd_retry_at.is_some_and(|t| now >= t)
+    }
+
+    /// Desktop switches must be polled for: no event thread, but desktops do exist.
+    fn needs_vd_poll(&self) -> bool {
+        self.vd_supported && self.vd_thread.is_none()
+    }
+}
+
+/// Key of `guid`'s entry in `cfg.desktops`: the GUID itself, or its index with `key_by_index`.
+fn label_key(cfg: &Config, guid: &str) -> String {
+    if !cfg.appearance.key_by_index {
+        return guid.to_string();
+    }
+    config::label_key(cfg, guid, &vd::list_desktop_guids())
+}
+
+/// Move labels to the keys `key_by_index` calls for after it was switched, saving if any moved.
+fn rekey_labels(cfg: &mut Config, paths: &Paths) {
+    if cfg.read_only || !config::rekey_desktops(cfg, &vd::list_desktop_guids()) {
+        return;
+    }
+    tracing::info!(
+        by_index = cfg.appearance.key_by_index,
+        "rekeyed desktop labels"
+    );
+    if let Err(e) = config::save_atomic(cfg, paths) {
+        tracing::warn!(error=?e, "rekey: save failed");
+    }
+}
+
+/// Draw `guid`'s label on the overlay and on any mirrors, then update the tray to match.
+/// A mirror shows its own monitor's desktop when the OS reports one (see
+/// [`vd::current_desktop_guid_for_monitor`]), else `guid`.
+fn draw_overlay_line(overlay: &Overlay, cfg: &Config, guid: &str) {
+    let (hwnd, mirrors, handles, contrast) = APP.with(|slot| {
+        slot.borrow()
+            .as_ref()
+            .map(|app| {
+                (
+                    Some(app.hwnd),
+                    app.mirrors.clone(),
+                    app.mirror_handles.clone(),
+                    app.contrast.clone(),
+                )
+            })
+            .unwrap_or_default()
+    });
+    for (ov, &handle) in mirrors.iter().zip(&handles) {
+        let own = vd::current_desktop_guid_for_monitor(handle).unwrap_or_else(|| guid.to_string());
+        let key = label_key(cfg, &own);
+        let lines = mddskmgr::core::overlay_lines(cfg, &key, &vd::WinVd);
+        let lines: Vec<&str> = lines.iter().map(String::as_str).collect();
+        draw_label(ov, cfg, &key, &lines, contrast.get(&own).copied());
+    }
+    let luminance = contrast.get(guid).copied();
+    let key = label_key(cfg, guid);
+    let guid = key.as_str();
+    let lines = mddskmgr::core::overlay_lines(cfg, guid, &vd::WinVd);
+    tracing::debug!(key=%guid, ?lines, "update_overlay_text");
+    let lines: Vec<&str> = lines.iter().map(String::as_str).collect();
+    draw_label(overlay, cfg, guid, &lines, luminance);
+    if let Some(hwnd) = hwnd {
+        update_tray_badge(hwnd, cfg, guid);
+        // The tooltip shows where we are even while the overlay is hidden.
+        if let Err(e) = Tray::set_tooltip_for(hwnd, &lines.join("\n")) {
+            tracing::warn!(error=?e, "update tray tooltip failed");
+        }
+    }
+}
+
+/// Draw `guid`'s label (`lines`, from `overlay_lines`) on one overlay at the desktop's font
+/// size (its `font_size_dip` override, else the global one). The overlay caches its text
+/// format by size, so a desktop switch rebuilds it only when the size actually changes.
+/// `luminance` is the auto-contrast sample for the desktop, if there is one.
+fn draw_label(overlay: &Overlay, cfg: &Config, guid: &str, lines: &[&str], luminance: Option<f32>) {
+    let background = mddskmgr::core::desktop_background(cfg, guid);
+    let (text, background) = match luminance.filter(|_| cfg.appearance.auto_contrast) {
+        Some(lum) => {
+            let configured = background.unwrap_or(overlay.colors().1);
+            let (text, bg) = mddskmgr::core::auto_contrast_colors(
+                lum,
+                cfg.appearance.min_contrast_ratio,
+                configured,
+            );
+            (Some(text), Some(bg))
+        }
+        None => (None, background),
+    };
+    let overlay = &overlay
+        .with_font_size(mddskmgr::core::desktop_font_size(cfg, guid))
+        .with_text_color(text);
+    let margin = cfg.appearance.margin_px;
+    let hints = if cfg.appearance.show_hints {
+        hotkeys::hint_text(&cfg.hotkeys)
+    } else {
+        String::new()
+    };
+    if cfg.appearance.stacked_description {
+        let (title, desc) = mddskmgr::core::decorated_label(cfg, guid, &vd::WinVd);
+        let desc: Vec<&str> = desc.iter().map(String::as_str).collect();
+        let scale = mddskmgr::core::description_scale(cfg.appearance.description_scale);
+        let _ =
+            overlay.draw_title_and_description(&title, &desc, &hints, margin, background, scale);
+    } else {
+        let _ = overlay.draw_lines_anchored_colored(lines, &hints, margin, background);
+    }
 }
 
-fn compute_line(cfg: &Config, guid: &str) -> (String, i32) {
-    let label = cfg.desktops.get(guid).cloned().unwrap_or_default();
-    let title = if label.title.trim().is_empty() {
-        "Desktop".to_string()
+/// Match the mirror overlays to the attached monitors: one on every monitor but the main
+/// overlay's while `appearance.mirror_all_monitors` is on, none otherwise. Existing windows
+/// are reused; each mirror takes the main overlay's current settings, so call this after
+/// they change and before redrawing.
+fn sync_mirrors() {
+    let snap = APP.with(|slot| {
+        slot.borrow().as_ref().map(|app| {
+            (
+                app.cfg.appearance.mirror_all_monitors,
+                app.overlay.clone(),
+                app.mirrors.iter().map(Overlay::hwnd).collect::<Vec<_>>(),
+                overlay_should_show(app),
+            )
+        })
+    });
+    let Some((enabled, main, mut windows, show)) = snap else {
+        return;
+    };
+    let (targets, handles) = if enabled {
+        let monitors = mddskmgr::monitors::list();
+        let primary = monitors.iter().position(|m| m.primary);
+        let targets =
+            mddskmgr::core::mirror_monitors(monitors.len(), main.monitor_index(), primary);
+        // Cached here so draws don't enumerate monitors to find each mirror's desktop.
+        let handles = targets.iter().map(|&i| monitors[i].handle).collect();
+        (targets, handles)
     } else {
-        label.title
+        (Vec::new(), Vec::new())
     };
-    let desc = label.description;
-    let line = format!("{} : {}", title, desc);
-    (line, cfg.appearance.margin_px)
+    while windows.len() > targets.len() {
+        if let Some(h) = windows.pop() {
+            unsafe {
+                let _ = DestroyWindow(h);
+            }
+        }
+    }
+    while windows.len() < targets.len() {
+        let Some(h) = create_mirror_window() else {
+            break;
+        };
+        // Mirrors follow the label, not a desktop: always pinned.
+        apply_desktop_placement(h, false);
+        unsafe {
+            let _ = ShowWindow(h, if show { SW_SHOWNOACTIVATE } else { SW_HIDE });
+        }
+        windows.push(h);
+    }
+    let mirrors: Vec<Overlay> = windows
+        .iter()
+        .zip(&targets)
+        .map(|(&h, &monitor)| main.mirrored(h, monitor))
+        .collect();
+    tracing::debug!(count = mirrors.len(), "mirror overlays synced");
+    APP.with(|slot| {
+        if let Some(app) = &mut *slot.borrow_mut() {
+            app.mirrors = mirrors;
+            app.mirror_handles = handles;
+        }
+    });
 }
 
-fn anchor_ratio_from_index(idx: u8) -> f32 {
-    match idx % 3 {
-        0 => 0.25,
-        1 => 0.5,
-        _ => 0.75,
+unsafe extern "system" fn mirror_wndproc(hwnd: HWND, msg: u32, w: WPARAM, l: LPARAM) -> LRESULT {
+    unsafe { DefWindowProcW(hwnd, msg, w, l) }
+}
+
+/// A click-through layered window for one mirror overlay; `None` (logged) on failure.
+fn create_mirror_window() -> Option<HWND> {
+    static REGISTER: std::sync::Once = std::sync::Once::new();
+    unsafe {
+        let hinst = GetModuleHandleW(None).ok()?;
+        REGISTER.call_once(|| {
+            let wc = WNDCLASSW {
+                lpfnWndProc: Some(mirror_wndproc),
+                hInstance: hinst.into(),
+                lpszClassName: MIRROR_WND_CLASS,
+                ..Default::default()
+            };
+            RegisterClassW(&wc);
+        });
+        CreateWindowExW(
+            WINDOW_EX_STYLE(
+                (WS_EX_TOOLWINDOW
+                    | WS_EX_LAYERED
+                    | WS_EX_TOPMOST
+                    | WS_EX_NOACTIVATE
+                    | WS_EX_TRANSPARENT)
+                    .0,
+            ),
+            MIRROR_WND_CLASS,
+            windows::core::w!(""),
+            WS_POPUP,
+            0,
+            0,
+            400,
+            40,
+            None,
+            None,
+            hinst,
+            None,
+        )
+        .map_err(|e| tracing::warn!(error=?e, "could not create a mirror overlay window"))
+        .ok()
     }
 }
 
-fn draw_overlay_line(overlay: &Overlay, cfg: &Config, guid: &str) {
-    let (line, margin) = compute_line(cfg, guid);
-    let hints = "(Ctrl+Alt+T,D,O,L)";
-    tracing::debug!(guid=%guid, line=%line, "update_overlay_text");
-    let ratio = APP.with(|slot| {
-        if let Some(app) = &*slot.borrow() {
-            anchor_ratio_from_index(app.anchor_index)
-        } else {
-            0.5
+fn update_tray_badge(hwnd: HWND, cfg: &Config, guid: &str) {
+    let badge = mddskmgr::core::tray_badge_for(cfg, guid, &vd::WinVd);
+    if let Err(e) = Tray::set_badge_for(hwnd, badge.as_deref()) {
+        tracing::warn!(error=?e, "update_tray_badge failed");
+    }
+}
+
+/// Forget labels of desktops that no longer exist, saving only if something was removed.
+fn prune_deleted_desktops() {
+    let live = vd::list_desktop_guids();
+    APP.with(|slot| {
+        if let Some(app) = &mut *slot.borrow_mut() {
+            let keys: Vec<String> = live
+                .iter()
+                .map(|g| config::label_key(&app.cfg, g, &live))
+                .collect();
+            if app.cfg.read_only || !config::prune_desktops(&mut app.cfg, &keys) {
+                return;
+            }
+            tracing::info!(
+                remaining = app.cfg.desktops.len(),
+                "pruned labels of deleted desktops"
+            );
+            if let Err(e) = config::save_atomic(&app.cfg, &app.cfg_paths) {
+                tracing::warn!(error=?e, "prune: save failed");
+            }
         }
     });
-    let _ = overlay.draw_line_top_anchor_with_hints(&line, hints, margin, ratio);
+}
+
+/// Run the user's `on_switch_command` (if any) for the desktop just switched to.
+fn run_switch_hook(cfg: &Config, guid: &str) {
+    let Some(cmd) = cfg
+        .on_switch_command
+        .as_deref()
+        .filter(|c| !c.trim().is_empty())
+    else {
+        return;
+    };
+    let title = cfg
+        .desktops
+        .get(&label_key(cfg, guid))
+        .map(|l| l.title.clone())
+        .unwrap_or_default();
+    let env = mddskmgr::hook::switch_env(vd::current_desktop_index(), guid, &title);
+    mddskmgr::hook::spawn_switch_hook(
+        cmd.to_string(),
+        env,
+        std::time::Duration::from_millis(cfg.on_switch_timeout_ms),
+    );
+}
+
+/// Pin the overlay to every desktop, or (per-desktop mode) unpin it and move it to the current one.
+fn apply_desktop_placement(hwnd: HWND, per_desktop_window: bool) {
+    let ok = if per_desktop_window {
+        vd::unpin_window(hwnd);
+        vd::move_to_current_desktop(hwnd)
+    } else {
+        vd::pin_window(hwnd)
+    };
+    if !ok {
+        tracing::debug!(per_desktop_window, "apply_desktop_placement failed");
+    }
+}
+
+/// Setup tip for the first start, naming the configured edit chord.
+fn welcome_text(hk: &mddskmgr::config::Hotkeys) -> String {
+    format!(
+        "Press {} to name this desktop. Right-click the tray icon for more.",
+        hotkeys::format_chord(&hk.edit_title)
+    )
+}
+
+/// Quiet period after the last switch event before the overlay is redrawn (timer 9).
+const SWITCH_SETTLE_MS: u32 = 120;
+
+/// End of a burst of switch events (timer 9): draw the desktop it ended on and, if the
+/// desktop changed during it, run the switch hook and auto-hide window once.
+fn settle_switch(hwnd: HWND) {
+    unsafe {
+        let _ = KillTimer(hwnd, 9);
+    }
+    // Draw outside of the borrow to avoid re-entrancy.
+    let snapshot = APP.with(|slot| {
+        slot.borrow_mut().as_mut().map(|app| {
+            let switched = std::mem::take(&mut app.switch_pending);
+            (
+                app.overlay.clone(),
+                app.cfg.clone(),
+                app.current_guid.clone(),
+                switched,
+            )
+        })
+    });
+    let Some((ov, cfg_clone, gid, switched)) = snapshot else {
+        return;
+    };
+    draw_overlay_line(&ov, &cfg_clone, &gid);
+    if cfg_clone.per_desktop_window {
+        vd::move_to_current_desktop(hwnd);
+    }
+    if switched {
+        run_switch_hook(&cfg_clone, &gid);
+        begin_auto_hide_window(hwnd);
+    }
+    // The new desktop may be flagged `hidden` (or the old one may have been).
+    refresh_visibility_now();
+}
+
+/// Delay after a switch before auto-contrast samples the screen (timer 8), so the desktop
+/// transition has finished.
+const CONTRAST_SAMPLE_DELAY_MS: u32 = 400;
+
+/// Sample the screen around the overlay for auto-contrast (timer 8), remember the result for
+/// the current desktop and redraw with it.
+fn sample_contrast(hwnd: HWND) {
+    unsafe {
+        let _ = KillTimer(hwnd, 8);
+    }
+    let mut rc = RECT::default();
+    let mut mi = MONITORINFO {
+        cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+        ..Default::default()
+    };
+    let ok = unsafe {
+        GetWindowRect(hwnd, &mut rc).is_ok()
+            && GetMonitorInfoW(MonitorFromWindow(hwnd, MONITOR_DEFAULTTONEAREST), &mut mi).as_bool()
+    };
+    if !ok {
+        return;
+    }
+    let m = mi.rcMonitor;
+    let points = mddskmgr::core::contrast_sample_points(
+        (rc.left, rc.top, rc.right, rc.bottom),
+        (m.left, m.top, m.right, m.bottom),
+    );
+    let Some(luminance) =
+        mddskmgr::core::average_luminance(&mddskmgr::overlay::sample_screen(&points))
+    else {
+        tracing::debug!("auto-contrast: no readable screen samples");
+        return;
+    };
+    let mut redraw: Option<(Overlay, Config, String)> = None;
+    APP.with(|slot| {
+        if let Some(app) = &mut *slot.borrow_mut() {
+            tracing::debug!(guid = %app.current_guid, luminance, "auto-contrast sample");
+            app.contrast.insert(app.current_guid.clone(), luminance);
+            redraw = Some((
+                app.overlay.clone(),
+                app.cfg.clone(),
+                app.current_guid.clone(),
+            ));
+        }
+    });
+    if let Some((ov, cfg, gid)) = redraw {
+        draw_overlay_line(&ov, &cfg, &gid);
+    }
+}
+
+/// Balloon text for a config written by a newer build; see [`config::is_from_newer_version`].
+const NEWER_CONFIG_NOTE: &str = "The config was created by a newer version; some settings may be ignored, and changes made here won't be saved.";
+
+/// Hotkey failure balloon text, followed by [`NEWER_CONFIG_NOTE`] when `newer`, so one
+/// notice never hides the other.
+fn hotkey_failure_text(failed: &str, newer: bool) -> String {
+    if newer {
+        format!("{failed}\n{NEWER_CONFIG_NOTE}")
+    } else {
+        failed.to_string()
+    }
+}
+
+/// Delay before retrying registrations that failed during recovery (timer 6).
+const HOTKEY_RETRY_MS: u32 = 1000;
+
+/// Re-register every hotkey after something outside the app (explorer restart, session unlock)
+/// may have dropped them. Failures get one retry after [`HOTKEY_RETRY_MS`].
+fn recover_hotkeys(hwnd: HWND, why: &str) {
+    let hk = APP.with(|slot| slot.borrow().as_ref().map(|app| app.cfg.hotkeys.clone()));
+    let Some(hk) = hk else { return };
+    let results = hotkeys::register_all(hwnd, &hk);
+    if let Some(failed) = hotkeys::failure_summary(&results) {
+        tracing::info!(why, %failed, "hotkey re-registration failed; retrying shortly");
+        unsafe {
+            SetTimer(hwnd, 6, HOTKEY_RETRY_MS, None);
+        }
+    } else {
+        tracing::debug!(why, "hotkeys re-registered");
+    }
+}
+
+/// Second and last attempt after [`recover_hotkeys`] saw a failure; reports what still fails.
+fn retry_hotkeys(hwnd: HWND) {
+    unsafe {
+        let _ = KillTimer(hwnd, 6);
+    }
+    let hk = APP.with(|slot| slot.borrow().as_ref().map(|app| app.cfg.hotkeys.clone()));
+    let Some(hk) = hk else { return };
+    if let Some(failed) = hotkeys::failure_summary(&hotkeys::register_all(hwnd, &hk)) {
+        tracing::warn!(%failed, "hotkeys still failing after retry");
+        let _ = Tray::balloon_for(hwnd, "Hotkeys", &failed);
+    }
+}
+
+/// Re-register hotkeys from the in-memory config (no reload) and report the result in a balloon.
+fn rehook_and_report(hwnd: HWND) {
+    let hk = APP.with(|slot| slot.borrow().as_ref().map(|app| app.cfg.hotkeys.clone()));
+    if let Some(hk) = hk {
+        let results = hotkeys::register_all(hwnd, &hk);
+        tracing::info!(?results, "rehook hotkeys");
+        let summary = match hotkeys::failure_summary(&results) {
+            Some(failed) => failed,
+            None => {
+                let names: Vec<(&str, bool)> = results.iter().map(|r| (r.name, r.ok())).collect();
+                hotkeys::rehook_summary(&names)
+            }
+        };
+        let _ = Tray::balloon_for(hwnd, "Hotkeys", &summary);
+    }
+}
+
+/// Window property holding an overlay's `core::placement_tag`, read by later instances.
+const PLACEMENT_PROP: PCWSTR = windows::core::w!("mddsklbl.placement");
+
+/// Record where this overlay sits so instances started later can stack below it.
+fn publish_placement(hwnd: HWND, cfg: &Config) {
+    let tag = mddskmgr::core::placement_tag(cfg.appearance.anchor, cfg.appearance.monitor_index);
+    unsafe {
+        let _ = SetPropW(hwnd, PLACEMENT_PROP, HANDLE(tag as *mut std::ffi::c_void));
+    }
+}
+
+/// Count overlay windows from other running instances at the same anchor and monitor as `own`.
+fn count_other_overlays(own: HWND) -> u32 {
+    let mut count = 0;
+    let mut prev = HWND(std::ptr::null_mut());
+    unsafe {
+        let tag = GetPropW(own, PLACEMENT_PROP);
+        while let Ok(h) = FindWindowExW(None, prev, OVERLAY_WND_CLASS, None) {
+            if h.0.is_null() {
+                break;
+            }
+            if h != own && GetPropW(h, PLACEMENT_PROP) == tag {
+                count += 1;
+            }
+            prev = h;
+        }
+    }
+    count
+}
+
+/// Vertical offset for this instance's overlay: the configured slot, else `peer_slot`, the
+/// number of overlays sharing its placement when it started.
+fn instance_offset_for(cfg: &Config, peer_slot: u32) -> i32 {
+    let slot = cfg.instance_slot.unwrap_or(peer_slot);
+    mddskmgr::core::instance_offset(slot, cfg.instance_step_px)
+}
+
+/// Title of this process's overlay window, derived from the config file in use.
+fn instance_title() -> windows::core::HSTRING {
+    let title = config::project_paths()
+        .map(|p| mddskmgr::core::instance_window_title(&p.cfg_file))
+        .unwrap_or_default();
+    windows::core::HSTRING::from(title)
+}
+
+/// Whether apps use the light theme (Settings > Personalization > Colors). Dark when unknown.
+fn is_light_theme() -> bool {
+    use windows::Win32::System::Registry::{HKEY_CURRENT_USER, RRF_RT_REG_DWORD, RegGetValueW};
+    let mut value: u32 = 0;
+    let mut size = std::mem::size_of::<u32>() as u32;
+    let status = unsafe {
+        RegGetValueW(
+            HKEY_CURRENT_USER,
+            windows::core::w!("Software\\Microsoft\\Windows\\CurrentVersion\\Themes\\Personalize"),
+            windows::core::w!("AppsUseLightTheme"),
+            RRF_RT_REG_DWORD,
+            None,
+            Some(&mut value as *mut u32 as *mut std::ffi::c_void),
+            Some(&mut size),
+        )
+    };
+    status.is_ok() && value != 0
+}
+
+/// Log, once per load, the settings that draws fall back from without a word.
+fn warn_config_fallbacks(cfg: &Config) {
+    mddskmgr::core::warn_invalid_desktop_colors(cfg);
+    mddskmgr::monitors::warn_if_missing(cfg.appearance.monitor_index);
+}
+
+/// Overlay colors for `appearance` under the current Windows theme.
+fn overlay_colors(
+    appearance: &mddskmgr::config::Appearance,
+) -> (mddskmgr::core::Rgba, mddskmgr::core::Rgba) {
+    mddskmgr::core::effective_colors(appearance, is_light_theme(), is_high_contrast())
 }
 
 fn is_high_contrast() -> bool {
@@ -126,18 +723,14 @@ fn is_foreground_fullscreen(app: &AppState) -> bool {
             return false;
         }
         let m = mi.rcMonitor;
-        let tol = 2;
-        let covers_monitor = rc.left <= m.left + tol
-            && rc.top <= m.top + tol
-            && rc.right >= m.right - tol
-            && rc.bottom >= m.bottom - tol;
-        if !covers_monitor {
-            return false;
-        }
-        // If it covers the monitor, treat as fullscreen only when there's no caption (likely borderless fullscreen)
+        // Borderless games have neither a caption nor a sizing frame.
         let style = GetWindowLongPtrW(fg, GWL_STYLE) as u32;
-        let has_caption = (style & WS_CAPTION.0) != 0;
-        let fullscreen = covers_monitor && !has_caption;
+        let has_frame = style & (WS_CAPTION.0 | WS_THICKFRAME.0) != 0;
+        let fullscreen = mddskmgr::core::is_fullscreen(
+            (rc.left, rc.top, rc.right, rc.bottom),
+            (m.left, m.top, m.right, m.bottom),
+            has_frame,
+        );
         if fullscreen {
             tracing::debug!(style=%format!("0x{style:08X}"), "fullscreen detected");
         }
@@ -145,34 +738,228 @@ fn is_foreground_fullscreen(app: &AppState) -> bool {
     }
 }
 
+/// Full image path of the foreground window's process (ignoring our own overlay).
+fn foreground_process_path(app: &AppState) -> Option<String> {
+    use windows::Win32::Foundation::CloseHandle;
+    use windows::Win32::System::Threading::{
+        OpenProcess, PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION,
+        QueryFullProcessImageNameW,
+    };
+    unsafe {
+        let fg = GetForegroundWindow();
+        if fg.0.is_null() || fg == app.hwnd {
+            return None;
+        }
+        let mut pid = 0u32;
+        GetWindowThreadProcessId(fg, Some(&mut pid));
+        if pid == 0 {
+            return None;
+        }
+        // Elevated or protected processes may refuse even limited access; treat as unknown.
+        let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid).ok()?;
+        let mut buf = [0u16; 1024];
+        let mut len = buf.len() as u32;
+        let queried = QueryFullProcessImageNameW(
+            process,
+            PROCESS_NAME_WIN32,
+            windows::core::PWSTR(buf.as_mut_ptr()),
+            &mut len,
+        );
+        let _ = CloseHandle(process);
+        queried.ok()?;
+        Some(String::from_utf16_lossy(&buf[..len as usize]))
+    }
+}
+
+/// Re-evaluate the foreground-window checks; each is skipped (and cleared) while its
+/// setting (`hide_on_fullscreen`, `hide_for_processes`) is off.
+fn update_foreground_flags(app: &mut AppState) {
+    app.hide_for_fullscreen =
+        app.cfg.appearance.hide_on_fullscreen && is_foreground_fullscreen(app);
+    let excluded = &app.cfg.appearance.hide_for_processes;
+    app.hide_for_app = !excluded.is_empty()
+        && foreground_process_path(app)
+            .is_some_and(|path| mddskmgr::core::is_excluded_process(&path, excluded));
+}
+
+fn visibility_state(app: &AppState) -> VisibilityState {
+    VisibilityState {
+        toggled_on: app.visible,
+        auto_hide: app.cfg.appearance.auto_hide_ms.is_some(),
+        shown_by_switch: app.shown_by_switch,
+        manual_override: app.manual_override,
+        high_contrast: app.hide_for_accessibility,
+        fullscreen: app.hide_for_fullscreen,
+        excluded_app: app.hide_for_app,
+        desktop_hidden: mddskmgr::core::desktop_hidden(&app.cfg, &app.label_key()),
+        display_off: app.display_off,
+        remote_disconnected: app.remote_disconnected,
+        always_show: app.cfg.appearance.always_show,
+        panic_hidden: app.panic_hidden,
+        peeking: app
+            .peek_until
+            .is_some_and(|t| std::time::Instant::now() < t),
+    }
+}
+
+fn overlay_should_show(app: &AppState) -> bool {
+    visibility_state(app).should_show()
+}
+
+/// Move the overlay against the current work area, then redraw it at the new size.
+fn reposition_overlay() {
+    let snap = APP.with(|slot| {
+        slot.borrow().as_ref().map(|app| {
+            (
+                app.overlay.clone(),
+                app.cfg.clone(),
+                app.current_guid.clone(),
+            )
+        })
+    });
+    if let Some((ov, cfg_clone, gid)) = snap {
+        if let Err(e) = ov.reposition(cfg_clone.appearance.margin_px) {
+            tracing::warn!(error=?e, "reposition failed");
+        }
+        draw_overlay_line(&ov, &cfg_clone, &gid);
+    }
+}
+
+/// Auto-hide: show the overlay now and arm the hide timer (4). No-op without `auto_hide_ms`.
+fn begin_auto_hide_window(hwnd: HWND) {
+    let ms = APP.with(|slot| {
+        let mut borrow = slot.borrow_mut();
+        let app = borrow.as_mut()?;
+        let ms = app.cfg.appearance.auto_hide_ms?;
+        app.shown_by_switch = true;
+        app.manual_override = false;
+        Some(ms)
+    });
+    if let Some(ms) = ms {
+        unsafe {
+            SetTimer(hwnd, 4, ms, None);
+        }
+        refresh_visibility_now();
+    }
+}
+
+/// Show (`Some(true)`), hide (`Some(false)`) or flip (`None`) the overlay at the user's
+/// request, then refresh. Flipping goes by what is on screen, not the stored toggle, so after
+/// auto-hide the first press shows it again. The choice also overrides auto-hide until the
+/// next switch. Shared by the toggle hotkey, the tray menu, the tray double-click and
+/// `WM_SHOW_OVERLAY`.
+fn set_visible_by_user(want: Option<bool>) {
+    APP.with(|slot| {
+        if let Some(app) = &mut *slot.borrow_mut() {
+            let show = want.unwrap_or_else(|| visibility_state(app).hide_reason().is_some());
+            app.visible = show;
+            app.manual_override = true;
+        }
+    });
+    refresh_visibility_now();
+}
+
+/// Engage or release the panic hide; confirms engaging it with a balloon.
+fn toggle_panic_hide(hwnd: HWND) {
+    let hidden = APP.with(|slot| {
+        slot.borrow_mut().as_mut().map(|app| {
+            app.panic_hidden = !app.panic_hidden;
+            app.panic_hidden
+        })
+    });
+    tracing::info!(?hidden, "panic hide");
+    if hidden == Some(true) {
+        let _ = Tray::balloon_for(
+            hwnd,
+            "Overlay hidden",
+            "Press the panic-hide hotkey again to show it.",
+        );
+    }
+}
+
+/// How long the peek hotkey shows the overlay.
+const PEEK_MS: u32 = 1500;
+
+/// Peek hotkey: show the overlay for [`PEEK_MS`] whatever hides it (see
+/// [`VisibilityState::peeking`]); timer 10 ends the peek. Pressing it again restarts the window.
+fn begin_peek(hwnd: HWND) {
+    let until = std::time::Instant::now() + std::time::Duration::from_millis(PEEK_MS.into());
+    APP.with(|slot| {
+        if let Some(app) = &mut *slot.borrow_mut() {
+            app.peek_until = Some(until);
+        }
+    });
+    unsafe {
+        SetTimer(hwnd, 10, PEEK_MS, None);
+    }
+}
+
+/// End of the peek (timer 10): nothing else was touched, so re-evaluating restores the
+/// visibility from before it.
+fn end_peek(hwnd: HWND) {
+    unsafe {
+        let _ = KillTimer(hwnd, 10);
+    }
+    APP.with(|slot| {
+        if let Some(app) = &mut *slot.borrow_mut() {
+            app.peek_until = None;
+        }
+    });
+    refresh_visibility_now();
+}
+
 fn refresh_visibility_now() {
     // Avoid holding RefCell borrows across ShowWindow (can re-enter wndproc).
     let args = APP.with(|slot| {
-        if let Some(app) = &*slot.borrow() {
-            let should_show = mddskmgr::core::should_show(
-                app.visible,
-                app.hide_for_accessibility,
-                app.hide_for_fullscreen,
-            );
-            Some((app.hwnd, should_show))
+        slot.borrow().as_ref().map(|app| {
+            (
+                app.hwnd,
+                visibility_state(app).hide_reason(),
+                app.cfg.appearance.fade_ms,
+                app.overlay.clone(),
+                app.mirrors.iter().map(Overlay::hwnd).collect::<Vec<_>>(),
+            )
+        })
+    });
+    if let Some((hwnd, hide_reason, fade_ms, overlay, mirrors)) = args {
+        let should_show = hide_reason.is_none();
+        // A panic hide must not linger on screen while fading out.
+        let fade_ms = if hide_reason == Some(HideReason::PanicHidden) {
+            0
         } else {
-            None
+            fade_ms
+        };
+        tracing::debug!(
+            ?hide_reason,
+            state = %(if should_show { "SHOW" } else { "HIDE" }),
+            "refresh_visibility_now"
+        );
+        if fade_ms > 0 {
+            start_fade(hwnd, &overlay, should_show, fade_ms);
+        } else {
+            // Fading may have just been turned off mid-animation.
+            cancel_fade(hwnd);
+            if overlay.fade() != 255 {
+                overlay.set_fade(255);
+            }
+            unsafe {
+                let _ = ShowWindow(hwnd, if should_show { SW_SHOW } else { SW_HIDE });
+            }
         }
-    });
-    if let Some((hwnd, should_show)) = args {
-        APP.with(|slot| {
-            if let Some(app) = &*slot.borrow() {
-                tracing::debug!(
-                    visible=%app.visible,
-                    hc_hide=%app.hide_for_accessibility,
-                    fs_hide=%app.hide_for_fullscreen,
-                    state=%(if should_show { "SHOW" } else { "HIDE" }),
-                    "refresh_visibility_now"
+        // Mirrors show and hide with the overlay, without fading.
+        for h in mirrors {
+            unsafe {
+                let _ = ShowWindow(
+                    h,
+                    if should_show {
+                        SW_SHOWNOACTIVATE
+                    } else {
+                        SW_HIDE
+                    },
                 );
             }
-        });
+        }
         unsafe {
-            let _ = ShowWindow(hwnd, if should_show { SW_SHOW } else { SW_HIDE });
             let _ = SetWindowPos(
                 hwnd,
                 HWND_TOPMOST,
@@ -186,11 +973,147 @@ fn refresh_visibility_now() {
     }
 }
 
+/// Fade the overlay toward shown or hidden, stepped by timer 5; the window is hidden only
+/// once a fade-out finishes. A new fade starts from the current level and replaces any
+/// in-flight one (re-arming timer 5 rather than adding a timer).
+fn start_fade(hwnd: HWND, overlay: &Overlay, show: bool, fade_ms: u32) {
+    let target = if show { 255 } else { 0 };
+    let window_visible = unsafe { IsWindowVisible(hwnd).as_bool() };
+    let from = if window_visible { overlay.fade() } else { 0 };
+    let heading_to = APP.with(|slot| {
+        slot.borrow()
+            .as_ref()
+            .and_then(|app| app.fade_anim.as_ref().map(|f| f.to))
+    });
+    let settled = heading_to.is_none() && from == target && window_visible == show;
+    if heading_to == Some(target) || settled {
+        return;
+    }
+    if show && !window_visible {
+        overlay.set_fade(0);
+        unsafe {
+            let _ = ShowWindow(hwnd, SW_SHOW);
+        }
+    }
+    let anim = FadeAnim {
+        from,
+        to: target,
+        start: std::time::Instant::now(),
+        duration_ms: mddskmgr::core::fade_duration(fade_ms, from, target),
+    };
+    APP.with(|slot| {
+        if let Some(app) = &mut *slot.borrow_mut() {
+            app.fade_anim = Some(anim);
+        }
+    });
+    unsafe {
+        SetTimer(hwnd, 5, 15, None);
+    }
+}
+
+/// Timer 5: advance the current fade; finish (and hide after a fade-out) once it's done.
+fn step_fade(hwnd: HWND) {
+    let step = APP.with(|slot| {
+        let borrow = slot.borrow();
+        let app = borrow.as_ref()?;
+        let anim = app.fade_anim.as_ref()?;
+        let elapsed = u32::try_from(anim.start.elapsed().as_millis()).unwrap_or(u32::MAX);
+        let level = mddskmgr::core::fade_level(anim.from, anim.to, elapsed, anim.duration_ms);
+        Some((app.overlay.clone(), level, elapsed >= anim.duration_ms))
+    });
+    let Some((overlay, level, done)) = step else {
+        cancel_fade(hwnd);
+        return;
+    };
+    overlay.set_fade(level);
+    if done {
+        cancel_fade(hwnd);
+        if level == 0 {
+            unsafe {
+                let _ = ShowWindow(hwnd, SW_HIDE);
+            }
+        }
+    }
+}
+
+fn cancel_fade(hwnd: HWND) {
+    unsafe {
+        let _ = KillTimer(hwnd, 5);
+    }
+    APP.with(|slot| {
+        if let Some(app) = &mut *slot.borrow_mut() {
+            app.fade_anim = None;
+        }
+    });
+}
+
+/// Balloon and `true` when the config is read-only, so edit entry points can bail out.
+fn labels_locked() -> bool {
+    let read_only = APP.with(|slot| {
+        slot.borrow()
+            .as_ref()
+            .map(|app| (app.hwnd, app.cfg.read_only))
+    });
+    if let Some((hwnd, true)) = read_only {
+        let _ = Tray::balloon_for(
+            hwnd,
+            "Read-only",
+            "Labels are locked by the config (read_only).",
+        );
+        return true;
+    }
+    false
+}
+
+/// Handle a `WM_COPYDATA` label update from `--set-title`/`--set-desc`.
+/// Returns `true` when the payload was valid and applied.
+fn set_label_from_ipc(hwnd: HWND, cds: &COPYDATASTRUCT) -> bool {
+    let len = cds.cbData as usize;
+    if cds.dwData != ipc::COPYDATA_SET_LABEL || cds.lpData.is_null() || len > ipc::MAX_PAYLOAD_BYTES
+    {
+        return false;
+    }
+    let bytes = unsafe { std::slice::from_raw_parts(cds.lpData as *const u8, len) };
+    let (field, text) = match ipc::decode_set_label(bytes) {
+        Ok(v) => v,
+        Err(e) => {
+            tracing::warn!(error=?e, "ignoring malformed label update");
+            return false;
+        }
+    };
+    if labels_locked() {
+        return false;
+    }
+    let target = APP.with(|slot| {
+        slot.borrow()
+            .as_ref()
+            .map(|app| (app.label_key(), app.cfg.appearance.label_limits()))
+    });
+    let Some((key, (title_max, desc_max))) = target else {
+        return false;
+    };
+    tracing::info!(?field, guid=%key, "label update from command line");
+    match field {
+        LabelField::Title => {
+            let text = mddskmgr::core::clamp_label(&text, title_max);
+            apply_label_edit(hwnd, key, Some(text), None);
+        }
+        LabelField::Description => {
+            let text = mddskmgr::core::clamp_label(&text, desc_max);
+            apply_label_edit(hwnd, key, None, Some(text));
+        }
+    }
+    true
+}
+
 fn quick_edit(edit_title: bool) {
+    if labels_locked() {
+        return;
+    }
     // Snapshot state without holding a mutable borrow during the modal UI.
     let snapshot = APP.with(|slot| {
         if let Some(app) = &*slot.borrow() {
-            let key = app.current_guid.clone();
+            let key = app.label_key();
             let label = app.cfg.desktops.get(&key).cloned().unwrap_or_default();
             let caption = if edit_title {
                 "Edit Desktop Title"
@@ -207,44 +1130,610 @@ fn quick_edit(edit_title: bool) {
             } else {
                 label.description
             };
+            let (title_max, desc_max) = app.cfg.appearance.label_limits();
+            let max_len = if edit_title { title_max } else { desc_max };
             Some((
                 app.hwnd,
                 key,
                 caption.to_string(),
                 hint.to_string(),
                 initial,
+                app.cfg.dialog_placement,
+                max_len,
             ))
         } else {
             None
         }
     });
 
-    if let Some((hwnd, key, caption, hint, initial)) = snapshot {
-        tracing::debug!(caption=%caption, guid=%key, initial=%initial, "quick_edit start");
-        if let Some(newtext) = ui::prompt_text(hwnd, &caption, &hint, &initial) {
-            tracing::debug!(text=%newtext, "quick_edit: new text");
-            let mut snap: Option<(Overlay, Config, String)> = None;
+    if let Some((hwnd, key, caption, hint, initial, placement, max_len)) = snapshot {
+        tracing::debug!(caption=%caption, guid=%key, initial=%initial, "quick_edit start");
+        if let Some(newtext) =
+            ui::prompt_text_limited(hwnd, &caption, &hint, &initial, placement, max_len)
+        {
+            tracing::debug!(text=%newtext, "quick_edit: new text");
+            if edit_title {
+                apply_label_edit(hwnd, key, Some(newtext), None);
+            } else {
+                apply_label_edit(hwnd, key, None, Some(newtext));
+            }
+        }
+    }
+}
+
+/// Edit title and description of the current desktop in one dialog.
+fn quick_edit_both() {
+    if labels_locked() {
+        return;
+    }
+    let snapshot = APP.with(|slot| {
+        slot.borrow().as_ref().map(|app| {
+            let key = app.label_key();
+            let label = app.cfg.desktops.get(&key).cloned().unwrap_or_default();
+            let limits = app.cfg.appearance.label_limits();
+            (app.hwnd, key, label, app.cfg.dialog_placement, limits)
+        })
+    });
+    if let Some((hwnd, key, label, placement, limits)) = snapshot {
+        tracing::debug!(guid=%key, "quick_edit_both start");
+        if let Some((title, description)) = ui::prompt_two_fields_at(
+            hwnd,
+            "Edit Desktop Label",
+            "Title",
+            &label.title,
+            "Description",
+            &label.description,
+            placement,
+            limits,
+        ) {
+            apply_label_edit(hwnd, key, Some(title), Some(description));
+        }
+    }
+}
+
+/// The tray menu, at the cursor; shared by the tray icon and the overlay itself.
+fn show_context_menu(hwnd: HWND) {
+    let checks = APP.with(|slot| {
+        slot.borrow()
+            .as_ref()
+            .map(|app| tray::MenuChecks {
+                overlay_visible: app.visible,
+                desktop_hidden: mddskmgr::core::desktop_hidden(&app.cfg, &app.label_key()),
+                hide_on_fullscreen: app.cfg.appearance.hide_on_fullscreen,
+            })
+            .unwrap_or_default()
+    });
+    let _ = Tray::show_popup_menu_with_state(hwnd, checks);
+}
+
+/// Interactive: the overlay takes mouse input (right-click menu, double-click to edit),
+/// so clicks no longer reach the windows beneath it. Otherwise it is fully click-through.
+fn set_overlay_interactive(hwnd: HWND, on: bool) {
+    unsafe {
+        let ex = GetWindowLongPtrW(hwnd, GWL_EXSTYLE);
+        let transparent = WS_EX_TRANSPARENT.0 as isize;
+        let new_ex = if on {
+            ex & !transparent
+        } else {
+            ex | transparent
+        };
+        if new_ex != ex {
+            SetWindowLongPtrW(hwnd, GWL_EXSTYLE, new_ex);
+        }
+    }
+}
+
+/// Flip the current desktop's `hidden` flag, save, and apply it right away.
+fn toggle_desktop_hidden() {
+    if labels_locked() {
+        return;
+    }
+    let result = APP.with(|slot| {
+        let mut borrow = slot.borrow_mut();
+        let app = borrow.as_mut()?;
+        let key = app.label_key();
+        let entry = app.cfg.desktops.entry(key).or_default();
+        entry.hidden = !entry.hidden;
+        let hidden = entry.hidden;
+        Some((
+            app.hwnd,
+            hidden,
+            config::save_atomic(&app.cfg, &app.cfg_paths),
+        ))
+    });
+    let Some((hwnd, hidden, saved)) = result else {
+        return;
+    };
+    tracing::info!(hidden, "toggled overlay for the current desktop");
+    if let Err(e) = saved {
+        tracing::warn!(error=?e, "toggle_desktop_hidden: save failed");
+    }
+    refresh_visibility_now();
+    let text = if hidden {
+        "The overlay is hidden on this desktop."
+    } else {
+        "The overlay shows on this desktop again."
+    };
+    let _ = Tray::balloon_for(hwnd, "Desktop Labeler", text);
+}
+
+/// Flip `appearance.hide_on_fullscreen` from the tray and save it.
+fn toggle_hide_on_fullscreen() {
+    if labels_locked() {
+        return;
+    }
+    let result = APP.with(|slot| {
+        let mut borrow = slot.borrow_mut();
+        let app = borrow.as_mut()?;
+        let appearance = &mut app.cfg.appearance;
+        appearance.hide_on_fullscreen = !appearance.hide_on_fullscreen;
+        let enabled = appearance.hide_on_fullscreen;
+        // Re-check the foreground window now so the change shows without waiting for timer 2.
+        update_foreground_flags(app);
+        Some((enabled, config::save_atomic(&app.cfg, &app.cfg_paths)))
+    });
+    let Some((enabled, saved)) = result else {
+        return;
+    };
+    tracing::info!(enabled, "toggled hide_on_fullscreen");
+    if let Err(e) = saved {
+        tracing::warn!(error=?e, "toggle_hide_on_fullscreen: save failed");
+    }
+    refresh_visibility_now();
+}
+
+/// Balloon and `false` when virtual desktops are unavailable on this system.
+fn desktops_available(hwnd: HWND) -> bool {
+    let supported = APP.with(|slot| slot.borrow().as_ref().is_some_and(|app| app.vd_supported));
+    if !supported {
+        let _ = Tray::balloon_for(
+            hwnd,
+            "Virtual desktops",
+            "Virtual desktops aren't available on this system.",
+        );
+    }
+    supported
+}
+
+/// Create a virtual desktop, then offer to title it (skipped when labels are read-only).
+fn new_desktop(hwnd: HWND) {
+    if !desktops_available(hwnd) {
+        return;
+    }
+    let guid = match vd::create_desktop() {
+        Ok(guid) => guid,
+        Err(e) => {
+            tracing::warn!(error=?e, "new desktop failed");
+            let _ = Tray::balloon_for(
+                hwnd,
+                "New Desktop",
+                &format!("Couldn't create a desktop: {e}"),
+            );
+            return;
+        }
+    };
+    tracing::info!(guid=%guid, "created desktop");
+    let prompt = APP.with(|slot| {
+        slot.borrow()
+            .as_ref()
+            .filter(|app| !app.cfg.read_only)
+            .map(|app| {
+                (
+                    app.cfg.dialog_placement,
+                    app.cfg.appearance.label_limits().0,
+                )
+            })
+    });
+    let Some((placement, title_max)) = prompt else {
+        return;
+    };
+    let Some(title) = ui::prompt_text_limited(
+        hwnd,
+        "New Desktop",
+        "Title for the new desktop (optional)",
+        "",
+        placement,
+        title_max,
+    )
+    .filter(|t| !t.trim().is_empty()) else {
+        return;
+    };
+    APP.with(|slot| {
+        if let Some(app) = &mut *slot.borrow_mut() {
+            let key = label_key(&app.cfg, &guid);
+            app.cfg.desktops.entry(key).or_default().title = title;
+            if let Err(e) = config::save_atomic(&app.cfg, &app.cfg_paths) {
+                tracing::warn!(error=?e, "new desktop: save failed");
+            }
+        }
+    });
+}
+
+/// Remove the current virtual desktop and drop its label. Refuses to remove the last one.
+fn remove_current_desktop(hwnd: HWND) {
+    if !desktops_available(hwnd) {
+        return;
+    }
+    // Index-keyed labels need the position, which is gone once the desktop is.
+    let index = vd::current_desktop_index();
+    match vd::remove_current_desktop() {
+        Ok(Some(guid)) => {
+            tracing::info!(guid=%guid, "removed desktop");
+            APP.with(|slot| {
+                if let Some(app) = &mut *slot.borrow_mut() {
+                    if app.cfg.read_only {
+                        return;
+                    }
+                    let removed = match index.filter(|_| app.cfg.appearance.key_by_index) {
+                        Some(i) => config::remove_index_label(&mut app.cfg, i),
+                        None => app.cfg.desktops.remove(&guid).is_some(),
+                    };
+                    if !removed {
+                        return;
+                    }
+                    if let Err(e) = config::save_atomic(&app.cfg, &app.cfg_paths) {
+                        tracing::warn!(error=?e, "remove desktop: save failed");
+                    }
+                }
+            });
+        }
+        Ok(None) => {
+            let _ = Tray::balloon_for(
+                hwnd,
+                "Remove Desktop",
+                "This is the only desktop; Windows needs at least one.",
+            );
+        }
+        Err(e) => {
+            tracing::warn!(error=?e, "remove desktop failed");
+            let _ = Tray::balloon_for(
+                hwnd,
+                "Remove Desktop",
+                &format!("Couldn't remove the desktop: {e}"),
+            );
+        }
+    }
+}
+
+/// Edit every desktop's label in one window, saving once if anything changed.
+fn manage_labels() {
+    if labels_locked() {
+        return;
+    }
+    let live_guids = vd::list_desktop_guids();
+    let snapshot = APP.with(|slot| {
+        slot.borrow().as_ref().map(|app| {
+            // Rows are keyed like `cfg.desktops`: by GUID, or by index with `key_by_index`.
+            let key_of = |g: &str| config::label_key(&app.cfg, g, &live_guids);
+            let live: Vec<String> = live_guids.iter().map(|g| key_of(g)).collect();
+            let current = key_of(&app.current_guid);
+            // Live desktops in Task View order (labeled or current), then stale entries.
+            let mut guids: Vec<String> = live
+                .iter()
+                .filter(|g| app.cfg.desktops.contains_key(*g) || **g == current)
+                .cloned()
+                .collect();
+            if !guids.contains(&current) {
+                guids.insert(0, current.clone());
+            }
+            let mut rest: Vec<String> = app
+                .cfg
+                .desktops
+                .keys()
+                .filter(|g| !guids.contains(g))
+                .cloned()
+                .collect();
+            rest.sort();
+            guids.extend(rest);
+            let rows: Vec<config::LabelEdit> = guids
+                .into_iter()
+                .map(|guid| {
+                    let label = app.cfg.desktops.get(&guid).cloned().unwrap_or_default();
+                    config::LabelEdit {
+                        guid,
+                        title: label.title,
+                        description: label.description,
+                        remove: false,
+                    }
+                })
+                .collect();
+            // Visit counts next to each desktop, only for users who opted in.
+            let visits: HashMap<String, u64> = if app.cfg.track_usage {
+                rows.iter()
+                    .map(|r| (r.guid.clone(), app.usage.visits(&r.guid)))
+                    .collect()
+            } else {
+                HashMap::new()
+            };
+            (
+                app.hwnd,
+                rows,
+                current,
+                visits,
+                app.cfg.dialog_placement,
+                app.cfg.appearance.label_limits(),
+            )
+        })
+    });
+    let Some((hwnd, rows, current, visits, placement, limits)) = snapshot else {
+        return;
+    };
+    let Some(edited) = ui::manage_labels(hwnd, rows, &current, &visits, placement, limits) else {
+        return;
+    };
+    let mut snap: Option<(Overlay, Config, String)> = None;
+    APP.with(|slot| {
+        if let Some(app) = &mut *slot.borrow_mut() {
+            if !config::apply_label_edits(&mut app.cfg, &edited) {
+                return;
+            }
+            if let Err(e) = config::save_atomic(&app.cfg, &app.cfg_paths) {
+                tracing::warn!(error=?e, "manage_labels: save failed");
+            }
+            snap = Some((
+                app.overlay.clone(),
+                app.cfg.clone(),
+                app.current_guid.clone(),
+            ));
+        }
+    });
+    if let Some((ov, cfg_clone, gid)) = snap {
+        draw_overlay_line(&ov, &cfg_clone, &gid);
+        refresh_visibility_now();
+    }
+}
+
+/// Rebind one of the main chords from the capture dialog, then save and re-register.
+fn edit_hotkeys(hwnd: HWND) {
+    if labels_locked() {
+        return;
+    }
+    let snapshot = APP.with(|slot| {
+        slot.borrow()
+            .as_ref()
+            .map(|app| (app.cfg.hotkeys.clone(), app.cfg.dialog_placement))
+    });
+    let Some((hk, placement)) = snapshot else {
+        return;
+    };
+    let actions = hotkeys::RebindAction::ALL
+        .iter()
+        .map(|a| (a.name().to_string(), a.chord(&hk).clone()))
+        .collect();
+    let Some((index, chord)) = ui::capture_hotkey(hwnd, actions, placement) else {
+        return;
+    };
+    let action = hotkeys::RebindAction::ALL[index];
+    let mut updated = hk.clone();
+    *action.chord_mut(&mut updated) = chord.clone();
+    if updated == hk {
+        return;
+    }
+    let shown = hotkeys::format_chord(&chord);
+    if hotkeys::has_duplicates(&updated) {
+        let _ = Tray::balloon_for(
+            hwnd,
+            "Hotkeys",
+            &format!("{shown} is already used by another hotkey."),
+        );
+        return;
+    }
+    let mut snap: Option<(Overlay, Config, String)> = None;
+    APP.with(|slot| {
+        if let Some(app) = &mut *slot.borrow_mut() {
+            // Updating the in-memory chords first keeps the reload from re-registering again.
+            app.cfg.hotkeys = updated.clone();
+            if let Err(e) = config::save_atomic(&app.cfg, &app.cfg_paths) {
+                tracing::warn!(error=?e, "edit_hotkeys: save failed");
+            }
+            snap = Some((
+                app.overlay.clone(),
+                app.cfg.clone(),
+                app.current_guid.clone(),
+            ));
+        }
+    });
+    let results = hotkeys::register_all(hwnd, &updated);
+    tracing::info!(action = action.name(), chord = %shown, "rebound hotkey");
+    let text = hotkeys::failure_summary(&results)
+        .unwrap_or_else(|| format!("{} is now {shown}.", action.name()));
+    let _ = Tray::balloon_for(hwnd, "Hotkeys", &text);
+    if let Some((ov, cfg_clone, gid)) = snap {
+        // The hint after the title names the edit chords.
+        draw_overlay_line(&ov, &cfg_clone, &gid);
+    }
+}
+
+/// Save the running config to a file picked in a Save dialog.
+fn export_config_to_file(hwnd: HWND) {
+    let Some(cfg) = APP.with(|slot| slot.borrow().as_ref().map(|app| app.cfg.clone())) else {
+        return;
+    };
+    let Some(path) = ui::save_json_dialog(hwnd, "Export Config", "labels-export.json") else {
+        return;
+    };
+    let text = match config::export_config(&cfg, &path) {
+        Ok(()) => format!("Exported to {}", path.display()),
+        Err(e) => {
+            tracing::warn!(error=?e, "export config failed");
+            format!("Export failed: {e:#}")
+        }
+    };
+    let _ = Tray::balloon_for(hwnd, "Export Config", &text);
+}
+
+/// Put every label on the clipboard as text, the current desktop marked.
+fn copy_labels_to_clipboard(hwnd: HWND) {
+    let Some((cfg, current)) = APP.with(|slot| {
+        slot.borrow()
+            .as_ref()
+            .map(|app| (app.cfg.clone(), app.current_guid.clone()))
+    }) else {
+        return;
+    };
+    let text = mddskmgr::core::labels_text(&cfg, &current, &vd::list_desktop_guids());
+    let note = match mddskmgr::utils::set_clipboard_text(hwnd, &text) {
+        Ok(()) => format!("Copied {} label(s) to the clipboard", cfg.desktops.len()),
+        Err(e) => {
+            tracing::warn!(error=?e, "copy labels failed");
+            format!("Copy failed: {e:#}")
+        }
+    };
+    let _ = Tray::balloon_for(hwnd, "Copy Labels", &note);
+}
+
+/// Take the labels from a file picked in an Open dialog, merged or replacing the current ones.
+/// The running config is only changed through the reload after a successful save.
+fn import_config_from_file(hwnd: HWND) {
+    if labels_locked() {
+        return;
+    }
+    let Some(path) = ui::open_json_dialog(hwnd, "Import Config") else {
+        return;
+    };
+    let imported = match config::read_config_file(&path) {
+        Ok(c) => c,
+        Err(e) => {
+            tracing::warn!(error=?e, path=%path.display(), "import config: invalid file");
+            let _ = Tray::balloon_for(hwnd, "Import Config", &format!("Import failed: {e:#}"));
+            return;
+        }
+    };
+    let Some(mode) = ui::ask_import_mode(hwnd) else {
+        return;
+    };
+    let snapshot = APP.with(|slot| {
+        slot.borrow()
+            .as_ref()
+            .map(|app| (app.cfg.clone(), app.cfg_paths.clone()))
+    });
+    let Some((mut cfg, paths)) = snapshot else {
+        return;
+    };
+    let count = imported.desktops.len();
+    config::import_desktops(&mut cfg, imported, mode);
+    match config::save_atomic(&cfg, &paths) {
+        Ok(()) => {
+            unsafe {
+                let _ = PostMessageW(hwnd, WM_CFG_CHANGED, WPARAM(0), LPARAM(0));
+            }
+            let _ = Tray::balloon_for(
+                hwnd,
+                "Import Config",
+                &format!("Imported {count} label(s) from {}", path.display()),
+            );
+        }
+        Err(e) => {
+            tracing::warn!(error=?e, "import config: save failed");
+            let _ = Tray::balloon_for(hwnd, "Import Config", &format!("Import failed: {e:#}"));
+        }
+    }
+}
+
+/// After confirmation, back up the config and replace it with the defaults; the reload
+/// (WM_CFG_CHANGED) clears the labels and re-registers the default hotkeys.
+fn reset_config(hwnd: HWND) {
+    if labels_locked() || !ui::confirm_reset(hwnd) {
+        return;
+    }
+    let Some(paths) = APP.with(|slot| slot.borrow().as_ref().map(|app| app.cfg_paths.clone()))
+    else {
+        return;
+    };
+    match config::reset_to_defaults(&paths) {
+        Ok(backup) => {
+            tracing::info!(backup=?backup, "config reset to defaults");
             APP.with(|slot| {
                 if let Some(app) = &mut *slot.borrow_mut() {
-                    let entry = app.cfg.desktops.entry(key).or_default();
-                    if edit_title {
-                        entry.title = newtext;
-                    } else {
-                        entry.description = newtext;
-                    }
-                    let _ = mddskmgr::config::save_atomic(&app.cfg, &app.cfg_paths);
-                    tracing::debug!(?app.cfg_paths.cfg_file, "quick_edit: saved config");
-                    snap = Some((
-                        app.overlay.clone(),
-                        app.cfg.clone(),
-                        app.current_guid.clone(),
-                    ));
+                    app.cfg.desktops.clear();
                 }
             });
-            if let Some((ov, cfg_clone, gid)) = snap {
-                draw_overlay_line(&ov, &cfg_clone, &gid);
-                refresh_visibility_now();
+            unsafe {
+                let _ = PostMessageW(hwnd, WM_CFG_CHANGED, WPARAM(0), LPARAM(0));
+            }
+            let text = match backup {
+                Some(bak) => format!(
+                    "Config reset to defaults. The old one was saved as {}",
+                    bak.display()
+                ),
+                None => "Config reset to defaults.".to_string(),
+            };
+            let _ = Tray::balloon_for(hwnd, "Reset Config", &text);
+        }
+        Err(e) => {
+            tracing::warn!(error=?e, "reset config failed");
+            let _ = Tray::balloon_for(hwnd, "Reset Config", &format!("Reset failed: {e:#}"));
+        }
+    }
+}
+
+/// Store the edited field(s) for desktop `key` with a single save, then redraw.
+fn apply_label_edit(hwnd: HWND, key: String, title: Option<String>, description: Option<String>) {
+    let mut snap: Option<(Overlay, Config, String)> = None;
+    let mut sync_name: Option<String> = None;
+    APP.with(|slot| {
+        if let Some(app) = &mut *slot.borrow_mut() {
+            let title = title.map(|t| {
+                if app.cfg.preserve_whitespace {
+                    t
+                } else {
+                    mddskmgr::config::normalize_label(&t)
+                }
+            });
+            let entry = app.cfg.desktops.entry(key).or_default();
+            if let Some(t) = &title {
+                entry.title = t.clone();
+            }
+            if let Some(d) = description {
+                entry.description = d;
+            }
+            match mddskmgr::config::save_atomic(&app.cfg, &app.cfg_paths) {
+                Ok(()) => {
+                    tracing::debug!(?app.cfg_paths.cfg_file, "quick_edit: saved config");
+                    if app.cfg.appearance.sync_windows_desktop_name {
+                        sync_name = title;
+                    }
+                }
+                Err(e) => tracing::warn!(error=?e, "quick_edit: save failed"),
             }
+            snap = Some((
+                app.overlay.clone(),
+                app.cfg.clone(),
+                app.current_guid.clone(),
+            ));
+        }
+    });
+    if let Some(Err(e)) = sync_name.map(|name| vd::set_current_desktop_name(&name)) {
+        tracing::warn!(error=?e, "quick_edit: desktop rename failed");
+        let _ = Tray::balloon_for(
+            hwnd,
+            "Desktop name",
+            &format!("Couldn't rename the Windows desktop: {e}"),
+        );
+    }
+    if let Some((ov, cfg_clone, gid)) = snap {
+        draw_overlay_line(&ov, &cfg_clone, &gid);
+        refresh_visibility_now();
+    }
+}
+
+/// Flip run-at-login and confirm the new state (or the failure) via a tray balloon.
+fn toggle_run_at_login(hwnd: HWND) {
+    let was_enabled = autorun::get_run_at_login();
+    match autorun::set_run_at_login(!was_enabled) {
+        Ok(()) => {
+            let text = if was_enabled {
+                "Run at login disabled"
+            } else {
+                "Run at login enabled"
+            };
+            let _ = Tray::balloon_for(hwnd, "Run at login", text);
+        }
+        Err(e) => {
+            tracing::warn!(error=?e, "toggle_run_at_login failed");
+            // Best-effort rollback in case the registry write was partial.
+            let _ = autorun::set_run_at_login(was_enabled);
+            let _ = Tray::balloon_for(hwnd, "Run at login", &format!("Failed to update: {e}"));
         }
     }
 }
@@ -253,10 +1742,38 @@ extern "system" fn wndproc(hwnd: HWND, msg: u32, w: WPARAM, l: LPARAM) -> LRESUL
     match msg {
         WM_CREATE => {
             APP.with(|slot| {
-                let (cfg, paths) = config::load_or_default().expect("config load");
-                let overlay = Overlay::new(hwnd, &cfg.appearance.font_family, cfg.appearance.font_size_dip).expect("overlay");
+                let (mut cfg, paths, outcome) = match config::load_with_outcome() {
+                    Ok(loaded) => loaded,
+                    Err(e) => return startup_failure("load its configuration", &e),
+                };
+                rekey_labels(&mut cfg, &paths);
+                warn_config_fallbacks(&cfg);
+                let mut overlay = match Overlay::new(hwnd, &cfg.appearance.font_family, cfg.appearance.font_size_dip) {
+                    Ok(overlay) => overlay,
+                    Err(e) => return startup_failure("create the overlay", &e),
+                };
+                overlay.set_font(&cfg.appearance.font_family, cfg.appearance.font_size_dip, cfg.appearance.font_weight, cfg.appearance.font_italic);
+                overlay.set_fallback_fonts(&cfg.appearance.fallback_fonts);
+                // The slot is fixed for this run, so reloads never move two instances together.
+                publish_placement(hwnd, &cfg);
+                let peer_slot = count_other_overlays(hwnd);
+                overlay.set_offset_y(instance_offset_for(&cfg, peer_slot));
+                overlay.set_nudge(cfg.appearance.offset_x, cfg.appearance.offset_y);
+                overlay.set_anchor(cfg.appearance.anchor);
+                let (fg, bg) = overlay_colors(&cfg.appearance);
+                overlay.set_colors(fg, bg);
+                overlay.set_opacity(cfg.appearance.opacity);
+                overlay.set_monitor_index(cfg.appearance.monitor_index);
+                overlay.set_shape(cfg.appearance.padding_px, cfg.appearance.corner_radius_px);
+                overlay.set_text_shadow(mddskmgr::core::text_shadow(&cfg.appearance));
+                Tray::set_edits_locked(cfg.read_only);
+                if cfg.appearance.backdrop_blur && !mddskmgr::overlay::set_backdrop_blur(hwnd, true) { tracing::debug!("backdrop blur unavailable; using solid background"); }
                 let taskbar_created_msg = unsafe { RegisterWindowMessageW(PCWSTR(windows::core::w!("TaskbarCreated").as_wide().as_ptr())) };
-                let tray = Tray::new(hwnd, "Desktop Labeler").expect("tray");
+                let icon = config::tray_icon_file(&cfg, &paths.cfg_dir);
+                let tray = match Tray::new(hwnd, "Desktop Labeler", icon.as_deref()) {
+                    Ok(tray) => tray,
+                    Err(e) => return startup_failure("add its tray icon", &e),
+                };
 
                 // Register hotkeys (warn on duplicates)
                 let hk = &cfg.hotkeys;
@@ -264,23 +1781,50 @@ extern "system" fn wndproc(hwnd: HWND, msg: u32, w: WPARAM, l: LPARAM) -> LRESUL
                     // Show a friendly tray balloon (without holding a RefCell borrow).
                     let _ = mddskmgr::tray::Tray::balloon_for(hwnd, "Hotkeys", "Duplicate hotkeys detected; adjust labels.json");
                 }
-                let _ = hotkeys::register(hwnd, hk.edit_title.ctrl, hk.edit_title.alt, hk.edit_title.shift, &hk.edit_title.key, HK_EDIT_TITLE);
-                let _ = hotkeys::register(hwnd, hk.edit_description.ctrl, hk.edit_description.alt, hk.edit_description.shift, &hk.edit_description.key, HK_EDIT_DESC);
-                let _ = hotkeys::register(hwnd, hk.toggle_overlay.ctrl, hk.toggle_overlay.alt, hk.toggle_overlay.shift, &hk.toggle_overlay.key, HK_TOGGLE);
-                let _ = hotkeys::register(hwnd, hk.snap_position.ctrl, hk.snap_position.alt, hk.snap_position.shift, &hk.snap_position.key, hotkeys::HK_SNAP);
+                let registrations = hotkeys::register_all(hwnd, hk);
+                if let Some(failed) = hotkeys::failure_summary(&registrations) {
+                    tracing::warn!(%failed, "some hotkeys failed to register");
+                    let text = hotkey_failure_text(&failed, config::is_from_newer_version(&cfg));
+                    let _ = Tray::balloon_for(hwnd, "Hotkeys", &text);
+                } else if config::is_from_newer_version(&cfg) {
+                    let _ = Tray::balloon_for(hwnd, "Desktop Labeler", NEWER_CONFIG_NOTE);
+                } else if outcome == config::LoadOutcome::FirstRun {
+                    tracing::info!("first run: showing the welcome balloon");
+                    let _ = Tray::balloon_for(hwnd, "Desktop Labeler", &welcome_text(&cfg.hotkeys));
+                }
 
-                let current_guid = vd::get_current_desktop_guid();
-                let vd_thread = mddskmgr::vd::start_vd_events(hwnd, WM_VD_SWITCHED);
-                let app = AppState { hwnd, cfg, cfg_paths: paths, overlay, current_guid, visible: true, tray, taskbar_created_msg, vd_thread, hide_for_accessibility: false, hide_for_fullscreen: false, anchor_index: 1 };
+                let (current_guid, vd_supported) = match vd::try_current_desktop_guid() {
+                    Ok(guid) => (guid, true),
+                    Err(e) => {
+                        tracing::warn!(error=?e, "virtual desktops unsupported; labeling a single desktop");
+                        (vd::FALLBACK_GUID.to_string(), false)
+                    }
+                };
+                let vd_thread = if vd_supported {
+                    mddskmgr::vd::start_vd_events(hwnd, WM_VD_SWITCHED)
+                        .map_err(|e| tracing::warn!(error=?e, "desktop events unavailable; polling instead"))
+                        .ok()
+                } else {
+                    None
+                };
+                Tray::set_desktops_available(vd_supported);
+                let usage = Usage::load(&usage::usage_path(&paths.cfg_dir));
+                let app = AppState { hwnd, cfg, cfg_paths: paths, overlay, mirrors: Vec::new(), mirror_handles: Vec::new(), current_guid, visible: true, tray, taskbar_created_msg, vd_thread, vd_supported, hide_for_accessibility: false, hide_for_fullscreen: false, hide_for_app: false, power_notify: None, display_off: false, remote_disconnected: false, panic_hidden: false, shown_by_switch: false, manual_override: false, fade_anim: None, usage, contrast: HashMap::new(), switch_pending: false, peek_until: None, vd_restarts: 0, vd_retry_at: None, peer_slot };
                 // Draw initial line before storing
                 let ov = app.overlay.clone();
                 let cfg_clone = app.cfg.clone();
                 let gid = app.current_guid.clone();
                 *slot.borrow_mut() = Some(app);
+                if let Some(app) = &*slot.borrow() { app.want_contrast_sample(); }
+                sync_mirrors();
                 draw_overlay_line(&ov, &cfg_clone, &gid);
+                set_overlay_interactive(hwnd, cfg_clone.appearance.overlay_interactive);
                 start_runtime_services(hwnd);
-            });
-            LRESULT(0)
+                if !vd_supported {
+                    let _ = Tray::balloon_for(hwnd, "Desktop Labeler", "Virtual desktops aren't available on this Windows version; one label is shown on every desktop.");
+                }
+                LRESULT(0)
+            })
         }
         msg if {
             let mut is_taskbar = false;
@@ -291,10 +1835,17 @@ extern "system" fn wndproc(hwnd: HWND, msg: u32, w: WPARAM, l: LPARAM) -> LRESUL
         } => {
             // Re-add the tray icon without keeping a RefCell borrow during Shell calls.
             let _ = mddskmgr::tray::Tray::re_add_for(hwnd);
+            // A restarted shell can take registered hotkeys down with it.
+            recover_hotkeys(hwnd, "explorer restarted");
             LRESULT(0)
         }
         WM_RBUTTONUP | WM_CONTEXTMENU => {
-            let _ = mddskmgr::tray::Tray::show_popup_menu(hwnd);
+            // Only reachable in interactive mode (no WS_EX_TRANSPARENT).
+            show_context_menu(hwnd);
+            LRESULT(0)
+        }
+        WM_LBUTTONDBLCLK => {
+            quick_edit(true);
             LRESULT(0)
         }
         WM_SETCURSOR => {
@@ -304,94 +1855,206 @@ extern "system" fn wndproc(hwnd: HWND, msg: u32, w: WPARAM, l: LPARAM) -> LRESUL
             LRESULT(1)
         }
         WM_VD_SWITCHED => {
-            // Update current GUID, then draw outside of the borrow to avoid re-entrancy
-            let mut snapshot: Option<(Overlay, Config, String)> = None;
+            // Track the desktop right away (for --dump and the stats), but leave the redraw to
+            // timer 9, which every event re-arms: holding the switch shortcut then renders only
+            // the desktop the burst ends on.
             APP.with(|slot| {
-                if let Some(app) = &mut *slot.borrow_mut() {
-                    let id = vd::get_current_desktop_guid();
-                    if id != app.current_guid {
-                        app.current_guid = id.clone();
-                    }
-                    snapshot = Some((app.overlay.clone(), app.cfg.clone(), app.current_guid.clone()));
+                let mut borrow = slot.borrow_mut();
+                let Some(app) = borrow.as_mut() else { return };
+                if let Some(id) = mddskmgr::core::switched_desktop(&vd::WinVd, &app.current_guid) {
+                    app.current_guid = id;
+                    app.record_usage();
+                    app.want_contrast_sample();
+                    app.switch_pending = true;
                 }
             });
-            if let Some((ov, cfg_clone, gid)) = snapshot { draw_overlay_line(&ov, &cfg_clone, &gid); }
+            unsafe { SetTimer(hwnd, 9, SWITCH_SETTLE_MS, None); }
+            prune_deleted_desktops();
+            LRESULT(0)
+        }
+        WM_SHOW_OVERLAY => {
+            set_visible_by_user(Some(true));
+            LRESULT(0)
+        }
+        WM_REHOOK => {
+            rehook_and_report(hwnd);
             LRESULT(0)
         }
+        WM_COPYDATA => {
+            if l.0 == 0 {
+                return LRESULT(0);
+            }
+            let cds = unsafe { &*(l.0 as *const COPYDATASTRUCT) };
+            LRESULT(set_label_from_ipc(hwnd, cds) as isize)
+        }
         WM_CFG_CHANGED => {
             // Reload config and apply labels/hotkeys; show any balloon outside borrow.
-            let mut need_balloon = false;
+            let mut hotkey_failures: Option<String> = None;
+            let mut became_newer = false;
+            let mut icon_changed: Option<Option<PathBuf>> = None;
+            let mut placement_changed: Option<bool> = None;
+            let mut blur_changed: Option<bool> = None;
+            let mut resume_timers: Option<bool> = None; // suspension lifted: VD poll needed?
             let mut snapshot: Option<(Overlay, Config, String, HWND)> = None;
             APP.with(|slot| {
                 let mut borrow = slot.borrow_mut();
-                if let (Some(app), Ok((new_cfg, _))) = (
-                    &mut *borrow,
-                    mddskmgr::config::load_or_default(),
-                ) {
+                // Never writes: a file an editor holds mid-save just keeps the current config.
+                let loaded = mddskmgr::config::load_without_writing()
+                    .map_err(|e| tracing::warn!(error=?e, "config reload failed; keeping the current config"));
+                if let (Some(app), Ok((new_cfg, _))) = (&mut *borrow, loaded) {
+                    if new_cfg.per_desktop_window != app.cfg.per_desktop_window {
+                        placement_changed = Some(new_cfg.per_desktop_window);
+                    }
+                    if new_cfg.appearance.tray_icon_path != app.cfg.appearance.tray_icon_path {
+                        icon_changed = Some(config::tray_icon_file(&new_cfg, &app.cfg_paths.cfg_dir));
+                    }
+                    if new_cfg.appearance.backdrop_blur != app.cfg.appearance.backdrop_blur {
+                        blur_changed = Some(new_cfg.appearance.backdrop_blur);
+                    }
+                    // A save often fires several reloads; only touch hotkeys when a chord changed,
+                    // so shortcuts never drop out while the same chords are re-registered.
+                    let hotkeys_changed = new_cfg.hotkeys != app.cfg.hotkeys;
+                    // E.g. a synced config just saved by a newer build on another machine.
+                    became_newer = config::is_from_newer_version(&new_cfg) && !config::is_from_newer_version(&app.cfg);
                     app.cfg = new_cfg;
-                    // Re-register hotkeys
-                    mddskmgr::hotkeys::unregister(app.hwnd, HK_EDIT_TITLE);
-                    mddskmgr::hotkeys::unregister(app.hwnd, HK_EDIT_DESC);
-                    mddskmgr::hotkeys::unregister(app.hwnd, HK_TOGGLE);
-                    mddskmgr::hotkeys::unregister(app.hwnd, hotkeys::HK_SNAP);
-                    let hk = &app.cfg.hotkeys;
-                    let ok1 = mddskmgr::hotkeys::register(app.hwnd, hk.edit_title.ctrl, hk.edit_title.alt, hk.edit_title.shift, &hk.edit_title.key, HK_EDIT_TITLE).unwrap_or(false);
-                    let ok2 = mddskmgr::hotkeys::register(app.hwnd, hk.edit_description.ctrl, hk.edit_description.alt, hk.edit_description.shift, &hk.edit_description.key, HK_EDIT_DESC).unwrap_or(false);
-                    let ok3 = mddskmgr::hotkeys::register(app.hwnd, hk.toggle_overlay.ctrl, hk.toggle_overlay.alt, hk.toggle_overlay.shift, &hk.toggle_overlay.key, HK_TOGGLE).unwrap_or(false);
-                    let ok4 = mddskmgr::hotkeys::register(app.hwnd, hk.snap_position.ctrl, hk.snap_position.alt, hk.snap_position.shift, &hk.snap_position.key, hotkeys::HK_SNAP).unwrap_or(false);
-                    if !(ok1 && ok2 && ok3 && ok4) { need_balloon = true; }
+                    rekey_labels(&mut app.cfg, &app.cfg_paths);
+                    warn_config_fallbacks(&app.cfg);
+                    let a = &app.cfg.appearance;
+                    app.overlay.set_font(&a.font_family, a.font_size_dip, a.font_weight, a.font_italic);
+                    app.overlay.set_fallback_fonts(&a.fallback_fonts);
+                    publish_placement(app.hwnd, &app.cfg);
+                    app.overlay.set_offset_y(instance_offset_for(&app.cfg, app.peer_slot));
+                    app.overlay.set_nudge(app.cfg.appearance.offset_x, app.cfg.appearance.offset_y);
+                    app.overlay.set_anchor(app.cfg.appearance.anchor);
+                    let (fg, bg) = overlay_colors(&app.cfg.appearance);
+                    app.overlay.set_colors(fg, bg);
+                    app.overlay.set_opacity(app.cfg.appearance.opacity);
+                    app.overlay.set_monitor_index(app.cfg.appearance.monitor_index);
+                    app.overlay.set_shape(app.cfg.appearance.padding_px, app.cfg.appearance.corner_radius_px);
+                    app.overlay.set_text_shadow(mddskmgr::core::text_shadow(&app.cfg.appearance));
+                    Tray::set_edits_locked(app.cfg.read_only);
+                    resume_timers = app.sync_power_notify().then(|| app.needs_vd_poll());
+                    if hotkeys_changed {
+                        hotkey_failures = hotkeys::failure_summary(&hotkeys::register_all(app.hwnd, &app.cfg.hotkeys));
+                    }
+                    // Apply visibility settings now rather than on the next timer tick.
+                    update_foreground_flags(app);
+                    app.want_contrast_sample();
                     snapshot = Some((app.overlay.clone(), app.cfg.clone(), app.current_guid.clone(), app.hwnd));
                 }
             });
+            if let (Some(poll), Some((_, cfg_clone, _, _))) = (resume_timers, &snapshot) {
+                tracing::info!("suspend_on_display_off turned off: resuming overlay");
+                start_timers(hwnd, poll, &cfg_clone.appearance);
+            }
+            sync_mirrors();
             if let Some((ov, cfg_clone, gid, _)) = snapshot { draw_overlay_line(&ov, &cfg_clone, &gid); }
-            if need_balloon {
-                let _ = mddskmgr::tray::Tray::balloon_for(hwnd, "Hotkeys", "Some hotkeys failed to register. Adjust in labels.json");
+            if let Some(per_desktop) = placement_changed { apply_desktop_placement(hwnd, per_desktop); }
+            if let Some(blur) = blur_changed { let _ = mddskmgr::overlay::set_backdrop_blur(hwnd, blur); }
+            if let Some(icon) = icon_changed {
+                if let Err(e) = Tray::set_icon_path(icon.as_deref()) {
+                    tracing::warn!(error=?e, "custom tray icon unavailable; using the built-in icon");
+                }
+                let _ = Tray::refresh_icon_for(hwnd);
+            }
+            let interactive = APP.with(|slot| slot.borrow().as_ref().is_some_and(|app| app.cfg.appearance.overlay_interactive));
+            set_overlay_interactive(hwnd, interactive);
+            refresh_visibility_now();
+            if let Some(failed) = hotkey_failures {
+                tracing::warn!(%failed, "some hotkeys failed to register");
+                let _ = Tray::balloon_for(hwnd, "Hotkeys", &hotkey_failure_text(&failed, became_newer));
+            } else if became_newer {
+                let _ = Tray::balloon_for(hwnd, "Desktop Labeler", NEWER_CONFIG_NOTE);
             }
             LRESULT(0)
         }
         WM_TIMER => {
             if w.0 == 1 { // VD poller
-                let mut snapshot: Option<(Overlay, Config, String)> = None;
+                let mut snapshot: Option<(Overlay, Config, String, bool)> = None;
                 APP.with(|slot| {
                     if let Some(app) = &mut *slot.borrow_mut() {
-                        let id = vd::get_current_desktop_guid();
-                        if id != app.current_guid {
-                            app.current_guid = id.clone();
-                        }
-                        snapshot = Some((app.overlay.clone(), app.cfg.clone(), app.current_guid.clone()));
+                        let switched = match mddskmgr::core::switched_desktop(&vd::WinVd, &app.current_guid) {
+                            Some(id) => { app.current_guid = id; app.record_usage(); app.want_contrast_sample(); true }
+                            None => false,
+                        };
+                        snapshot = Some((app.overlay.clone(), app.cfg.clone(), app.current_guid.clone(), switched));
                     }
                 });
-                if let Some((ov, cfg_clone, gid)) = snapshot { draw_overlay_line(&ov, &cfg_clone, &gid); }
+                if let Some((ov, cfg_clone, gid, switched)) = snapshot {
+                    draw_overlay_line(&ov, &cfg_clone, &gid);
+                    if cfg_clone.per_desktop_window { vd::move_to_current_desktop(hwnd); }
+                    if switched { run_switch_hook(&cfg_clone, &gid); begin_auto_hide_window(hwnd); }
+                }
             } else if w.0 == 2 {
                 APP.with(|slot| {
-                    if let Some(app) = &mut *slot.borrow_mut() {
-                        let hide = if app.cfg.appearance.hide_on_fullscreen { is_foreground_fullscreen(app) } else { false };
-                        app.hide_for_fullscreen = hide;
-                    }
+                    if let Some(app) = &mut *slot.borrow_mut() { update_foreground_flags(app); }
                 });
             }
-            if w.0 == 2 { refresh_visibility_now(); }
+            if w.0 == 2 { check_vd_events(hwnd); refresh_visibility_now(); }
+            if w.0 == 5 { step_fade(hwnd); }
+            if w.0 == 6 { retry_hotkeys(hwnd); }
+            if w.0 == 8 { sample_contrast(hwnd); }
+            if w.0 == 9 { settle_switch(hwnd); }
+            if w.0 == 10 { end_peek(hwnd); }
+            if w.0 == 7 {
+                unsafe { let _ = KillTimer(hwnd, 7); }
+                APP.with(|slot| {
+                    if let Some(app) = &mut *slot.borrow_mut() { app.flush_usage(); }
+                });
+            }
+            if w.0 == 4 {
+                // Auto-hide window elapsed
+                unsafe { let _ = KillTimer(hwnd, 4); }
+                APP.with(|slot| {
+                    if let Some(app) = &mut *slot.borrow_mut() { app.shown_by_switch = false; }
+                });
+                refresh_visibility_now();
+            }
             if w.0 == 3 {
                 // Keep overlay at the top of TOPMOST band without stealing focus
-                let visible = APP.with(|slot| {
-                    if let Some(app) = &*slot.borrow() {
-                        mddskmgr::core::should_show(
-                            app.visible,
-                            app.hide_for_accessibility,
-                            app.hide_for_fullscreen,
-                        )
-                    } else { false }
-                });
+                let visible = APP.with(|slot| slot.borrow().as_ref().is_some_and(overlay_should_show));
                 if visible { unsafe { let _ = SetWindowPos(hwnd, HWND_TOPMOST, 0,0,0,0, SWP_NOMOVE | SWP_NOSIZE | SWP_NOACTIVATE); } }
             }
             LRESULT(0)
         }
         WM_SETTINGCHANGE => {
+            // Also fires for light/dark theme switches ("ImmersiveColorSet").
+            let mut redraw: Option<(Overlay, Config, String)> = None;
+            let mut docked = false;
             APP.with(|slot| {
                 if let Some(app) = &mut *slot.borrow_mut() {
                     app.hide_for_accessibility = is_high_contrast();
+                    docked = app.cfg.appearance.anchor == config::OverlayAnchor::Taskbar;
+                    if w.0 as u32 == SPI_SETDESKWALLPAPER.0 {
+                        // New wallpaper: every desktop's sample is stale.
+                        app.contrast.clear();
+                        app.want_contrast_sample();
+                    }
+                    if app.cfg.appearance.auto_theme {
+                        let (fg, bg) = overlay_colors(&app.cfg.appearance);
+                        app.overlay.set_colors(fg, bg);
+                        redraw = Some((app.overlay.clone(), app.cfg.clone(), app.current_guid.clone()));
+                    }
                 }
             });
+            // The taskbar moved, resized or toggled auto-hide: follow it.
+            if w.0 as u32 == SPI_SETWORKAREA.0 || docked {
+                reposition_overlay();
+            } else if let Some((ov, cfg_clone, gid)) = redraw { draw_overlay_line(&ov, &cfg_clone, &gid); }
+            refresh_visibility_now();
+            LRESULT(0)
+        }
+        WM_DISPLAYCHANGE | WM_DPICHANGED => {
+            // Resolution, docking or monitor layout changed: the rect from the last draw was
+            // computed against the old work area, so move first, then redraw at the new size.
+            tracing::info!(msg, "display configuration changed: repositioning overlay");
+            // The overlay may now sit over different pixels.
+            APP.with(|slot| {
+                if let Some(app) = &mut *slot.borrow_mut() { app.contrast.clear(); app.want_contrast_sample(); }
+            });
+            // Monitors may have come or gone; rebuild the mirrors before redrawing them.
+            sync_mirrors();
+            reposition_overlay();
             refresh_visibility_now();
             LRESULT(0)
         }
@@ -399,38 +2062,93 @@ extern "system" fn wndproc(hwnd: HWND, msg: u32, w: WPARAM, l: LPARAM) -> LRESUL
             let code = w.0 as u32;
             APP.with(|slot| {
                 if let Some(app) = &mut *slot.borrow_mut() {
-                    match code { // 0x7 lock, 0x8 unlock
+                    match code { // 0x3 remote connect, 0x4 remote disconnect, 0x7 lock, 0x8 unlock
+                        0x3 => { app.remote_disconnected = false; app.hide_for_accessibility = is_high_contrast(); }
+                        0x4 => { app.remote_disconnected = true; }
                         0x7 => { app.hide_for_accessibility = true; }
                         0x8 => { app.hide_for_accessibility = is_high_contrast(); }
                         _ => {}
                     }
                 }
             });
+            if code == 0x3 {
+                // The remote client's resolution/DPI may differ from the last session's.
+                tracing::info!("remote session connected: repositioning overlay");
+                reposition_overlay();
+            }
+            if code == 0x8 {
+                recover_hotkeys(hwnd, "session unlocked");
+            }
             refresh_visibility_now();
             LRESULT(0)
         }
+        WM_POWERBROADCAST if w.0 as u32 == PBT_POWERSETTINGCHANGE => {
+            let setting = unsafe { &*(l.0 as *const POWERBROADCAST_SETTING) };
+            if setting.PowerSetting == GUID_CONSOLE_DISPLAY_STATE && setting.DataLength >= 1 {
+                let display_on = setting.Data[0] != 0; // dimmed (2) still counts as on
+                // Snapshot whether the state actually changed; notifications repeat on dim/undim.
+                let mut change: Option<(bool, Overlay, Config)> = None;
+                APP.with(|slot| {
+                    if let Some(app) = &mut *slot.borrow_mut() {
+                        let suspend = !display_on && app.cfg.suspend_on_display_off;
+                        if app.display_off != suspend {
+                            app.display_off = suspend;
+                            change = Some((app.needs_vd_poll(), app.overlay.clone(), app.cfg.clone()));
+                        }
+                    }
+                });
+                if let Some((poll, ov, cfg_clone)) = change {
+                    if display_on {
+                        tracing::info!("display on: resuming overlay");
+                        start_timers(hwnd, poll, &cfg_clone.appearance);
+                        // The desktop may have changed while the screen was off.
+                        let id = vd::get_current_desktop_guid();
+                        APP.with(|slot| {
+                            if let Some(app) = &mut *slot.borrow_mut() { app.current_guid = id.clone(); }
+                        });
+                        draw_overlay_line(&ov, &cfg_clone, &id);
+                    } else {
+                        tracing::info!("display off: suspending overlay");
+                        stop_timers(hwnd);
+                    }
+                    refresh_visibility_now();
+                }
+            }
+            LRESULT(1)
+        }
         WM_HOTKEY => {
             let id = w.0 as i32;
             let mut need_refresh = false;
             match id {
                 HK_EDIT_TITLE => quick_edit(true),
                 HK_EDIT_DESC => quick_edit(false),
-                HK_TOGGLE => {
-                    APP.with(|slot| {
-                        if let Some(app) = &mut *slot.borrow_mut() { app.visible = !app.visible; }
-                    });
-                    need_refresh = true;
-                }
+                HK_TOGGLE => set_visible_by_user(None),
                 hotkeys::HK_SNAP => {
-                    let mut snap: Option<(Overlay, Config, String, f32)> = None;
+                    let mut snap: Option<(Overlay, Config, String)> = None;
                     APP.with(|slot| {
                         if let Some(app) = &mut *slot.borrow_mut() {
-                            app.anchor_index = (app.anchor_index + 1) % 3;
-                            let ratio = anchor_ratio_from_index(app.anchor_index);
-                            snap = Some((app.overlay.clone(), app.cfg.clone(), app.current_guid.clone(), ratio));
+                            let anchor = app.cfg.appearance.anchor.next();
+                            app.cfg.appearance.anchor = anchor;
+                            app.overlay.set_anchor(anchor);
+                            // Persist so the position survives restart (refused when read-only).
+                            if let Err(e) = mddskmgr::config::save_atomic(&app.cfg, &app.cfg_paths) { tracing::warn!(error=?e, "snap: save failed"); }
+                            snap = Some((app.overlay.clone(), app.cfg.clone(), app.current_guid.clone()));
                         }
                     });
-                    if let Some((ov, cfg_clone, gid, _ratio)) = snap { draw_overlay_line(&ov, &cfg_clone, &gid); }
+                    if let Some((ov, cfg_clone, gid)) = snap { draw_overlay_line(&ov, &cfg_clone, &gid); }
+                }
+                hotkeys::HK_TOGGLE_AUTORUN => toggle_run_at_login(hwnd),
+                hotkeys::HK_EDIT_BOTH => quick_edit_both(),
+                hotkeys::HK_TOGGLE_DESKTOP_HIDDEN => toggle_desktop_hidden(),
+                hotkeys::HK_NEW_DESKTOP => new_desktop(hwnd),
+                hotkeys::HK_REMOVE_DESKTOP => remove_current_desktop(hwnd),
+                hotkeys::HK_PANIC => {
+                    toggle_panic_hide(hwnd);
+                    need_refresh = true;
+                }
+                hotkeys::HK_PEEK => {
+                    begin_peek(hwnd);
+                    need_refresh = true;
                 }
                 _ => {}
             }
@@ -440,13 +2158,8 @@ extern "system" fn wndproc(hwnd: HWND, msg: u32, w: WPARAM, l: LPARAM) -> LRESUL
         TRAY_MSG => {
             let l = l.0 as u32;
             match l {
-                WM_CONTEXTMENU | WM_RBUTTONUP => { let _ = mddskmgr::tray::Tray::show_popup_menu(hwnd); }
-                WM_LBUTTONDBLCLK => {
-                    APP.with(|slot| {
-                        if let Some(app) = &mut *slot.borrow_mut() { app.visible = true; }
-                    });
-                    refresh_visibility_now();
-                }
+                WM_CONTEXTMENU | WM_RBUTTONUP => show_context_menu(hwnd),
+                WM_LBUTTONDBLCLK => set_visible_by_user(Some(true)),
                 _ => {}
             }
             LRESULT(0)
@@ -460,12 +2173,16 @@ extern "system" fn wndproc(hwnd: HWND, msg: u32, w: WPARAM, l: LPARAM) -> LRESUL
             match cmd {
                 CMD_EDIT_TITLE => quick_edit(true),
                 CMD_EDIT_DESC => quick_edit(false),
-                CMD_TOGGLE => {
-                    APP.with(|slot| {
-                        if let Some(app) = &mut *slot.borrow_mut() { app.visible = !app.visible; }
-                    });
-                    refresh_visibility_now();
-                }
+                tray::CMD_MANAGE_LABELS => manage_labels(),
+                tray::CMD_TOGGLE_DESKTOP_HIDDEN => toggle_desktop_hidden(),
+                tray::CMD_TOGGLE_HIDE_FULLSCREEN => toggle_hide_on_fullscreen(),
+                tray::CMD_NEW_DESKTOP => new_desktop(hwnd),
+                tray::CMD_REMOVE_DESKTOP => remove_current_desktop(hwnd),
+                tray::CMD_RESET_CONFIG => reset_config(hwnd),
+                tray::CMD_EXPORT_CONFIG => export_config_to_file(hwnd),
+                tray::CMD_COPY_LABELS => copy_labels_to_clipboard(hwnd),
+                tray::CMD_IMPORT_CONFIG => import_config_from_file(hwnd),
+                CMD_TOGGLE => set_visible_by_user(None),
                 CMD_OPEN_CONFIG => {
                     // Snapshot path then ShellExecute without holding borrow.
                     let path = APP.with(|slot| {
@@ -479,13 +2196,16 @@ extern "system" fn wndproc(hwnd: HWND, msg: u32, w: WPARAM, l: LPARAM) -> LRESUL
                     }
                 }
                 CMD_EXIT => {
-                    // Trigger orderly teardown to avoid hangs: destroy window -> WM_DESTROY posts quit.
-                    unsafe { let _ = DestroyWindow(hwnd); }
+                    // Read the setting first; the message box pumps messages and must not run under a borrow.
+                    let confirm = APP.with(|slot| slot.borrow().as_ref().is_some_and(|app| app.cfg.appearance.confirm_exit));
+                    if !confirm || ui::confirm_exit(hwnd) {
+                        // Trigger orderly teardown to avoid hangs: destroy window -> WM_DESTROY posts quit.
+                        unsafe { let _ = DestroyWindow(hwnd); }
+                    }
                 },
-                tray::CMD_RUN_AT_STARTUP => {
-                    let cur = autorun::get_run_at_login();
-                    let _ = autorun::set_run_at_login(!cur);
-                }
+                tray::CMD_REHOOK => rehook_and_report(hwnd),
+                tray::CMD_EDIT_HOTKEYS => edit_hotkeys(hwnd),
+                tray::CMD_TOGGLE_AUTORUN => toggle_run_at_login(hwnd),
                 tray::CMD_ABOUT => {
                     unsafe {
                         let _ = MessageBoxW(
@@ -500,26 +2220,16 @@ extern "system" fn wndproc(hwnd: HWND, msg: u32, w: WPARAM, l: LPARAM) -> LRESUL
             }
             LRESULT(0)
         }
+        0x0016 /* WM_ENDSESSION */ => {
+            // Logoff or shutdown: the process may be ended without ever seeing WM_DESTROY.
+            if w.0 != 0 {
+                tracing::info!("session ending: shutting down");
+                shutdown(hwnd);
+            }
+            LRESULT(0)
+        }
         WM_DESTROY => {
-            APP.with(|slot| {
-                if let Some(app) = &mut *slot.borrow_mut() {
-                    // Stop timers to avoid re-entrancy during teardown
-                    unsafe {
-                        let _ = KillTimer(hwnd, 1);
-                        let _ = KillTimer(hwnd, 2);
-                        let _ = KillTimer(hwnd, 3);
-                    }
-                    mddskmgr::hotkeys::unregister(app.hwnd, HK_EDIT_TITLE);
-                    mddskmgr::hotkeys::unregister(app.hwnd, HK_EDIT_DESC);
-                    mddskmgr::hotkeys::unregister(app.hwnd, HK_TOGGLE);
-                    mddskmgr::hotkeys::unregister(app.hwnd, hotkeys::HK_SNAP);
-                    // Remove tray icon to prevent ghost icons after exit
-                    app.tray.remove_icon();
-                    // Drop virtual desktop event thread if present
-                    app.vd_thread = None;
-                }
-            });
-            unsafe { let _ = WTSUnRegisterSessionNotification(hwnd); }
+            shutdown(hwnd);
             unsafe { PostQuitMessage(0); }
             LRESULT(0)
         }
@@ -527,11 +2237,153 @@ extern "system" fn wndproc(hwnd: HWND, msg: u32, w: WPARAM, l: LPARAM) -> LRESUL
     }
 }
 
-fn single_instance_guard() -> bool {
+/// Orderly teardown for `WM_DESTROY` and `WM_ENDSESSION`: stop timers and notifications,
+/// write pending usage stats, unpin and close the windows, and remove the tray icon. Config
+/// saves are synchronous, so usage stats are the only deferred write. Safe to run twice.
+fn shutdown(hwnd: HWND) {
+    // Stop timers first to avoid re-entrancy during teardown.
+    unsafe {
+        for id in TIMER_IDS {
+            let _ = KillTimer(hwnd, id);
+        }
+    }
+    let mirrors = APP.with(|slot| {
+        let mut borrow = slot.borrow_mut();
+        let app = borrow.as_mut()?;
+        if let Some(h) = app.power_notify.take() {
+            unsafe {
+                let _ = UnregisterPowerSettingNotification(h);
+            }
+        }
+        hotkeys::unregister_all(app.hwnd);
+        unsafe {
+            let _ = RemovePropW(app.hwnd, PLACEMENT_PROP);
+        }
+        app.flush_usage();
+        // Remove tray icon to prevent ghost icons after exit
+        app.tray.remove_icon();
+        // Drop virtual desktop event thread if present
+        app.vd_thread = None;
+        Some(std::mem::take(&mut app.mirrors))
+    });
+    if mirrors.is_none() {
+        // No app state (failed start, or already torn down): remove the icon by handle.
+        let _ = Tray::remove_for(hwnd);
+    }
+    // Pinned windows are tracked by the shell; release them instead of leaving that to it.
+    vd::unpin_window(hwnd);
+    // Destroyed outside the borrow; each mirror gets its own WM_DESTROY.
+    for m in mirrors.unwrap_or_default() {
+        vd::unpin_window(m.hwnd());
+        unsafe {
+            let _ = DestroyWindow(m.hwnd());
+        }
+    }
+    unsafe {
+        let _ = WTSUnRegisterSessionNotification(hwnd);
+    }
+}
+
+/// Health check for the desktop-event thread, run on the visibility timer. When winvd's
+/// listener has died, fall back to polling (timer 1) and retry the subscription with backoff
+/// ([`mddskmgr::core::vd_restart_delay_ms`]); a successful restart stops the poll timer again.
+fn check_vd_events(hwnd: HWND) {
+    let now = std::time::Instant::now();
+    APP.with(|slot| {
+        let mut borrow = slot.borrow_mut();
+        let Some(app) = borrow.as_mut() else { return };
+        let poll_ms = mddskmgr::core::poll_interval(app.cfg.appearance.poll_interval_ms);
+        if let Some(dead) = app.vd_thread.take_if(|t| !t.is_alive()) {
+            let uptime_ms = dead.uptime().as_millis().try_into().unwrap_or(u64::MAX);
+            app.vd_restarts = mddskmgr::core::vd_restarts_after_death(app.vd_restarts, uptime_ms);
+            app.vd_retry_at = mddskmgr::core::vd_restart_delay_ms(app.vd_restarts)
+                .map(|ms| now + std::time::Duration::from_millis(ms.into()));
+            if app.vd_retry_at.is_none() {
+                tracing::warn!("desktop event thread died again; no restarts left");
+            }
+            match poll_ms {
+                Some(ms) => {
+                    tracing::warn!(uptime_ms, "desktop event thread died; polling until it restarts");
+                    unsafe {
+                        SetTimer(hwnd, 1, ms, None);
+                    }
+                }
+                None => tracing::warn!(
+                    "desktop event thread died and poll_interval_ms is 0: switches untracked until it restarts"
+                ),
+            }
+            return;
+        }
+        if !app.retry_at_due(now) {
+            return;
+        }
+        match vd::start_vd_events(hwnd, WM_VD_SWITCHED) {
+            Ok(events) => {
+                // Counted until the thread proves stable (`vd_restarts_after_death`).
+                app.vd_restarts += 1;
+                tracing::info!(attempt = app.vd_restarts, "desktop event thread restarted");
+                app.vd_thread = Some(events);
+                app.vd_retry_at = None;
+                unsafe {
+                    let _ = KillTimer(hwnd, 1);
+                }
+            }
+            Err(e) => {
+                app.vd_restarts += 1;
+                app.vd_retry_at = mddskmgr::core::vd_restart_delay_ms(app.vd_restarts)
+                    .map(|ms| now + std::time::Duration::from_millis(ms.into()));
+                if app.vd_retry_at.is_some() {
+                    tracing::warn!(error = ?e, attempt = app.vd_restarts, "desktop event restart failed; retrying");
+                } else {
+                    tracing::warn!(error = ?e, "desktop event restart failed; staying on the poller");
+                }
+            }
+        }
+    });
+}
+
+/// The overlay window of an already-running instance using the same config file, if there is
+/// one. Instances with other configs (`--config`, `--portable`) don't count.
+fn single_instance_guard() -> Option<HWND> {
+    unsafe {
+        let h =
+            FindWindowW(OVERLAY_WND_CLASS, &instance_title()).unwrap_or(HWND(std::ptr::null_mut()));
+        (!h.0.is_null()).then_some(h)
+    }
+}
+
+/// Start the periodic timers: 1 = VD poller (only when `poll` and not disabled), 2 = visibility,
+/// 3 = topmost. (Timer 4 is the one-shot auto-hide timer armed by `begin_auto_hide_window`.)
+fn start_timers(hwnd: HWND, poll: bool, appearance: &mddskmgr::config::Appearance) {
+    unsafe {
+        if let Some(ms) =
+            mddskmgr::core::poll_interval(appearance.poll_interval_ms).filter(|_| poll)
+        {
+            SetTimer(hwnd, 1, ms, None);
+        }
+        SetTimer(
+            hwnd,
+            2,
+            mddskmgr::core::visibility_interval(appearance.visibility_interval_ms),
+            None,
+        );
+        // Periodic topmost reassertion
+        SetTimer(hwnd, 3, 1200, None);
+    }
+}
+
+/// Every timer id the overlay window uses: 1 = VD poller, 2 = visibility, 3 = topmost,
+/// 4 = auto-hide, 5 = fade, 6 = hotkey retry, 7 = usage flush, 8 = contrast sample,
+/// 9 = switch settle, 10 = peek end. A new timer goes here so `shutdown` kills it too.
+const TIMER_IDS: [usize; 10] = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10];
+/// How many of `TIMER_IDS`, from the start, are the periodic ones `start_timers` arms.
+const PERIODIC_TIMERS: usize = 3;
+
+fn stop_timers(hwnd: HWND) {
     unsafe {
-        let class_name = windows::core::w!("DesktopOverlayWndClass");
-        let h = FindWindowW(class_name, None).unwrap_or(HWND(std::ptr::null_mut()));
-        h.0.is_null()
+        for id in &TIMER_IDS[..PERIODIC_TIMERS] {
+            let _ = KillTimer(hwnd, *id);
+        }
     }
 }
 
@@ -542,19 +2394,16 @@ fn start_runtime_services(hwnd: HWND) {
         let cfg_path_opt = {
             let borrowed = slot.borrow();
             if let Some(app) = &*borrowed {
-                if app.vd_thread.is_none() {
-                    unsafe {
-                        SetTimer(hwnd, 1, 250, None);
+                let poll_ms = mddskmgr::core::poll_interval(app.cfg.appearance.poll_interval_ms);
+                if app.needs_vd_poll() {
+                    match poll_ms {
+                        Some(ms) => vd::start_vd_poller(hwnd, WM_VD_SWITCHED, ms),
+                        None => tracing::warn!(
+                            "desktop events unavailable and poll_interval_ms is 0: desktop switches won't be tracked"
+                        ),
                     }
-                    vd::start_vd_poller(hwnd, WM_VD_SWITCHED);
-                }
-                unsafe {
-                    SetTimer(hwnd, 2, 1000, None);
-                }
-                // Periodic topmost reassertion
-                unsafe {
-                    SetTimer(hwnd, 3, 1200, None);
                 }
+                start_timers(hwnd, app.needs_vd_poll(), &app.cfg.appearance);
                 unsafe {
                     let _ = WTSRegisterSessionNotification(hwnd, NOTIFY_FOR_THIS_SESSION);
                 }
@@ -566,23 +2415,37 @@ fn start_runtime_services(hwnd: HWND) {
         // Then, mutable borrow to set accessibility/visibility flags
         if let Some(app) = &mut *slot.borrow_mut() {
             app.hide_for_accessibility = is_high_contrast();
+            app.sync_power_notify();
         }
         refresh_visibility_now();
+        // With auto-hide, announce the starting desktop like a switch.
+        begin_auto_hide_window(hwnd);
         // Launch config watcher threads outside of any RefCell borrow
         if let Some(cfg_path) = cfg_path_opt {
             let (tx, rx) = std_mpsc::channel::<()>();
             std::thread::spawn(move || {
-                let (watch_tx, watch_rx) = std_mpsc::channel();
+                let (watch_tx, watch_rx) = std_mpsc::channel::<notify::Result<notify::Event>>();
                 let mut watcher: RecommendedWatcher =
                     Watcher::new(watch_tx, notify::Config::default()).expect("watcher");
-                let _ = watcher.watch(&cfg_path, RecursiveMode::NonRecursive);
-                while let Ok(_ev) = watch_rx.recv() {
-                    let _ = tx.send(());
+                // Watch the directory: editors that delete-and-recreate the file break a file watch.
+                let cfg_dir = cfg_path
+                    .parent()
+                    .map(|p| p.to_path_buf())
+                    .unwrap_or_else(|| cfg_path.clone());
+                let _ = watcher.watch(&cfg_dir, RecursiveMode::NonRecursive);
+                while let Ok(ev) = watch_rx.recv() {
+                    if ev.is_ok_and(|ev| config::is_config_event(&ev.paths, &cfg_path)) {
+                        let _ = tx.send(());
+                    }
                 }
             });
             let hwnd_copy = hwnd.0 as usize;
             std::thread::spawn(move || {
                 while rx.recv().is_ok() {
+                    // Coalesce a save's burst of events into a single reload.
+                    if !mddskmgr::utils::drain_burst(&rx, std::time::Duration::from_millis(300)) {
+                        break;
+                    }
                     unsafe {
                         let _ = PostMessageW(
                             HWND(hwnd_copy as *mut std::ffi::c_void),
@@ -597,20 +2460,187 @@ fn start_runtime_services(hwnd: HWND) {
     });
 }
 
+/// Report a fatal `WM_CREATE` failure in a message box and return the value that makes
+/// `CreateWindowExW` fail, so `main` exits with an error instead of panicking.
+fn startup_failure(what: &str, err: &anyhow::Error) -> LRESULT {
+    // The HRESULT and its system message, e.g. from Direct2D or DirectWrite.
+    let hresult = err
+        .chain()
+        .find_map(|e| e.downcast_ref::<windows::core::Error>())
+        .map(|e| match e.message() {
+            message if message.is_empty() => format!(" (HRESULT 0x{:08X})", e.code().0 as u32),
+            message => format!(" (HRESULT 0x{:08X}: {message})", e.code().0 as u32),
+        })
+        .unwrap_or_default();
+    tracing::error!(error=?err, "startup failed: could not {}{}", what, hresult);
+    ui::show_error(
+        HWND(std::ptr::null_mut()),
+        "Desktop Labeler",
+        &format!("Desktop Labeler couldn't {what}{hresult}.\n\n{err:#}"),
+    );
+    LRESULT(-1)
+}
+
+/// Handle one-shot command-line actions that don't need the overlay window.
+fn run_cli_command(cmd: cli::Command) -> Result<()> {
+    match cmd {
+        cli::Command::Run => {}
+        cli::Command::ExportTheme(path) => {
+            let (cfg, _) = config::load_without_writing()?;
+            config::export_theme(&cfg, &path)?;
+            println!("Exported theme to {}", path.display());
+        }
+        cli::Command::Rehook => {
+            let mut posted = 0;
+            let mut prev = HWND(std::ptr::null_mut());
+            unsafe {
+                while let Ok(h) = FindWindowExW(None, prev, OVERLAY_WND_CLASS, None) {
+                    if h.0.is_null() {
+                        break;
+                    }
+                    if PostMessageW(h, WM_REHOOK, WPARAM(0), LPARAM(0)).is_ok() {
+                        posted += 1;
+                    }
+                    prev = h;
+                }
+            }
+            if posted == 0 {
+                anyhow::bail!("No running instance found");
+            }
+            println!(
+                "Asked {} running instance(s) to re-register hotkeys",
+                posted
+            );
+        }
+        cli::Command::ImportTheme(path) => {
+            let (mut cfg, paths) = config::load_or_default()?;
+            let ignored = config::import_theme(&mut cfg, &path)?;
+            for key in &ignored {
+                println!("Ignored unknown appearance key: {}", key);
+            }
+            // Saving triggers the running instance's config watcher, which reloads it.
+            config::save_atomic(&cfg, &paths)?;
+            println!("Imported theme from {}", path.display());
+        }
+        cli::Command::Dump => {
+            // Read-only: safe to run next to a live instance.
+            let (cfg, paths) = config::load_without_writing()?;
+            let current = vd::get_current_desktop_guid();
+            let live = vd::list_desktop_guids();
+            let mut dump = mddskmgr::core::state_dump(&cfg, &current, &live, &vd::WinVd);
+            // Which file was read: the profile, a portable copy or a `--config` override.
+            dump["config_file"] = serde_json::json!(paths.cfg_file);
+            if cfg.track_usage {
+                let usage = Usage::load(&usage::usage_path(&paths.cfg_dir));
+                dump["usage"] = serde_json::to_value(&usage.desktops)?;
+            }
+            println!("{}", serde_json::to_string_pretty(&dump)?);
+        }
+        cli::Command::SetLabel(field, text) => {
+            let payload = ipc::encode_set_label(field, &text)?;
+            let what = match field {
+                LabelField::Title => "title",
+                LabelField::Description => "description",
+            };
+            let running = single_instance_guard();
+            if let Some(h) = running {
+                let cds = COPYDATASTRUCT {
+                    dwData: ipc::COPYDATA_SET_LABEL,
+                    cbData: payload.len() as u32,
+                    lpData: payload.as_ptr() as *mut _,
+                };
+                let res = unsafe {
+                    SendMessageW(h, WM_COPYDATA, WPARAM(0), LPARAM(&cds as *const _ as isize))
+                };
+                if res.0 == 0 {
+                    anyhow::bail!(
+                        "The running instance rejected the update (labels may be read-only)"
+                    );
+                }
+                println!("Updated the {} of the current desktop", what);
+            } else {
+                // No instance: edit the file directly.
+                let (mut cfg, paths) = config::load_or_default()?;
+                if cfg.read_only {
+                    anyhow::bail!("Labels are locked by the config (read_only)");
+                }
+                let (title_max, desc_max) = cfg.appearance.label_limits();
+                let entry = config::label_entry(
+                    &mut cfg,
+                    &vd::get_current_desktop_guid(),
+                    &vd::list_desktop_guids(),
+                );
+                match field {
+                    LabelField::Title => {
+                        entry.title = mddskmgr::core::clamp_label(&text, title_max)
+                    }
+                    LabelField::Description => {
+                        entry.description = mddskmgr::core::clamp_label(&text, desc_max)
+                    }
+                }
+                config::save_atomic(&cfg, &paths)?;
+                println!(
+                    "No running instance; saved the {} to {}",
+                    what,
+                    paths.cfg_file.display()
+                );
+            }
+        }
+        cli::Command::Autostart(action) => {
+            match action {
+                cli::AutostartAction::On => autorun::set_run_at_login(true)?,
+                cli::AutostartAction::Off => autorun::set_run_at_login(false)?,
+                cli::AutostartAction::Status => {}
+            }
+            let state = if autorun::get_run_at_login() {
+                "enabled"
+            } else {
+                "disabled"
+            };
+            println!("Run at login: {}", state);
+        }
+    }
+    Ok(())
+}
+
 pub fn main() -> Result<()> {
     // Logging is initialized by src/main.rs; nothing to do here.
 
-    if !single_instance_guard() {
-        tracing::warn!("Another instance is already running. Exiting.");
+    // We are a GUI-subsystem binary, so one-shot commands borrow the launching
+    // terminal's console for their output (and usage errors) if there is one.
+    if std::env::args().len() > 1 {
+        unsafe {
+            let _ = AttachConsole(ATTACH_PARENT_PROCESS);
+        }
+    }
+
+    let invocation = cli::parse_invocation(std::env::args().skip(1))?;
+    if invocation.portable {
+        config::set_portable();
+    }
+    if let Some(path) = invocation.config {
+        config::set_config_override(path);
+    }
+    match invocation.command {
+        cli::Command::Run => {}
+        cmd => return run_cli_command(cmd),
+    }
+
+    if let Some(existing) = single_instance_guard() {
+        tracing::warn!("Another instance is already running; asking it to show the overlay.");
+        unsafe {
+            let _ = PostMessageW(existing, WM_SHOW_OVERLAY, WPARAM(0), LPARAM(0));
+        }
         return Ok(());
     }
 
     unsafe {
         CoInitializeEx(None, COINIT_APARTMENTTHREADED).ok()?;
 
-        let class_name = windows::core::w!("DesktopOverlayWndClass");
+        let class_name = OVERLAY_WND_CLASS;
         let hinst = GetModuleHandleW(None).unwrap();
         let wc = WNDCLASSW {
+            style: CS_DBLCLKS,
             lpfnWndProc: Some(wndproc),
             hInstance: hinst.into(),
             hCursor: LoadCursorW(None, IDC_ARROW).unwrap_or_default(),
@@ -621,10 +2651,16 @@ pub fn main() -> Result<()> {
 
         let hwnd = CreateWindowExW(
             WINDOW_EX_STYLE(
-                (WS_EX_TOOLWINDOW | WS_EX_LAYERED | WS_EX_TOPMOST | WS_EX_NOACTIVATE).0,
+                // Click-through until `overlay_interactive` clears WS_EX_TRANSPARENT.
+                (WS_EX_TOOLWINDOW
+                    | WS_EX_LAYERED
+                    | WS_EX_TOPMOST
+                    | WS_EX_NOACTIVATE
+                    | WS_EX_TRANSPARENT)
+                    .0,
             ),
             class_name,
-            windows::core::w!(""),
+            &instance_title(),
             WS_POPUP,
             0,
             0,
@@ -634,10 +2670,17 @@ pub fn main() -> Result<()> {
             None,
             hinst,
             None,
-        )?;
+        )
+        // WM_CREATE has already shown the reason in a message box.
+        .context("create overlay window")?;
         // Show first, then pin across desktops to avoid early 'WindowNotFound' logs in some shells
         let _ = ShowWindow(hwnd, SW_SHOW);
-        let _ = winvd::pin_window(hwnd);
+        let per_desktop = APP.with(|slot| {
+            slot.borrow()
+                .as_ref()
+                .is_some_and(|app| app.cfg.per_desktop_window)
+        });
+        apply_desktop_placement(hwnd, per_desktop);
 
         let mut msg = MSG::default();
         while GetMessageW(&mut msg, HWND(std::ptr::null_mut()), 0, 0).into() {
@@ -673,14 +2716,31 @@ mod tests {
                 cfg,
                 cfg_paths: paths,
                 overlay,
+                mirrors: Vec::new(),
+                mirror_handles: Vec::new(),
                 current_guid: "default".into(),
                 visible: true,
                 tray,
                 taskbar_created_msg: 0,
                 vd_thread: None,
+                vd_supported: true,
                 hide_for_accessibility: false,
                 hide_for_fullscreen: false,
-                anchor_index: 1,
+                hide_for_app: false,
+                power_notify: None,
+                display_off: false,
+                remote_disconnected: false,
+                panic_hidden: false,
+                shown_by_switch: false,
+                manual_override: false,
+                fade_anim: None,
+                usage: Usage::default(),
+                contrast: HashMap::new(),
+                switch_pending: false,
+                peek_until: None,
+                vd_restarts: 0,
+                vd_retry_at: None,
+                peer_slot: 0,
             };
             *slot.borrow_mut() = Some(app);
         });
@@ -719,14 +2779,31 @@ mod tests {
                             cfg,
                             cfg_paths: paths,
                             overlay,
+                            mirrors: Vec::new(),
+                            mirror_handles: Vec::new(),
                             current_guid: "default".into(),
                             visible: true,
                             tray,
                             taskbar_created_msg: 0,
                             vd_thread: None,
+                            vd_supported: true,
                             hide_for_accessibility: false,
                             hide_for_fullscreen: false,
-                            anchor_index: 1,
+                            hide_for_app: false,
+                            power_notify: None,
+                            display_off: false,
+                            remote_disconnected: false,
+                            panic_hidden: false,
+                            shown_by_switch: false,
+                            manual_override: false,
+                            fade_anim: None,
+                            usage: Usage::default(),
+                            contrast: HashMap::new(),
+                            switch_pending: false,
+                            peek_until: None,
+                            vd_restarts: 0,
+                            vd_retry_at: None,
+                            peer_slot: 0,
                         };
                         *slot.borrow_mut() = Some(app);
                     });
@@ -0,0 +1,64 @@
+use mddskmgr::config::{
+    Config, DesktopLabel, backup_config, paths_in, reset_to_defaults, save_atomic,
+};
+use std::fs;
+
+#[test]
+fn backs_up_and_keeps_only_the_newest() {
+    let td = tempfile::tempdir().expect("tmpdir");
+    let cfg_file = td.path().join("labels.json");
+    fs::write(&cfg_file, r#"{ "desktops": {}, }"#).unwrap();
+
+    let first = backup_config(&cfg_file, 5).expect("backup");
+    assert_eq!(
+        fs::read_to_string(&first).unwrap(),
+        r#"{ "desktops": {}, }"#
+    );
+    assert!(
+        first
+            .file_name()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .starts_with("labels.json.bak-")
+    );
+
+    let mut made = vec![first];
+    for _ in 0..6 {
+        made.push(backup_config(&cfg_file, 5).expect("backup"));
+    }
+    let mut left: Vec<_> = fs::read_dir(td.path())
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .filter(|p| p != &cfg_file)
+        .collect();
+    left.sort();
+    assert_eq!(left.len(), 5);
+    // The two oldest went; the original file is untouched.
+    assert!(!made[0].exists() && !made[1].exists());
+    assert!(made[2..].iter().all(|p| p.exists()));
+    assert!(cfg_file.exists());
+}
+
+#[test]
+fn reset_backs_up_then_writes_defaults() {
+    let td = tempfile::tempdir().expect("tmpdir");
+    let paths = paths_in(td.path());
+    // No file yet: nothing to back up.
+    assert_eq!(reset_to_defaults(&paths).unwrap(), None);
+
+    let mut cfg = Config::default();
+    cfg.desktops.insert(
+        "g".into(),
+        DesktopLabel {
+            title: "Work".into(),
+            ..Default::default()
+        },
+    );
+    save_atomic(&cfg, &paths).unwrap();
+    let bak = reset_to_defaults(&paths).unwrap().expect("backup");
+    assert!(fs::read_to_string(bak).unwrap().contains("Work"));
+    let reset: Config =
+        serde_json::from_str(&fs::read_to_string(&paths.cfg_file).unwrap()).unwrap();
+    assert!(reset.desktops.is_empty());
+}
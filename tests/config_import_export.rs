@@ -0,0 +1,47 @@
+use mddskmgr::config::{
+    Config, DesktopLabel, ImportMode, export_config, import_desktops, read_config_file,
+};
+use std::fs;
+
+fn label(title: &str) -> DesktopLabel {
+    DesktopLabel {
+        title: title.into(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn export_then_import_roundtrips_labels() {
+    let td = tempfile::tempdir().expect("tmpdir");
+    let path = td.path().join("moved.json");
+    let mut src = Config::default();
+    src.desktops.insert("a".into(), label("Build"));
+    src.desktops.insert("b".into(), label("Mail"));
+    export_config(&src, &path).expect("export");
+
+    let imported = read_config_file(&path).expect("valid file");
+    let mut dst = Config::default();
+    dst.desktops.insert("a".into(), label("Old"));
+    dst.desktops.insert("c".into(), label("Local"));
+    let mut merged = dst.clone();
+    import_desktops(&mut merged, imported.clone(), ImportMode::Merge);
+    assert_eq!(merged.desktops["a"].title, "Build");
+    assert_eq!(merged.desktops["c"].title, "Local");
+    assert_eq!(merged.desktops.len(), 3);
+
+    import_desktops(&mut dst, imported, ImportMode::Replace);
+    let mut keys: Vec<_> = dst.desktops.keys().cloned().collect();
+    keys.sort();
+    assert_eq!(keys, ["a", "b"]);
+}
+
+#[test]
+fn invalid_files_are_rejected() {
+    let td = tempfile::tempdir().expect("tmpdir");
+    let path = td.path().join("broken.json");
+    fs::write(&path, r#"{ "desktops": {}, }"#).unwrap();
+    assert!(read_config_file(&path).is_err());
+    fs::write(&path, r#"{ "title": "not a config" }"#).unwrap();
+    assert!(read_config_file(&path).is_err());
+    assert!(read_config_file(&td.path().join("missing.json")).is_err());
+}
@@ -0,0 +1,108 @@
+use mddskmgr::config::{
+    Config, DesktopLabel, label_entry, label_key, rekey_desktops, remove_index_label,
+};
+
+fn live() -> Vec<String> {
+    vec!["{A}".into(), "{B}".into(), "{C}".into()]
+}
+
+fn titled(title: &str) -> DesktopLabel {
+    DesktopLabel {
+        title: title.into(),
+        ..Default::default()
+    }
+}
+
+fn titles(cfg: &Config) -> Vec<(String, String)> {
+    let mut out: Vec<_> = cfg
+        .desktops
+        .iter()
+        .map(|(k, l)| (k.clone(), l.title.clone()))
+        .collect();
+    out.sort();
+    out
+}
+
+#[test]
+fn label_key_follows_the_mode() {
+    let mut cfg = Config::default();
+    assert_eq!(label_key(&cfg, "{B}", &live()), "{B}");
+    cfg.appearance.key_by_index = true;
+    assert_eq!(label_key(&cfg, "{B}", &live()), "1");
+    // A desktop missing from the list (or no list at all) keeps its GUID.
+    assert_eq!(label_key(&cfg, "{Z}", &live()), "{Z}");
+    assert_eq!(label_key(&cfg, "default", &[]), "default");
+}
+
+#[test]
+fn switching_modes_converts_keys_both_ways() {
+    let mut cfg = Config::default();
+    cfg.desktops.insert("{A}".into(), titled("Mail"));
+    cfg.desktops.insert("{C}".into(), titled("Games"));
+    cfg.desktops.insert("{gone}".into(), titled("Old"));
+    // GUID mode already: nothing to do.
+    assert!(!rekey_desktops(&mut cfg, &live()));
+
+    cfg.appearance.key_by_index = true;
+    assert!(rekey_desktops(&mut cfg, &live()));
+    assert_eq!(
+        titles(&cfg),
+        [("0", "Mail"), ("2", "Games"), ("{gone}", "Old")].map(|(k, t)| (k.into(), t.into()))
+    );
+    assert!(!rekey_desktops(&mut cfg, &live()), "second run is a no-op");
+
+    cfg.appearance.key_by_index = false;
+    assert!(rekey_desktops(&mut cfg, &live()));
+    assert_eq!(
+        titles(&cfg),
+        [("{A}", "Mail"), ("{C}", "Games"), ("{gone}", "Old")].map(|(k, t)| (k.into(), t.into()))
+    );
+}
+
+#[test]
+fn rekeying_never_overwrites_an_existing_label() {
+    let mut cfg = Config::default();
+    cfg.appearance.key_by_index = true;
+    cfg.desktops.insert("0".into(), titled("By index"));
+    cfg.desktops.insert("{A}".into(), titled("By GUID"));
+    assert!(!rekey_desktops(&mut cfg, &live()));
+    assert_eq!(cfg.desktops["0"].title, "By index");
+    assert_eq!(cfg.desktops["{A}"].title, "By GUID");
+}
+
+#[test]
+fn removing_a_desktop_shifts_later_index_labels_down() {
+    let mut cfg = Config::default();
+    cfg.appearance.key_by_index = true;
+    for (k, t) in [
+        ("0", "Mail"),
+        ("1", "Build"),
+        ("2", "Games"),
+        ("4", "Music"),
+    ] {
+        cfg.desktops.insert(k.into(), titled(t));
+    }
+    assert!(remove_index_label(&mut cfg, 1));
+    assert_eq!(
+        titles(&cfg),
+        [("0", "Mail"), ("1", "Games"), ("3", "Music")].map(|(k, t)| (k.into(), t.into()))
+    );
+    // Removing an unlabeled desktop past the end changes nothing.
+    assert!(!remove_index_label(&mut cfg, 7));
+}
+
+#[test]
+fn label_entry_writes_under_the_mode_key() {
+    let mut cfg = Config::default();
+    cfg.appearance.key_by_index = true;
+    label_entry(&mut cfg, "{B}", &live()).title = "Build".into();
+    assert_eq!(titles(&cfg), [("1".to_string(), "Build".to_string())]);
+    // The entry is found again, not duplicated.
+    label_entry(&mut cfg, "{B}", &live()).description = "CI".into();
+    assert_eq!(cfg.desktops.len(), 1);
+    assert_eq!(cfg.desktops["1"].description, "CI");
+
+    cfg.appearance.key_by_index = false;
+    label_entry(&mut cfg, "{B}", &live()).title = "By GUID".into();
+    assert_eq!(cfg.desktops["{B}"].title, "By GUID");
+}
@@ -0,0 +1,72 @@
+use mddskmgr::config::{Config, LogFormat, LogLevel, Logging, prune_log_files, read_logging};
+use std::fs;
+
+#[test]
+fn logging_section_defaults_and_lenient_level() {
+    let td = tempfile::tempdir().expect("tmpdir");
+    let cfg_file = td.path().join("labels.json");
+    // Missing file and missing section both give the defaults.
+    assert_eq!(read_logging(&cfg_file), Logging::default());
+    fs::write(&cfg_file, r#"{ "desktops": {} }"#).unwrap();
+    assert_eq!(read_logging(&cfg_file).level, LogLevel::Info);
+
+    fs::write(
+        &cfg_file,
+        r#"{ "logging": { "level": "debug", "max_files": 3 } }"#,
+    )
+    .unwrap();
+    let logging = read_logging(&cfg_file);
+    assert_eq!(logging.level, LogLevel::Debug);
+    assert_eq!(logging.level.as_filter(), "debug");
+    assert_eq!(logging.max_files, 3);
+
+    fs::write(&cfg_file, r#"{ "logging": { "level": "loud" } }"#).unwrap();
+    assert_eq!(read_logging(&cfg_file), Logging::default());
+}
+
+#[test]
+fn logging_format_defaults_to_text_and_tolerates_typos() {
+    let td = tempfile::tempdir().expect("tmpdir");
+    let cfg_file = td.path().join("labels.json");
+    assert_eq!(read_logging(&cfg_file).format, LogFormat::Text);
+    fs::write(&cfg_file, r#"{ "logging": { "format": "json" } }"#).unwrap();
+    assert_eq!(read_logging(&cfg_file).format, LogFormat::Json);
+    // A bad format doesn't take the level (or the rest of the config) down with it.
+    let raw = r#"{ "logging": { "level": "debug", "format": "xml" } }"#;
+    fs::write(&cfg_file, raw).unwrap();
+    let logging = read_logging(&cfg_file);
+    assert_eq!(
+        (logging.level, logging.format),
+        (LogLevel::Debug, LogFormat::Text)
+    );
+    let mut full = serde_json::to_value(Config::default()).unwrap();
+    full["logging"]["format"] = "xml".into();
+    let cfg: Config = serde_json::from_value(full).unwrap();
+    assert_eq!(cfg.logging.format, LogFormat::Text);
+}
+
+#[test]
+fn prunes_oldest_rotated_logs() {
+    let td = tempfile::tempdir().expect("tmpdir");
+    for day in ["2024-01-01", "2024-01-02", "2024-01-03", "2024-01-04"] {
+        fs::write(td.path().join(format!("mddsklbl.log.{day}")), "x").unwrap();
+    }
+    fs::write(td.path().join("other.txt"), "keep").unwrap();
+
+    assert_eq!(prune_log_files(td.path(), 2).unwrap(), 2);
+    let mut left: Vec<String> = fs::read_dir(td.path())
+        .unwrap()
+        .map(|e| e.unwrap().file_name().into_string().unwrap())
+        .collect();
+    left.sort();
+    assert_eq!(
+        left,
+        [
+            "mddsklbl.log.2024-01-03",
+            "mddsklbl.log.2024-01-04",
+            "other.txt"
+        ]
+    );
+    // 0 keeps everything.
+    assert_eq!(prune_log_files(td.path(), 0).unwrap(), 0);
+}
@@ -0,0 +1,72 @@
+use mddskmgr::config::{Config, DesktopLabel, LabelEdit, apply_label_edits, normalize_label};
+
+fn row(guid: &str, title: &str, description: &str) -> LabelEdit {
+    LabelEdit {
+        guid: guid.into(),
+        title: title.into(),
+        description: description.into(),
+        remove: false,
+    }
+}
+
+fn labeled() -> Config {
+    let mut cfg = Config::default();
+    cfg.desktops.insert(
+        "a".into(),
+        DesktopLabel {
+            title: "Build".into(),
+            description: "CI".into(),
+            color: Some("#8B0000".into()),
+            hidden: false,
+            font_size_dip: None,
+        },
+    );
+    cfg
+}
+
+#[test]
+fn unchanged_rows_are_not_saved() {
+    let mut cfg = labeled();
+    // The current desktop is listed even without a label; leaving it blank adds nothing.
+    assert!(!apply_label_edits(
+        &mut cfg,
+        &[row("a", "Build", "CI"), row("b", "", "")]
+    ));
+    assert_eq!(cfg.desktops.len(), 1);
+}
+
+#[test]
+fn edits_update_and_add_entries() {
+    let mut cfg = labeled();
+    assert!(apply_label_edits(
+        &mut cfg,
+        &[row("a", "Release", "CI"), row("b", "Mail", "")]
+    ));
+    let a = &cfg.desktops["a"];
+    assert_eq!(a.title, "Release");
+    // Fields the window doesn't show survive the edit.
+    assert_eq!(a.color.as_deref(), Some("#8B0000"));
+    assert_eq!(cfg.desktops["b"].title, "Mail");
+}
+
+#[test]
+fn removed_rows_delete_the_entry() {
+    let mut cfg = labeled();
+    let mut gone = row("a", "Build", "CI");
+    gone.remove = true;
+    assert!(apply_label_edits(&mut cfg, &[gone.clone()]));
+    assert!(cfg.desktops.is_empty());
+    // Removing a desktop that has no entry is not a change.
+    assert!(!apply_label_edits(&mut cfg, &[gone]));
+}
+
+#[test]
+fn normalize_label_trims_and_collapses_whitespace() {
+    assert_eq!(normalize_label("Work "), "Work");
+    assert_eq!(normalize_label("  Deep   work  "), "Deep work");
+    assert_eq!(normalize_label("Mail\tand\t\tchat"), "Mail and chat");
+    assert_eq!(normalize_label("Line\none\r\n"), "Line one");
+    // Nothing left: stored empty so the fallback title shows.
+    assert_eq!(normalize_label(" \t\n "), "");
+    assert_eq!(normalize_label(""), "");
+}
@@ -0,0 +1,82 @@
+use mddskmgr::config::{
+    CURRENT_VERSION, Config, Paths, is_from_newer_version, migrate, save_atomic,
+};
+
+#[test]
+fn version_zero_snap_key_moves_to_l() {
+    let mut v = serde_json::to_value(Config::default()).unwrap();
+    v["version"] = serde_json::json!(0);
+    v["hotkeys"]["snap_position"]["key"] = serde_json::json!("s");
+    let mut cfg: Config = serde_json::from_value(v).unwrap();
+
+    assert!(migrate(&mut cfg));
+    assert_eq!(cfg.hotkeys.snap_position.key, "L");
+    assert_eq!(cfg.version, Some(CURRENT_VERSION));
+    // Already current: nothing to do, so no save is needed.
+    assert!(!migrate(&mut cfg));
+}
+
+#[test]
+fn missing_version_counts_as_zero_and_keeps_custom_keys() {
+    let mut cfg = Config {
+        version: None,
+        ..Default::default()
+    };
+    cfg.hotkeys.snap_position.key = "P".into();
+    assert!(migrate(&mut cfg));
+    assert_eq!(cfg.hotkeys.snap_position.key, "P");
+    assert_eq!(cfg.version, Some(CURRENT_VERSION));
+}
+
+#[test]
+fn newer_versions_are_left_alone() {
+    let mut cfg = Config {
+        version: Some(CURRENT_VERSION + 5),
+        ..Default::default()
+    };
+    cfg.hotkeys.snap_position.key = "S".into();
+    assert!(!migrate(&mut cfg));
+    assert_eq!(cfg.hotkeys.snap_position.key, "S");
+}
+
+#[test]
+fn newer_configs_are_flagged_and_never_saved() {
+    let dir = tempfile::tempdir().unwrap();
+    let paths = Paths {
+        cfg_dir: dir.path().to_path_buf(),
+        cfg_file: dir.path().join("labels.json"),
+        log_dir: dir.path().join("logs"),
+    };
+    let mut cfg = Config {
+        version: Some(CURRENT_VERSION + 1),
+        ..Default::default()
+    };
+    assert!(is_from_newer_version(&cfg));
+    let err = save_atomic(&cfg, &paths).unwrap_err();
+    assert!(err.to_string().contains("newer version"), "{err:#}");
+    assert!(!paths.cfg_file.exists());
+    // Current, older and unversioned configs save as usual.
+    for version in [Some(CURRENT_VERSION), Some(0), None] {
+        cfg.version = version;
+        assert!(!is_from_newer_version(&cfg));
+        save_atomic(&cfg, &paths).unwrap();
+    }
+}
+
+#[test]
+fn version_one_drops_the_colors_older_builds_always_wrote() {
+    let mut v = serde_json::to_value(Config::default()).unwrap();
+    v["version"] = serde_json::json!(1);
+    v["appearance"]["text_color"] = serde_json::json!("#ffffff");
+    v["appearance"]["background_color"] = serde_json::json!("#33669980");
+    let mut cfg: Config = serde_json::from_value(v).unwrap();
+
+    assert!(migrate(&mut cfg));
+    assert_eq!(cfg.appearance.text_color, None);
+    assert_eq!(
+        cfg.appearance.background_color.as_deref(),
+        Some("#33669980")
+    );
+    assert_eq!(cfg.version, Some(2));
+    assert_eq!(CURRENT_VERSION, 2);
+}
@@ -0,0 +1,28 @@
+use mddskmgr::config::{paths_in, with_config_file};
+use std::fs;
+
+#[test]
+fn override_moves_config_but_keeps_logs() {
+    let td = tempfile::tempdir().expect("tmpdir");
+    let defaults = paths_in(&td.path().join("default"));
+    let custom = td.path().join("portable").join("my-labels.json");
+
+    let paths = with_config_file(defaults.clone(), &custom);
+    assert_eq!(paths.cfg_file, custom);
+    assert_eq!(paths.cfg_dir, td.path().join("portable"));
+    assert!(paths.cfg_dir.is_dir());
+    assert_eq!(paths.log_dir, defaults.log_dir);
+}
+
+#[test]
+fn uncreatable_override_falls_back_to_default() {
+    let td = tempfile::tempdir().expect("tmpdir");
+    let defaults = paths_in(&td.path().join("default"));
+    // A regular file where the override's parent directory should be.
+    let blocker = td.path().join("not-a-dir");
+    fs::write(&blocker, "x").unwrap();
+
+    let paths = with_config_file(defaults.clone(), &blocker.join("labels.json"));
+    assert_eq!(paths.cfg_file, defaults.cfg_file);
+    assert_eq!(paths.cfg_dir, defaults.cfg_dir);
+}
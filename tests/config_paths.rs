@@ -0,0 +1,42 @@
+use mddskmgr::config::paths_in;
+use std::path::Path;
+
+#[test]
+fn fallback_layout_keeps_config_and_logs_together() {
+    let paths = paths_in(Path::new("portable"));
+    assert_eq!(paths.cfg_dir, Path::new("portable"));
+    assert_eq!(paths.cfg_file, Path::new("portable").join("labels.json"));
+    assert_eq!(paths.log_dir, Path::new("portable").join("logs"));
+}
+
+#[test]
+fn fallback_skips_folders_that_cannot_be_created() {
+    use mddskmgr::config::first_usable_dir;
+    let td = tempfile::tempdir().expect("tmpdir");
+    // A regular file where a parent folder should be makes the first candidate unusable.
+    let blocker = td.path().join("blocker");
+    std::fs::write(&blocker, "").unwrap();
+    let unusable = blocker.join("mddskmgr");
+    let usable = td.path().join("temp").join("mddskmgr");
+    assert_eq!(
+        first_usable_dir([unusable.clone(), usable.clone()]),
+        Some(usable.clone())
+    );
+    assert!(usable.is_dir());
+    assert_eq!(first_usable_dir([unusable]), None);
+}
+
+#[test]
+fn portable_mode_uses_the_exe_folder_via_flag_or_marker() {
+    use mddskmgr::config::{PORTABLE_MARKER, portable_base};
+    let td = tempfile::tempdir().expect("tmpdir");
+    assert_eq!(portable_base(td.path(), false), None);
+    assert_eq!(
+        portable_base(td.path(), true),
+        Some(td.path().to_path_buf())
+    );
+    std::fs::write(td.path().join(PORTABLE_MARKER), "").unwrap();
+    let base = portable_base(td.path(), false).expect("marker enables portable mode");
+    assert_eq!(paths_in(&base).cfg_file, td.path().join("labels.json"));
+    assert_eq!(paths_in(&base).log_dir, td.path().join("logs"));
+}
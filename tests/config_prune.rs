@@ -0,0 +1,35 @@
+use mddskmgr::config::{Config, DesktopLabel, prune_desktops};
+
+fn label(title: &str) -> DesktopLabel {
+    DesktopLabel {
+        title: title.into(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn prunes_only_dead_guids() {
+    let mut cfg = Config::default();
+    cfg.desktops.insert("live-1".into(), label("A"));
+    cfg.desktops.insert("dead".into(), label("B"));
+    cfg.desktops.insert("live-2".into(), label("C"));
+    let live = vec![
+        "live-1".to_string(),
+        "live-2".to_string(),
+        "new".to_string(),
+    ];
+    assert!(prune_desktops(&mut cfg, &live));
+    let mut keys: Vec<_> = cfg.desktops.keys().cloned().collect();
+    keys.sort();
+    assert_eq!(keys, ["live-1", "live-2"]);
+    // Nothing left to remove the second time.
+    assert!(!prune_desktops(&mut cfg, &live));
+}
+
+#[test]
+fn empty_live_list_keeps_everything() {
+    let mut cfg = Config::default();
+    cfg.desktops.insert("a".into(), label("A"));
+    assert!(!prune_desktops(&mut cfg, &[]));
+    assert_eq!(cfg.desktops.len(), 1);
+}
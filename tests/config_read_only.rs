@@ -0,0 +1,18 @@
+use mddskmgr::config::{Config, paths_in, save_atomic};
+
+#[test]
+fn save_refuses_read_only_config() {
+    let dir = tempfile::tempdir().unwrap();
+    let paths = paths_in(dir.path());
+    let cfg = Config {
+        read_only: true,
+        ..Default::default()
+    };
+    let err = save_atomic(&cfg, &paths).unwrap_err();
+    assert!(err.to_string().contains("read-only"));
+    assert!(!paths.cfg_file.exists());
+
+    let cfg = Config::default();
+    save_atomic(&cfg, &paths).unwrap();
+    assert!(paths.cfg_file.exists());
+}
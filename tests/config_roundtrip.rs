@@ -10,6 +10,9 @@ fn save_and_load_roundtrip() {
         DesktopLabel {
             title: "Work".into(),
             description: "Tickets".into(),
+            color: None,
+            hidden: false,
+            font_size_dip: None,
         },
     );
     cfg.hotkeys = Hotkeys {
@@ -37,12 +40,25 @@ fn save_and_load_roundtrip() {
             shift: false,
             key: "L".into(),
         },
+        toggle_autorun: None,
+        edit_both: KeyChord {
+            ctrl: true,
+            alt: true,
+            shift: false,
+            key: "E".into(),
+        },
+        toggle_desktop_hidden: None,
+        new_desktop: None,
+        remove_desktop: None,
+        panic_hide: None,
+        peek: None,
     };
     cfg.appearance = Appearance {
         font_family: "Segoe UI".into(),
         font_size_dip: 16,
         margin_px: 8,
         hide_on_fullscreen: false,
+        ..Default::default()
     };
 
     let td = tempfile::tempdir().expect("tmpdir");
@@ -62,3 +78,246 @@ fn save_and_load_roundtrip() {
     assert_eq!(parsed.desktops.get("guid-1").unwrap().title, "Work");
     assert_eq!(parsed.hotkeys.toggle_overlay.key, "O");
 }
+
+#[test]
+fn invalid_anchor_falls_back_to_top_center() {
+    use mddskmgr::config::{Config, OverlayAnchor};
+    let mut v = serde_json::to_value(Config::default()).unwrap();
+    v["appearance"]["anchor"] = serde_json::json!("middle_of_nowhere");
+    let cfg: Config = serde_json::from_value(v.clone()).unwrap();
+    assert_eq!(cfg.appearance.anchor, OverlayAnchor::TopCenter);
+    v["appearance"]["anchor"] = serde_json::json!("bottom_center");
+    let cfg: Config = serde_json::from_value(v).unwrap();
+    assert_eq!(cfg.appearance.anchor, OverlayAnchor::BottomCenter);
+}
+
+#[test]
+fn opacity_accepts_bytes_and_fractions() {
+    use mddskmgr::config::Config;
+    let parse = |v: serde_json::Value| {
+        let mut j = serde_json::to_value(Config::default()).unwrap();
+        j["appearance"]["opacity"] = v;
+        serde_json::from_value::<Config>(j)
+            .unwrap()
+            .appearance
+            .opacity
+    };
+    assert_eq!(parse(serde_json::json!(180)), 180);
+    assert_eq!(parse(serde_json::json!(0.5)), 128);
+    assert_eq!(parse(serde_json::json!(1.0)), 255);
+    assert_eq!(parse(serde_json::json!(999)), 255);
+    assert_eq!(parse(serde_json::json!(-3)), 0);
+    assert_eq!(parse(serde_json::json!("half")), 255);
+    assert_eq!(Config::default().appearance.opacity, 255);
+}
+
+#[test]
+fn font_weight_accepts_names_and_numbers() {
+    use mddskmgr::config::Config;
+    let parse = |v: serde_json::Value| {
+        let mut j = serde_json::to_value(Config::default()).unwrap();
+        j["appearance"]["font_weight"] = v;
+        serde_json::from_value::<Config>(j)
+            .unwrap()
+            .appearance
+            .font_weight
+    };
+    assert_eq!(parse(serde_json::json!("bold")), 700);
+    assert_eq!(parse(serde_json::json!(600)), 600);
+    assert_eq!(parse(serde_json::json!("300")), 300);
+    assert_eq!(parse(serde_json::json!("chunky")), 400);
+    assert_eq!(parse(serde_json::json!(-5)), 400);
+    assert_eq!(Config::default().appearance.font_weight, 400);
+    assert!(!Config::default().appearance.font_italic);
+}
+
+#[test]
+fn fallback_fonts_default_and_override() {
+    use mddskmgr::config::Config;
+    let defaults = Config::default().appearance.fallback_fonts;
+    assert_eq!(defaults, vec!["Segoe UI Emoji", "Segoe UI Symbol"]);
+    // Older files without the key get the defaults; an explicit list (even empty) is kept.
+    let mut j = serde_json::to_value(Config::default()).unwrap();
+    j["appearance"]
+        .as_object_mut()
+        .unwrap()
+        .remove("fallback_fonts");
+    let cfg: Config = serde_json::from_value(j.clone()).unwrap();
+    assert_eq!(cfg.appearance.fallback_fonts, defaults);
+    j["appearance"]["fallback_fonts"] = serde_json::json!(["Noto Color Emoji", "Yu Gothic UI"]);
+    let cfg: Config = serde_json::from_value(j.clone()).unwrap();
+    assert_eq!(
+        cfg.appearance.fallback_fonts,
+        vec!["Noto Color Emoji", "Yu Gothic UI"]
+    );
+    j["appearance"]["fallback_fonts"] = serde_json::json!([]);
+    let cfg: Config = serde_json::from_value(j).unwrap();
+    assert!(cfg.appearance.fallback_fonts.is_empty());
+}
+
+#[test]
+fn label_limits_default_and_override() {
+    let a = Appearance::default();
+    assert_eq!(a.label_limits(), (64, 64));
+    let a = Appearance {
+        max_label_chars: 40,
+        max_description_chars: Some(120),
+        ..Default::default()
+    };
+    assert_eq!(a.label_limits(), (40, 120));
+}
+
+#[test]
+fn save_atomic_round_trips_and_leaves_no_temp_file() {
+    let td = tempfile::tempdir().expect("tmpdir");
+    let cfg_dir = td.path().join("cfg");
+    let paths = Paths {
+        cfg_file: cfg_dir.join("labels.json"),
+        cfg_dir: cfg_dir.clone(),
+        log_dir: td.path().join("log"),
+    };
+    let mut cfg = Config::default();
+    cfg.desktops.insert(
+        "guid-1".into(),
+        DesktopLabel {
+            title: "Media 🎵".into(),
+            description: "Player\nQueue".into(),
+            color: Some("#203040".into()),
+            hidden: true,
+            font_size_dip: Some(12),
+        },
+    );
+    cfg.appearance.fallback_fonts = vec!["Yu Gothic UI".into()];
+    // The folder is created on demand, and a second save replaces the first.
+    save_atomic(&Config::default(), &paths).expect("first save");
+    save_atomic(&cfg, &paths).expect("second save");
+    let back: Config = serde_json::from_str(&fs::read_to_string(&paths.cfg_file).unwrap()).unwrap();
+    assert_eq!(
+        serde_json::to_value(&back).unwrap(),
+        serde_json::to_value(&cfg).unwrap()
+    );
+    let names: Vec<_> = fs::read_dir(&cfg_dir)
+        .unwrap()
+        .map(|e| e.unwrap().file_name().into_string().unwrap())
+        .collect();
+    assert_eq!(names, vec!["labels.json".to_string()]);
+}
+
+#[test]
+fn first_run_is_reported_once() {
+    use mddskmgr::config::{LoadOutcome, load_at, paths_in};
+    let td = tempfile::tempdir().expect("tmpdir");
+    let paths = paths_in(td.path());
+    fs::create_dir_all(&paths.cfg_dir).unwrap();
+    let missing_legacy = td.path().join("old").join("labels.json");
+
+    let (cfg, outcome) = load_at(&paths, Some(&missing_legacy)).unwrap();
+    assert_eq!(outcome, LoadOutcome::FirstRun);
+    assert!(cfg.desktops.is_empty());
+    // The defaults were written, so the next start is an ordinary one.
+    assert!(paths.cfg_file.is_file());
+    let (_, outcome) = load_at(&paths, Some(&missing_legacy)).unwrap();
+    assert_eq!(outcome, LoadOutcome::Existing);
+}
+
+#[test]
+fn legacy_and_broken_configs_are_not_first_runs() {
+    use mddskmgr::config::{LoadOutcome, load_at, paths_in};
+    let td = tempfile::tempdir().expect("tmpdir");
+    let paths = paths_in(&td.path().join("new"));
+    fs::create_dir_all(&paths.cfg_dir).unwrap();
+    let legacy = td.path().join("old.json");
+    let mut old = Config::default();
+    old.desktops.insert(
+        "g".into(),
+        DesktopLabel {
+            title: "Old".into(),
+            ..Default::default()
+        },
+    );
+    fs::write(&legacy, serde_json::to_string(&old).unwrap()).unwrap();
+    let (cfg, outcome) = load_at(&paths, Some(&legacy)).unwrap();
+    assert_eq!(outcome, LoadOutcome::Migrated);
+    assert_eq!(cfg.desktops["g"].title, "Old");
+
+    fs::write(&paths.cfg_file, "{ not json").unwrap();
+    let (_, outcome) = load_at(&paths, None).unwrap();
+    assert_eq!(outcome, LoadOutcome::Recovered);
+}
+
+#[test]
+fn unknown_top_level_keys_survive_load_migrate_and_save() {
+    use mddskmgr::config::{CURRENT_VERSION, load_at, paths_in};
+    let td = tempfile::tempdir().expect("tmpdir");
+    let paths = paths_in(td.path());
+    fs::create_dir_all(&paths.cfg_dir).unwrap();
+    // A hand-edited, pre-versioning file: it still needs migrating.
+    let mut raw = serde_json::to_value(Config::default()).unwrap();
+    let obj = raw.as_object_mut().unwrap();
+    obj.remove("version");
+    obj.insert("_comment".into(), "work laptop, keep titles short".into());
+    obj.insert("future_setting".into(), serde_json::json!({ "level": 3 }));
+    fs::write(&paths.cfg_file, serde_json::to_string_pretty(&raw).unwrap()).unwrap();
+
+    let (cfg, _) = load_at(&paths, None).unwrap();
+    assert_eq!(cfg.version, Some(CURRENT_VERSION));
+    assert_eq!(cfg.extra.len(), 2);
+    assert!(!cfg.extra.contains_key("version"));
+    save_atomic(&cfg, &paths).unwrap();
+
+    let saved: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&paths.cfg_file).unwrap()).unwrap();
+    assert_eq!(saved["_comment"], "work laptop, keep titles short");
+    assert_eq!(saved["future_setting"]["level"], 3);
+    assert_eq!(saved["version"], CURRENT_VERSION);
+    // Reloading and saving again writes the unknown keys in the same order.
+    let first = fs::read_to_string(&paths.cfg_file).unwrap();
+    for _ in 0..3 {
+        let (cfg, _) = load_at(&paths, None).unwrap();
+        save_atomic(&cfg, &paths).unwrap();
+        assert_eq!(fs::read_to_string(&paths.cfg_file).unwrap(), first);
+    }
+}
+
+#[test]
+fn tray_icon_path_resolves_against_the_config_folder() {
+    use mddskmgr::config::tray_icon_file;
+    let dir = std::path::Path::new("cfgdir");
+    let mut cfg = Config::default();
+    assert_eq!(tray_icon_file(&cfg, dir), None);
+    cfg.appearance.tray_icon_path = Some("  ".into());
+    assert_eq!(tray_icon_file(&cfg, dir), None);
+    cfg.appearance.tray_icon_path = Some("icons/work.ico".into());
+    assert_eq!(tray_icon_file(&cfg, dir), Some(dir.join("icons/work.ico")));
+    let absolute = std::env::temp_dir().join("work.ico");
+    cfg.appearance.tray_icon_path = Some(absolute.display().to_string());
+    assert_eq!(tray_icon_file(&cfg, dir), Some(absolute));
+}
+
+#[test]
+fn unreadable_config_is_an_error_not_a_first_run() {
+    use mddskmgr::config::{load_at, paths_in, read_existing};
+    let td = tempfile::tempdir().expect("tmpdir");
+    let paths = paths_in(td.path());
+    // A directory where the file should be: reading fails with something other than NotFound.
+    fs::create_dir_all(&paths.cfg_file).unwrap();
+    assert!(load_at(&paths, None).is_err());
+    assert!(read_existing(&paths).is_err());
+    assert!(paths.cfg_file.is_dir(), "nothing was written over it");
+}
+
+#[test]
+fn read_existing_never_writes() {
+    use mddskmgr::config::{paths_in, read_existing};
+    let td = tempfile::tempdir().expect("tmpdir");
+    let paths = paths_in(td.path());
+    fs::create_dir_all(&paths.cfg_dir).unwrap();
+    // Missing: defaults, and no file appears.
+    assert!(read_existing(&paths).unwrap().desktops.is_empty());
+    assert!(!paths.cfg_file.exists());
+    // Broken: an error, and the file is neither replaced nor backed up.
+    fs::write(&paths.cfg_file, "{ not json").unwrap();
+    assert!(read_existing(&paths).is_err());
+    assert_eq!(fs::read_to_string(&paths.cfg_file).unwrap(), "{ not json");
+    assert_eq!(fs::read_dir(&paths.cfg_dir).unwrap().count(), 1);
+}
@@ -0,0 +1,49 @@
+use mddskmgr::config::{Config, is_config_event};
+use mddskmgr::utils::drain_burst;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
+
+#[test]
+fn matches_only_the_config_file_name() {
+    let cfg = Path::new("C:/cfg/labels.json");
+    let ev = |p: &str| vec![PathBuf::from(p)];
+    assert!(is_config_event(&ev("C:/cfg/labels.json"), cfg));
+    assert!(!is_config_event(&ev("C:/cfg/labels.json.tmp"), cfg));
+    assert!(!is_config_event(&ev("C:/cfg/other.json"), cfg));
+    // Renames report both ends; either matching counts.
+    let rename = vec![
+        PathBuf::from("C:/cfg/labels.json.tmp"),
+        PathBuf::from("C:/cfg/labels.json"),
+    ];
+    assert!(is_config_event(&rename, cfg));
+}
+
+#[test]
+fn drain_burst_swallows_queued_events() {
+    let (tx, rx) = mpsc::channel();
+    for _ in 0..5 {
+        tx.send(()).unwrap();
+    }
+    assert!(drain_burst(&rx, Duration::from_millis(20)));
+    assert!(rx.try_recv().is_err());
+    drop(tx);
+    assert!(!drain_burst(&rx, Duration::from_millis(20)));
+}
+
+#[test]
+fn label_only_reload_keeps_hotkeys_equal() {
+    let mut cfg = Config::default();
+    let json = serde_json::to_string(&cfg).unwrap();
+    // A reload of a save that only touched labels: no chord change, no re-registration.
+    cfg.desktops.insert("guid".into(), Default::default());
+    let reloaded: Config = serde_json::from_str(&json).unwrap();
+    assert_eq!(reloaded.hotkeys, cfg.hotkeys);
+    // Any chord difference counts, including an optional chord being set.
+    let mut changed = reloaded.clone();
+    changed.hotkeys.toggle_overlay.shift = !changed.hotkeys.toggle_overlay.shift;
+    assert_ne!(changed.hotkeys, cfg.hotkeys);
+    let mut changed = reloaded;
+    changed.hotkeys.toggle_autorun = Some(cfg.hotkeys.edit_title.clone());
+    assert_ne!(changed.hotkeys, cfg.hotkeys);
+}
@@ -0,0 +1,55 @@
+use mddskmgr::config::{Config, DesktopLabel, TrayBadge};
+use mddskmgr::core::{format_overlay_line, switched_desktop, tray_badge_for};
+use mddskmgr::vd_backend::MockVd;
+
+fn labeled_config() -> Config {
+    let mut cfg = Config::default();
+    cfg.desktops.insert(
+        "guid-a".into(),
+        DesktopLabel {
+            title: "Build".into(),
+            description: "CI".into(),
+            color: None,
+            hidden: false,
+            font_size_dip: None,
+        },
+    );
+    cfg
+}
+
+#[test]
+fn detects_switch_only_when_guid_changes() {
+    let mut vd = MockVd {
+        guid: "guid-a".into(),
+        ..Default::default()
+    };
+    assert_eq!(switched_desktop(&vd, "guid-a"), None);
+    vd.guid = "guid-b".into();
+    assert_eq!(switched_desktop(&vd, "guid-a"), Some("guid-b".into()));
+}
+
+#[test]
+fn formats_labeled_and_unlabeled_desktops() {
+    let cfg = labeled_config();
+    let vd = MockVd::default();
+    assert_eq!(format_overlay_line(&cfg, "guid-a", &vd), "Build : CI");
+    assert_eq!(format_overlay_line(&cfg, "guid-b", &vd), "Desktop : ");
+}
+
+#[test]
+fn tray_badge_follows_switches() {
+    let mut cfg = labeled_config();
+    cfg.tray_badge = TrayBadge::FirstLetter;
+    let mut vd = MockVd {
+        guid: "guid-a".into(),
+        index: Some(0),
+        count: 2,
+        ..Default::default()
+    };
+    assert_eq!(tray_badge_for(&cfg, "guid-a", &vd), Some("B".into()));
+    vd.guid = "guid-b".into();
+    vd.index = Some(1);
+    assert_eq!(tray_badge_for(&cfg, "guid-b", &vd), Some("2".into()));
+    cfg.tray_badge = TrayBadge::None;
+    assert_eq!(tray_badge_for(&cfg, "guid-b", &vd), None);
+}
@@ -40,9 +40,92 @@ fn detects_duplicate_hotkeys() {
             shift: false,
             key: "S".into(),
         },
+        toggle_autorun: None,
+        edit_both: KeyChord {
+            ctrl: true,
+            alt: true,
+            shift: false,
+            key: "E".into(),
+        },
+        toggle_desktop_hidden: None,
+        new_desktop: None,
+        remove_desktop: None,
+        panic_hide: None,
+        peek: None,
     };
     assert!(!has_duplicates(&hk));
     // Collide description with title
     hk.edit_description.key = "t".into();
     assert!(has_duplicates(&hk));
 }
+
+#[test]
+fn detects_duplicate_with_optional_autorun_chord() {
+    let mut hk = mddskmgr::config::Config::default().hotkeys;
+    assert!(!mddskmgr::hotkeys::has_duplicates(&hk));
+    hk.toggle_autorun = Some(KeyChord {
+        ctrl: true,
+        alt: true,
+        shift: false,
+        key: "o".into(),
+    });
+    assert!(mddskmgr::hotkeys::has_duplicates(&hk));
+    hk.toggle_autorun.as_mut().unwrap().key = "R".into();
+    assert!(!mddskmgr::hotkeys::has_duplicates(&hk));
+}
+
+#[test]
+fn edit_both_chord_is_checked_for_duplicates() {
+    let mut hk = mddskmgr::config::Config::default().hotkeys;
+    assert_eq!(hk.edit_both.key, "E");
+    hk.edit_both.key = "d".into();
+    assert!(mddskmgr::hotkeys::has_duplicates(&hk));
+}
+
+#[test]
+fn desktop_chords_are_checked_for_duplicates() {
+    let mut hk = mddskmgr::config::Config::default().hotkeys;
+    hk.new_desktop = Some(KeyChord {
+        ctrl: true,
+        alt: true,
+        shift: false,
+        key: "N".into(),
+    });
+    hk.remove_desktop = Some(KeyChord {
+        ctrl: true,
+        alt: true,
+        shift: true,
+        key: "N".into(),
+    });
+    assert!(!mddskmgr::hotkeys::has_duplicates(&hk));
+    hk.remove_desktop.as_mut().unwrap().shift = false;
+    assert!(mddskmgr::hotkeys::has_duplicates(&hk));
+}
+
+#[test]
+fn panic_hide_chord_is_checked_for_duplicates() {
+    let mut hk = mddskmgr::config::Config::default().hotkeys;
+    hk.panic_hide = Some(KeyChord {
+        ctrl: true,
+        alt: true,
+        shift: true,
+        key: "H".into(),
+    });
+    assert!(!mddskmgr::hotkeys::has_duplicates(&hk));
+    hk.panic_hide = Some(hk.toggle_overlay.clone());
+    assert!(mddskmgr::hotkeys::has_duplicates(&hk));
+}
+
+#[test]
+fn peek_chord_is_checked_for_duplicates() {
+    let mut hk = mddskmgr::config::Config::default().hotkeys;
+    hk.peek = Some(KeyChord {
+        ctrl: true,
+        alt: true,
+        shift: false,
+        key: "W".into(),
+    });
+    assert!(!mddskmgr::hotkeys::has_duplicates(&hk));
+    hk.panic_hide = hk.peek.clone();
+    assert!(mddskmgr::hotkeys::has_duplicates(&hk));
+}
@@ -0,0 +1,50 @@
+use mddskmgr::hotkeys::rehook_summary;
+
+#[test]
+fn summary_lists_successes_and_failures() {
+    assert_eq!(
+        rehook_summary(&[("Edit Title", true), ("Toggle Overlay", true)]),
+        "All hotkeys registered: Edit Title, Toggle Overlay"
+    );
+    assert_eq!(
+        rehook_summary(&[("Edit Title", true), ("Snap Position", false)]),
+        "Registered: Edit Title. Failed: Snap Position"
+    );
+    assert_eq!(
+        rehook_summary(&[("Edit Title", false)]),
+        "No hotkeys registered. Failed: Edit Title"
+    );
+}
+
+#[cfg(not(windows))]
+#[test]
+fn register_all_covers_the_configured_chords() {
+    use mddskmgr::config::{Config, Hotkeys, KeyChord};
+    use mddskmgr::hotkeys::{RegisterFailure, register_all};
+    let mut hk = Config::default().hotkeys;
+    let names =
+        |hk: &Hotkeys| -> Vec<&str> { register_all((), hk).iter().map(|r| r.name).collect() };
+    assert_eq!(
+        names(&hk),
+        [
+            "Edit Title",
+            "Edit Description",
+            "Toggle Overlay",
+            "Snap Position",
+            "Edit Both"
+        ]
+    );
+    hk.panic_hide = Some(KeyChord {
+        ctrl: true,
+        alt: true,
+        shift: false,
+        key: "Bogus".into(),
+    });
+    let results = register_all((), &hk);
+    assert_eq!(results.last().unwrap().name, "Panic Hide");
+    assert_eq!(
+        results.last().unwrap().result,
+        Err(RegisterFailure::UnknownKey)
+    );
+    assert!(results[..results.len() - 1].iter().all(|r| r.ok()));
+}
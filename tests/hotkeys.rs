@@ -1,15 +1,179 @@
-use mddskmgr::hotkeys::vk_from_char;
+use mddskmgr::config::{Config, KeyChord};
+use mddskmgr::hotkeys::{format_chord, hint_text, vk_from_str};
 
 #[test]
-fn maps_alpha_keys_to_vk() {
-    let t = vk_from_char("t");
-    assert_eq!(t.0, 'T' as u32);
-    let d = vk_from_char("D");
-    assert_eq!(d.0, 'D' as u32);
+fn formats_chords_for_display() {
+    let chord = KeyChord {
+        ctrl: true,
+        alt: false,
+        shift: true,
+        key: "f5".into(),
+    };
+    assert_eq!(format_chord(&chord), "Ctrl+Shift+F5");
+    let mut hk = Config::default().hotkeys;
+    assert_eq!(hint_text(&hk), "(Ctrl+Alt+T, Ctrl+Alt+D)");
+    hk.edit_title.key = "n".into();
+    hk.edit_title.shift = true;
+    assert_eq!(hint_text(&hk), "(Ctrl+Alt+Shift+N, Ctrl+Alt+D)");
 }
 
 #[test]
-fn default_key_when_empty() {
-    let b = vk_from_char("");
-    assert_eq!(b.0, 'B' as u32);
+fn parses_named_keys() {
+    assert_eq!(vk_from_str("t"), Some(b'T' as u16));
+    assert_eq!(vk_from_str("D"), Some(b'D' as u16));
+    assert_eq!(vk_from_str("7"), Some(b'7' as u16));
+    assert_eq!(vk_from_str("F1"), Some(0x70));
+    assert_eq!(vk_from_str("f24"), Some(0x87));
+    assert_eq!(vk_from_str("Left"), Some(0x25));
+    assert_eq!(vk_from_str("UP"), Some(0x26));
+    assert_eq!(vk_from_str("right"), Some(0x27));
+    assert_eq!(vk_from_str("Down"), Some(0x28));
+    assert_eq!(vk_from_str(" Space "), Some(0x20));
+    assert_eq!(vk_from_str("Esc"), Some(0x1B));
+    assert_eq!(vk_from_str("escape"), Some(0x1B));
+    assert_eq!(vk_from_str("Num0"), Some(0x60));
+    assert_eq!(vk_from_str("numpad9"), Some(0x69));
+    assert_eq!(vk_from_str("PgDn"), Some(0x22));
+}
+
+#[test]
+fn rejects_unknown_keys() {
+    for key in ["", "F0", "F25", "Num10", "Spacebar", "é", "Ctrl", "-"] {
+        assert_eq!(vk_from_str(key), None, "{key:?}");
+    }
+}
+
+#[cfg(not(windows))]
+#[test]
+fn register_reports_unknown_keys() {
+    assert!(!mddskmgr::hotkeys::register((), true, true, false, "Bogus", 1).unwrap());
+    assert!(mddskmgr::hotkeys::register((), true, true, false, "F3", 1).unwrap());
+}
+
+fn chord(ctrl: bool, alt: bool, shift: bool, key: &str) -> KeyChord {
+    KeyChord {
+        ctrl,
+        alt,
+        shift,
+        key: key.into(),
+    }
+}
+
+#[test]
+fn flags_probably_reserved_chords() {
+    use mddskmgr::hotkeys::is_probably_reserved;
+    for c in [
+        chord(true, true, false, "Delete"),
+        chord(true, true, false, "del"),
+        chord(true, false, true, "Esc"),
+        chord(true, false, false, "Escape"),
+        chord(false, true, false, "Tab"),
+        chord(false, true, true, "tab"),
+        chord(false, true, false, "Esc"),
+        chord(true, true, false, "F12"),
+    ] {
+        assert!(is_probably_reserved(&c), "{c:?}");
+    }
+    for c in [
+        chord(true, true, false, "T"),
+        chord(true, false, false, "Delete"),
+        chord(true, false, false, "Tab"),
+        chord(true, true, false, "F11"),
+    ] {
+        assert!(!is_probably_reserved(&c), "{c:?}");
+    }
+    let hk = Config::default().hotkeys;
+    for c in [&hk.edit_title, &hk.edit_description, &hk.toggle_overlay] {
+        assert!(!is_probably_reserved(c), "default {c:?}");
+    }
+}
+
+#[test]
+fn failure_summary_names_each_failing_chord() {
+    use mddskmgr::hotkeys::{RegisterFailure, Registration, failure_summary};
+    let ok = Registration {
+        name: "Toggle Overlay",
+        chord: chord(true, true, false, "O"),
+        result: Ok(()),
+    };
+    assert_eq!(failure_summary(std::slice::from_ref(&ok)), None);
+    let results = [
+        Registration {
+            name: "Edit Title",
+            chord: chord(true, true, false, "t"),
+            result: Err(RegisterFailure::InUse),
+        },
+        ok,
+        Registration {
+            name: "Snap Position",
+            chord: chord(true, true, false, "Delete"),
+            result: Err(RegisterFailure::Reserved),
+        },
+        Registration {
+            name: "Edit Both",
+            chord: chord(true, true, false, "Bogus"),
+            result: Err(RegisterFailure::UnknownKey),
+        },
+    ];
+    assert_eq!(
+        failure_summary(&results).unwrap(),
+        "Edit Title (Ctrl+Alt+T) failed; Snap Position (Ctrl+Alt+Delete) is reserved by Windows; \
+         Edit Both (Ctrl+Alt+Bogus) has an unknown key"
+    );
+}
+
+#[cfg(not(windows))]
+#[test]
+fn register_chord_reports_why() {
+    use mddskmgr::hotkeys::{RegisterFailure, register_chord};
+    let r = register_chord((), "Edit Title", &chord(true, true, false, "T"), 1);
+    assert!(r.ok());
+    assert_eq!(r.name, "Edit Title");
+    let r = register_chord((), "Edit Title", &chord(true, true, false, "Bogus"), 1);
+    assert_eq!(r.result, Err(RegisterFailure::UnknownKey));
+    let r = register_chord((), "Edit Title", &chord(false, true, false, "Tab"), 1);
+    assert_eq!(r.result, Err(RegisterFailure::Reserved));
+}
+
+#[test]
+fn captured_keys_round_trip_through_config_names() {
+    use mddskmgr::hotkeys::key_name;
+    for vk in (0u16..=0xFF).filter_map(|vk| key_name(vk).map(|name| (vk, name))) {
+        assert_eq!(vk_from_str(&vk.1), Some(vk.0), "{}", vk.1);
+    }
+    assert_eq!(key_name(0x71).as_deref(), Some("F2"));
+    assert_eq!(key_name(0x63).as_deref(), Some("Num3"));
+    // Modifiers and unnamed keys (Ctrl, Shift, Alt, Win, OEM punctuation) can't be bound.
+    for vk in [0x10, 0x11, 0x12, 0x5B, 0xBA] {
+        assert_eq!(key_name(vk), None, "{vk:#x}");
+    }
+}
+
+#[test]
+fn captured_chord_needs_a_modifier() {
+    use mddskmgr::hotkeys::captured_chord;
+    assert_eq!(captured_chord(b'K' as u16, false, false, false), None);
+    assert_eq!(captured_chord(0x11, true, false, false), None);
+    assert_eq!(
+        captured_chord(b'K' as u16, true, false, true),
+        Some(chord(true, false, true, "K"))
+    );
+    assert_eq!(
+        captured_chord(0x25, false, true, false),
+        Some(chord(false, true, false, "Left"))
+    );
+}
+
+#[test]
+fn rebind_actions_point_at_their_chords() {
+    use mddskmgr::hotkeys::RebindAction;
+    let mut hk = Config::default().hotkeys;
+    for action in RebindAction::ALL {
+        *action.chord_mut(&mut hk) = chord(true, true, true, action.name());
+    }
+    assert_eq!(hk.edit_title.key, "Edit Title");
+    assert_eq!(hk.edit_description.key, "Edit Description");
+    assert_eq!(hk.toggle_overlay.key, "Toggle Overlay");
+    assert_eq!(hk.snap_position.key, "Snap Position");
+    assert_eq!(RebindAction::SnapPosition.chord(&hk), &hk.snap_position);
 }
@@ -0,0 +1,107 @@
+use mddskmgr::config::{Config, DesktopLabel, export_theme, import_theme};
+use pretty_assertions::assert_eq;
+use std::fs;
+
+#[test]
+fn export_theme_omits_desktop_labels() {
+    let mut cfg = Config::default();
+    cfg.desktops.insert(
+        "guid-1".into(),
+        DesktopLabel {
+            title: "Secret".into(),
+            description: "Private".into(),
+            color: None,
+            hidden: false,
+            font_size_dip: None,
+        },
+    );
+    cfg.appearance.font_family = "Consolas".into();
+
+    let td = tempfile::tempdir().expect("tmpdir");
+    let path = td.path().join("my.theme.json");
+    export_theme(&cfg, &path).expect("export");
+    let data = fs::read_to_string(&path).expect("read theme");
+    assert!(data.contains("Consolas"));
+    assert!(!data.contains("Secret"));
+    assert!(!data.contains("desktops"));
+}
+
+#[test]
+fn import_theme_merges_appearance_and_reports_unknown_keys() {
+    let td = tempfile::tempdir().expect("tmpdir");
+    let path = td.path().join("in.theme.json");
+    fs::write(
+        &path,
+        r#"{ "appearance": { "font_size_dip": 22, "sparkles": true } }"#,
+    )
+    .unwrap();
+
+    let mut cfg = Config::default();
+    cfg.desktops
+        .insert("guid-1".into(), DesktopLabel::default());
+    let ignored = import_theme(&mut cfg, &path).expect("import");
+    assert_eq!(ignored, vec!["sparkles".to_string()]);
+    assert_eq!(cfg.appearance.font_size_dip, 22);
+    // Untouched fields keep their current values.
+    assert_eq!(cfg.appearance.font_family, "Segoe UI");
+    assert!(cfg.desktops.contains_key("guid-1"));
+}
+
+#[test]
+fn import_theme_rejects_invalid_field_types() {
+    let td = tempfile::tempdir().expect("tmpdir");
+    let path = td.path().join("bad.theme.json");
+    fs::write(&path, r#"{ "font_size_dip": "huge" }"#).unwrap();
+
+    let mut cfg = Config::default();
+    assert!(import_theme(&mut cfg, &path).is_err());
+    assert_eq!(cfg.appearance.font_size_dip, 16);
+}
+
+#[test]
+fn themes_never_carry_label_keying() {
+    let mut cfg = Config::default();
+    cfg.appearance.key_by_index = true;
+    let td = tempfile::tempdir().expect("tmpdir");
+    let path = td.path().join("my.theme.json");
+    export_theme(&cfg, &path).expect("export");
+    assert!(!fs::read_to_string(&path).unwrap().contains("key_by_index"));
+
+    // A hand-written theme that sets it is imported without it.
+    fs::write(
+        &path,
+        r#"{ "appearance": { "key_by_index": true, "font_size_dip": 20 } }"#,
+    )
+    .unwrap();
+    let mut cfg = Config::default();
+    let ignored = import_theme(&mut cfg, &path).expect("import");
+    assert!(ignored.is_empty());
+    assert!(!cfg.appearance.key_by_index);
+    assert_eq!(cfg.appearance.font_size_dip, 20);
+}
+
+#[test]
+fn themes_leave_out_the_app_list_and_local_icon_path() {
+    let mut cfg = Config::default();
+    cfg.appearance.hide_for_processes = vec!["secret-client.exe".into()];
+    cfg.appearance.tray_icon_path = Some("C:\\Users\\me\\icon.ico".into());
+    let td = tempfile::tempdir().expect("tmpdir");
+    let path = td.path().join("my.theme.json");
+    export_theme(&cfg, &path).expect("export");
+    let data = fs::read_to_string(&path).unwrap();
+    assert!(!data.contains("secret-client"));
+    assert!(!data.contains("icon.ico"));
+
+    fs::write(
+        &path,
+        r#"{ "hide_for_processes": ["obs64.exe"], "tray_icon_path": "x.ico" }"#,
+    )
+    .unwrap();
+    let ignored = import_theme(&mut cfg, &path).expect("import");
+    assert!(ignored.is_empty());
+    assert_eq!(cfg.appearance.hide_for_processes, ["secret-client.exe"]);
+    assert_eq!(
+        cfg.appearance.tray_icon_path.as_deref(),
+        Some("C:\\Users\\me\\icon.ico")
+    );
+}
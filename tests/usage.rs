@@ -0,0 +1,41 @@
+use mddskmgr::usage::{DesktopUsage, Usage, usage_path};
+use std::fs;
+
+#[test]
+fn switches_are_counted_per_desktop_and_survive_a_save() {
+    let td = tempfile::tempdir().expect("tmpdir");
+    let path = usage_path(td.path());
+    assert_eq!(path.file_name().unwrap(), "usage.json");
+    // Missing file: nothing recorded yet.
+    let mut usage = Usage::load(&path);
+    assert_eq!(usage, Usage::default());
+    assert!(!usage.is_dirty());
+
+    usage.record_switch("{A}", 100);
+    usage.record_switch("{B}", 200);
+    usage.record_switch("{A}", 300);
+    assert!(usage.is_dirty());
+    assert_eq!(usage.visits("{A}"), 2);
+    assert_eq!(usage.visits("{C}"), 0);
+
+    usage.save(&path).unwrap();
+    assert!(!usage.is_dirty());
+    let back = Usage::load(&path);
+    assert_eq!(
+        back.desktops["{A}"],
+        DesktopUsage {
+            switches: 2,
+            last_visited: 300
+        }
+    );
+    assert_eq!(back.visits("{B}"), 1);
+    assert!(!td.path().join("usage.json.tmp").exists());
+}
+
+#[test]
+fn unreadable_stats_start_over() {
+    let td = tempfile::tempdir().expect("tmpdir");
+    let path = usage_path(td.path());
+    fs::write(&path, "{ not json").unwrap();
+    assert_eq!(Usage::load(&path), Usage::default());
+}
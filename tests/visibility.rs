@@ -1,13 +1,289 @@
-use mddskmgr::core::should_show;
+use mddskmgr::config::{Config, DesktopLabel};
+use mddskmgr::core::{
+    HideReason, VisibilityState, auto_hide_allows, desktop_hidden, is_excluded_process,
+    is_fullscreen, should_show,
+};
 
 #[test]
 fn visibility_truth_table() {
-    // toggled, hc, fs -> show?
-    assert!(should_show(true, false, false));
-    assert!(!should_show(true, true, false));
-    assert!(!should_show(true, false, true));
-    assert!(!should_show(true, true, true));
-    assert!(!should_show(false, false, false));
-    assert!(!should_show(false, true, false));
-    assert!(!should_show(false, false, true));
+    // toggled, hc, fs, desktop hidden, excluded app -> show?
+    assert!(should_show(true, false, false, false, false));
+    assert!(!should_show(true, true, false, false, false));
+    assert!(!should_show(true, false, true, false, false));
+    assert!(!should_show(true, true, true, false, false));
+    assert!(!should_show(false, false, false, false, false));
+    assert!(!should_show(false, true, false, false, false));
+    assert!(!should_show(false, false, true, false, false));
+    // A hidden desktop stays hidden even with the global toggle on.
+    assert!(!should_show(true, false, false, true, false));
+    assert!(!should_show(false, false, false, true, false));
+    // So does an excluded foreground app.
+    assert!(!should_show(true, false, false, false, true));
+}
+
+#[test]
+fn auto_hide_truth_table() {
+    // auto_hide, shown_by_switch, manual_override -> allowed?
+    assert!(auto_hide_allows(false, false, false));
+    assert!(auto_hide_allows(true, true, false));
+    assert!(auto_hide_allows(true, false, true));
+    assert!(!auto_hide_allows(true, false, false));
+    // Combined with the toggle, auto-hide only ever removes visibility.
+    let combined =
+        |toggled: bool, allowed: bool| should_show(toggled && allowed, false, false, false, false);
+    assert!(combined(true, auto_hide_allows(true, true, false)));
+    assert!(!combined(true, auto_hide_allows(true, false, false)));
+    assert!(!combined(false, auto_hide_allows(true, true, false)));
+}
+
+#[test]
+fn per_desktop_hidden_flag() {
+    let mut cfg = Config::default();
+    cfg.desktops.insert(
+        "games".into(),
+        DesktopLabel {
+            title: "Games".into(),
+            hidden: true,
+            ..Default::default()
+        },
+    );
+    cfg.desktops.insert("work".into(), DesktopLabel::default());
+    assert!(desktop_hidden(&cfg, "games"));
+    assert!(!desktop_hidden(&cfg, "work"));
+    assert!(!desktop_hidden(&cfg, "unlabeled"));
+    // Round-trips, and the flag is omitted from the file when false.
+    let json = serde_json::to_string(&cfg).unwrap();
+    assert_eq!(json.matches("\"hidden\"").count(), 1);
+    let back: Config = serde_json::from_str(&json).unwrap();
+    assert!(desktop_hidden(&back, "games"));
+}
+
+#[test]
+fn excluded_processes_match_base_name_case_insensitively() {
+    let list = vec![
+        "obs64.exe".to_string(),
+        "C:\\Windows\\System32\\MSTSC.EXE".to_string(),
+    ];
+    assert!(is_excluded_process(
+        "C:\\Program Files\\obs-studio\\bin\\64bit\\OBS64.exe",
+        &list
+    ));
+    assert!(is_excluded_process("D:\\portable\\mstsc.exe", &list));
+    assert!(!is_excluded_process("C:\\Windows\\notepad.exe", &list));
+    assert!(!is_excluded_process("C:\\tools\\obs64.exe.bak", &list));
+    assert!(!is_excluded_process("", &list));
+    assert!(!is_excluded_process("C:\\x\\obs64.exe", &[]));
+}
+
+#[test]
+fn fullscreen_needs_whole_monitor_and_no_frame() {
+    // Secondary monitor to the right of a 1920x1080 primary.
+    let monitor = (1920, 0, 4480, 1440);
+    // Borderless game covering the whole monitor, taskbar area included.
+    assert!(is_fullscreen((1920, 0, 4480, 1440), monitor, false));
+    // Small overhangs (within 2px) still count.
+    assert!(is_fullscreen((1918, -2, 4482, 1442), monitor, false));
+    // Maximized normal window: has a caption/frame.
+    assert!(!is_fullscreen((1912, -8, 4488, 1448), monitor, true));
+    // Covers only the work area (taskbar visible).
+    assert!(!is_fullscreen((1920, 0, 4480, 1392), monitor, false));
+    // Fullscreen on the primary, judged against the secondary.
+    assert!(!is_fullscreen((0, 0, 1920, 1080), monitor, false));
+}
+
+/// Every combination of the thirteen inputs.
+fn all_states() -> impl Iterator<Item = VisibilityState> {
+    (0u32..1 << 13).map(|bits| {
+        let b = |i: u32| bits & (1 << i) != 0;
+        VisibilityState {
+            toggled_on: b(0),
+            auto_hide: b(1),
+            shown_by_switch: b(2),
+            manual_override: b(3),
+            high_contrast: b(4),
+            fullscreen: b(5),
+            excluded_app: b(6),
+            desktop_hidden: b(7),
+            display_off: b(8),
+            remote_disconnected: b(9),
+            always_show: b(10),
+            panic_hidden: b(11),
+            peeking: b(12),
+        }
+    })
+}
+
+#[test]
+fn visibility_state_shows_only_when_nothing_hides() {
+    for s in all_states() {
+        let expected = !s.panic_hidden
+            && (s.peeking || s.toggled_on)
+            && (s.peeking || auto_hide_allows(s.auto_hide, s.shown_by_switch, s.manual_override))
+            && !s.high_contrast
+            && (s.always_show || s.peeking || !s.fullscreen)
+            && (s.always_show || s.peeking || !s.excluded_app)
+            && (s.peeking || !s.desktop_hidden)
+            && !s.display_off
+            && !s.remote_disconnected;
+        assert_eq!(s.should_show(), expected, "{s:?}");
+        assert_eq!(s.hide_reason().is_none(), expected, "{s:?}");
+    }
+}
+
+#[test]
+fn each_hide_reason_dominates_the_ones_after_it() {
+    let applies = |s: &VisibilityState, r: HideReason| match r {
+        HideReason::PanicHidden => s.panic_hidden,
+        HideReason::DisplayOff => s.display_off,
+        HideReason::RemoteDisconnected => s.remote_disconnected,
+        HideReason::DesktopHidden => s.desktop_hidden && !s.peeking,
+        HideReason::HighContrast => s.high_contrast,
+        HideReason::Fullscreen => s.fullscreen && !s.always_show && !s.peeking,
+        HideReason::ExcludedApp => s.excluded_app && !s.always_show && !s.peeking,
+        HideReason::ToggledOff => !s.toggled_on && !s.peeking,
+        HideReason::AutoHidden => {
+            !auto_hide_allows(s.auto_hide, s.shown_by_switch, s.manual_override) && !s.peeking
+        }
+    };
+    let order = [
+        HideReason::PanicHidden,
+        HideReason::DisplayOff,
+        HideReason::RemoteDisconnected,
+        HideReason::DesktopHidden,
+        HideReason::HighContrast,
+        HideReason::Fullscreen,
+        HideReason::ExcludedApp,
+        HideReason::ToggledOff,
+        HideReason::AutoHidden,
+    ];
+    for s in all_states() {
+        // The reported reason is the first applicable one in precedence order.
+        let first = order.iter().copied().find(|&r| applies(&s, r));
+        assert_eq!(s.hide_reason(), first, "{s:?}");
+    }
+    // Spot checks: suspension beats everything, the desktop flag beats the user's toggle.
+    let everything = VisibilityState {
+        toggled_on: false,
+        auto_hide: true,
+        high_contrast: true,
+        fullscreen: true,
+        excluded_app: true,
+        desktop_hidden: true,
+        display_off: true,
+        remote_disconnected: true,
+        ..Default::default()
+    };
+    assert_eq!(everything.hide_reason(), Some(HideReason::DisplayOff));
+    let hidden_desktop = VisibilityState {
+        toggled_on: false,
+        desktop_hidden: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        hidden_desktop.hide_reason(),
+        Some(HideReason::DesktopHidden)
+    );
+}
+
+#[test]
+fn always_show_overrides_only_fullscreen_and_excluded_apps() {
+    let over_game = VisibilityState {
+        toggled_on: true,
+        fullscreen: true,
+        excluded_app: true,
+        always_show: true,
+        ..Default::default()
+    };
+    assert!(over_game.should_show());
+    // The user's toggle, accessibility/lock and the desktop flag still hide it.
+    for s in [
+        VisibilityState {
+            toggled_on: false,
+            ..over_game
+        },
+        VisibilityState {
+            high_contrast: true,
+            ..over_game
+        },
+        VisibilityState {
+            desktop_hidden: true,
+            ..over_game
+        },
+    ] {
+        assert!(!s.should_show(), "{s:?}");
+    }
+    assert_eq!(
+        VisibilityState {
+            always_show: false,
+            ..over_game
+        }
+        .hide_reason(),
+        Some(HideReason::Fullscreen)
+    );
+}
+
+#[test]
+fn panic_hide_beats_always_show_and_switch_announcements() {
+    let shown = VisibilityState {
+        toggled_on: true,
+        auto_hide: true,
+        shown_by_switch: true,
+        fullscreen: true,
+        always_show: true,
+        ..Default::default()
+    };
+    assert!(shown.should_show());
+    let panicked = VisibilityState {
+        panic_hidden: true,
+        display_off: true,
+        ..shown
+    };
+    assert_eq!(panicked.hide_reason(), Some(HideReason::PanicHidden));
+}
+
+#[test]
+fn peek_shows_over_user_and_foreground_hiding_only() {
+    let hidden = VisibilityState {
+        toggled_on: false,
+        auto_hide: true,
+        fullscreen: true,
+        excluded_app: true,
+        desktop_hidden: true,
+        ..Default::default()
+    };
+    assert_eq!(hidden.hide_reason(), Some(HideReason::DesktopHidden));
+    let peek = VisibilityState {
+        peeking: true,
+        ..hidden
+    };
+    assert!(peek.should_show());
+    // Ending the peek restores exactly the previous state.
+    assert_eq!(
+        VisibilityState {
+            peeking: false,
+            ..peek
+        },
+        hidden
+    );
+    // The panic hide, system state and accessibility still win.
+    for s in [
+        VisibilityState {
+            panic_hidden: true,
+            ..peek
+        },
+        VisibilityState {
+            display_off: true,
+            ..peek
+        },
+        VisibilityState {
+            remote_disconnected: true,
+            ..peek
+        },
+        VisibilityState {
+            high_contrast: true,
+            ..peek
+        },
+    ] {
+        assert!(!s.should_show(), "{s:?}");
+    }
 }